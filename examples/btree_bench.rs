@@ -0,0 +1,66 @@
+extern crate adivon;
+extern crate rand;
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use adivon::btree::{BPlusTree, BTree};
+use adivon::rbtree::RedBlackBST;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+const N: usize = 200_000;
+const SCANS: usize = 2_000;
+const SCAN_LEN: u64 = 1_000;
+
+fn time<F: FnOnce() -> u64>(f: F) -> (Duration, u64) {
+    let start = Instant::now();
+    let checksum = f();
+    (start.elapsed(), checksum)
+}
+
+macro_rules! bench {
+    ($name:expr, $make:expr, $keys:expr, $lows:expr) => {{
+        let keys: &[u64] = $keys;
+        let lows: &[u64] = $lows;
+        let mut t = $make;
+        let (insert, _) = time(|| {
+            for &k in keys {
+                t.insert(k, k);
+            }
+            0
+        });
+        let (get, found) = time(|| keys.iter().filter_map(|k| t.get(k)).sum());
+        let (scan, scanned) = time(|| {
+            lows.iter()
+                .map(|&lo| t.range(lo..lo + SCAN_LEN).map(|(_, v)| v).sum::<u64>())
+                .sum()
+        });
+        let (remove, removed) = time(|| keys.iter().filter_map(|k| t.remove(k)).sum());
+        assert_eq!(found, removed);
+        println!(
+            "{:<20} insert {:>9.2?}  get {:>9.2?}  range {:>9.2?}  remove {:>9.2?}  ({})",
+            $name, insert, get, scan, remove, scanned
+        );
+    }};
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut keys = (0..N).map(|_| rng.gen_range(0, 1 << 32)).collect::<Vec<u64>>();
+    keys.sort_unstable();
+    keys.dedup();
+    keys.shuffle(&mut rng);
+    let lows = (0..SCANS).map(|_| rng.gen_range(0, 1 << 32)).collect::<Vec<u64>>();
+
+    println!("{} distinct random keys, {} range scans of width {}", keys.len(), SCANS, SCAN_LEN);
+    bench!("RedBlackBST", RedBlackBST::new(), &keys, &lows);
+    for &fanout in [4, 16, 64, 256].iter() {
+        bench!(format!("BTree({})", fanout), BTree::with_fanout(fanout), &keys, &lows);
+    }
+    for &fanout in [4, 16, 64, 256].iter() {
+        bench!(format!("BPlusTree({})", fanout), BPlusTree::with_fanout(fanout), &keys, &lows);
+    }
+    bench!("std BTreeMap", BTreeMap::new(), &keys, &lows);
+}
@@ -0,0 +1,543 @@
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+use std::iter::FromIterator;
+use std::ops::Index;
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+pub type NodeCell<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Clone)]
+pub struct Node<K, V> {
+    pub key: K,
+    pub val: V,
+    pub left: NodeCell<K, V>,
+    pub right: NodeCell<K, V>,
+    // height of the subtree, 1 for a leaf
+    height: usize,
+    // number of nodes in the subtree
+    n: usize,
+}
+
+impl<K, V> Node<K, V> {
+    #[inline]
+    pub fn new(key: K, val: V) -> Node<K, V> {
+        Node {
+            key,
+            val,
+            left: None,
+            right: None,
+            height: 1,
+            n: 1,
+        }
+    }
+
+    #[inline]
+    fn update(&mut self) {
+        self.height = 1 + cmp::max(height(&self.left), height(&self.right));
+        self.n = 1 + size(&self.left) + size(&self.right);
+    }
+
+    // height of the left subtree minus height of the right subtree
+    #[inline]
+    fn balance_factor(&self) -> isize {
+        height(&self.left) as isize - height(&self.right) as isize
+    }
+}
+
+#[inline]
+fn height<K, V>(x: &NodeCell<K, V>) -> usize {
+    x.as_ref().map_or(0, |n| n.height)
+}
+
+#[inline]
+fn size<K, V>(x: &NodeCell<K, V>) -> usize {
+    x.as_ref().map_or(0, |n| n.n)
+}
+
+fn rotate_left<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut x = h.right.take().expect("rotate left needs a right child");
+    h.right = x.left.take();
+    h.update();
+    x.left = Some(h);
+    x.update();
+    x
+}
+
+fn rotate_right<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut x = h.left.take().expect("rotate right needs a left child");
+    h.left = x.right.take();
+    h.update();
+    x.right = Some(h);
+    x.update();
+    x
+}
+
+// restore the AVL property at h, whose subtrees differ in height by at most 2
+fn balance<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    h.update();
+    if h.balance_factor() > 1 {
+        if h.left.as_ref().unwrap().balance_factor() < 0 {
+            h.left = h.left.take().map(rotate_left);
+        }
+        rotate_right(h)
+    } else if h.balance_factor() < -1 {
+        if h.right.as_ref().unwrap().balance_factor() > 0 {
+            h.right = h.right.take().map(rotate_right);
+        }
+        rotate_left(h)
+    } else {
+        h
+    }
+}
+
+fn put<K: PartialOrd, V>(x: NodeCell<K, V>, key: K, val: V, old: &mut Option<V>) -> Box<Node<K, V>> {
+    let mut h = match x {
+        None => return Box::new(Node::new(key, val)),
+        Some(h) => h,
+    };
+    match key.partial_cmp(&h.key).unwrap() {
+        Ordering::Less => h.left = Some(put(h.left.take(), key, val, old)),
+        Ordering::Greater => h.right = Some(put(h.right.take(), key, val, old)),
+        Ordering::Equal => {
+            *old = Some(std::mem::replace(&mut h.val, val));
+            return h;
+        }
+    }
+    balance(h)
+}
+
+// remove the smallest node under h, returns the rest and the node
+fn delete_min<K, V>(mut h: Box<Node<K, V>>) -> (NodeCell<K, V>, Box<Node<K, V>>) {
+    match h.left.take() {
+        None => (h.right.take(), h),
+        Some(left) => {
+            let (left, min) = delete_min(left);
+            h.left = left;
+            (Some(balance(h)), min)
+        }
+    }
+}
+
+// remove the largest node under h, returns the rest and the node
+fn delete_max<K, V>(mut h: Box<Node<K, V>>) -> (NodeCell<K, V>, Box<Node<K, V>>) {
+    match h.right.take() {
+        None => (h.left.take(), h),
+        Some(right) => {
+            let (right, max) = delete_max(right);
+            h.right = right;
+            (Some(balance(h)), max)
+        }
+    }
+}
+
+fn delete<K, Q, V>(x: NodeCell<K, V>, key: &Q) -> (NodeCell<K, V>, NodeCell<K, V>)
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    let mut h = match x {
+        None => return (None, None),
+        Some(h) => h,
+    };
+    let deleted = match key.partial_cmp(h.key.borrow()).unwrap() {
+        Ordering::Less => {
+            let (left, deleted) = delete(h.left.take(), key);
+            h.left = left;
+            deleted
+        }
+        Ordering::Greater => {
+            let (right, deleted) = delete(h.right.take(), key);
+            h.right = right;
+            deleted
+        }
+        Ordering::Equal => {
+            // replace h with its successor
+            let (left, right) = (h.left.take(), h.right.take());
+            return match right {
+                None => (left, Some(h)),
+                Some(right) => {
+                    let (right, mut min) = delete_min(right);
+                    min.left = left;
+                    min.right = right;
+                    (Some(balance(min)), Some(h))
+                }
+            };
+        }
+    };
+    (Some(balance(h)), deleted)
+}
+
+fn find<'a, K, Q, V>(mut x: Option<&'a Node<K, V>>, key: &Q) -> Option<&'a Node<K, V>>
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    while let Some(node) = x {
+        match key.partial_cmp(node.key.borrow()).unwrap() {
+            Ordering::Less => x = node.left.as_deref(),
+            Ordering::Greater => x = node.right.as_deref(),
+            Ordering::Equal => return Some(node),
+        }
+    }
+    None
+}
+
+/// A symbol table implemented using an AVL tree.
+///
+/// The heights of the two subtrees of every node differ by at most one, so
+/// the tree is at most about 1.44 lg n high, a bit lower than a red-black
+/// tree, which favours lookups over updates. Every node also counts its
+/// subtree, for rank and select in O(log n).
+#[derive(Clone)]
+pub struct AVLTree<K, V> {
+    pub root: NodeCell<K, V>,
+}
+
+impl<K: PartialOrd, V> Default for AVLTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd, V> AVLTree<K, V> {
+    pub fn new() -> AVLTree<K, V> {
+        AVLTree { root: None }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        find(self.root.as_deref(), key).map(|n| &n.val)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref_mut();
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref_mut(),
+                Ordering::Greater => x = node.right.as_deref_mut(),
+                Ordering::Equal => return Some(&mut node.val),
+            }
+        }
+        None
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        self.insert(key, val);
+    }
+
+    /// insert a key-value pair, returns the value the key had before
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let mut old = None;
+        self.root = Some(put(self.root.take(), key, val, &mut old));
+        old
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove(key);
+    }
+
+    /// remove a key, returns its value if it was in the table
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (root, deleted) = delete(self.root.take(), key);
+        self.root = root;
+        deleted.map(|n| n.val)
+    }
+
+    /// remove and return the entry with the smallest key
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let (root, min) = delete_min(self.root.take()?);
+        self.root = root;
+        Some((min.key, min.val))
+    }
+
+    /// remove and return the entry with the largest key
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let (root, max) = delete_max(self.root.take()?);
+        self.root = root;
+        Some((max.key, max.val))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    /// smallest key
+    pub fn min(&self) -> Option<&K> {
+        let mut x = self.root.as_deref()?;
+        while let Some(left) = x.left.as_deref() {
+            x = left;
+        }
+        Some(&x.key)
+    }
+
+    /// largest key
+    pub fn max(&self) -> Option<&K> {
+        let mut x = self.root.as_deref()?;
+        while let Some(right) = x.right.as_deref() {
+            x = right;
+        }
+        Some(&x.key)
+    }
+
+    /// largest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (mut x, mut best) = (self.root.as_deref(), None);
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    best = Some(&node.key);
+                    x = node.right.as_deref();
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        best
+    }
+
+    /// smallest key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (mut x, mut best) = (self.root.as_deref(), None);
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => {
+                    best = Some(&node.key);
+                    x = node.left.as_deref();
+                }
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        best
+    }
+
+    /// number of keys less than key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (mut x, mut r) = (self.root.as_deref(), 0);
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    r += 1 + size(&node.left);
+                    x = node.right.as_deref();
+                }
+                Ordering::Equal => return r + size(&node.left),
+            }
+        }
+        r
+    }
+
+    /// key of rank k
+    pub fn select(&self, k: usize) -> Option<&K> {
+        let (mut x, mut k) = (self.root.as_deref(), k);
+        while let Some(node) = x {
+            let t = size(&node.left);
+            match k.cmp(&t) {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    x = node.right.as_deref();
+                    k -= t + 1;
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        None
+    }
+
+    /// iterator over the entries, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            len: self.size(),
+        };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// check the AVL invariants, naming the first one violated
+    pub fn check(&self) -> Result<(), &'static str> {
+        fn check_node<K: PartialOrd, V>(
+            x: &NodeCell<K, V>,
+            lo: Option<&K>,
+            hi: Option<&K>,
+        ) -> Result<(), &'static str> {
+            let node = match x {
+                None => return Ok(()),
+                Some(node) => node,
+            };
+            if lo.is_some_and(|lo| node.key <= *lo) || hi.is_some_and(|hi| node.key >= *hi) {
+                return Err("not in symmetric order");
+            }
+            if node.height != 1 + cmp::max(height(&node.left), height(&node.right)) {
+                return Err("height not consistent");
+            }
+            if node.n != 1 + size(&node.left) + size(&node.right) {
+                return Err("subtree counts not consistent");
+            }
+            if node.balance_factor().abs() > 1 {
+                return Err("not balanced");
+            }
+            check_node(&node.left, lo, Some(&node.key))?;
+            check_node(&node.right, Some(&node.key), hi)
+        }
+
+        check_node(&self.root, None, None)
+    }
+}
+
+impl<K: PartialOrd, V> FromIterator<(K, V)> for AVLTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut t = AVLTree::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<K: PartialOrd, V> Extend<(K, V)> for AVLTree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+impl<K, Q, V> Index<&Q> for AVLTree<K, V>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    fn index(&self, index: &Q) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+/// Iterator over the entries of an `AVLTree` in key order.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.stack.push(node);
+            x = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K: PartialOrd, V> IntoIterator for &'a AVLTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_avl_tree() {
+    // ascending inserts are the worst case for a plain BST
+    let mut t = (0..1000).map(|i| (i, i * i)).collect::<AVLTree<_, _>>();
+    assert_eq!(t.size(), 1000);
+    assert!(t.height() <= 11);
+    assert_eq!(t.check(), Ok(()));
+    assert_eq!(t[&30], 900);
+    assert_eq!(t.insert(30, 0), Some(900));
+
+    assert_eq!((t.rank(&500), t.select(500)), (500, Some(&500)));
+    for i in (0..1000).step_by(2) {
+        t.delete(&i);
+    }
+    assert_eq!(t.check(), Ok(()));
+    assert_eq!((t.min(), t.max()), (Some(&1), Some(&999)));
+    assert_eq!((t.floor(&500), t.ceiling(&500)), (Some(&499), Some(&501)));
+    assert_eq!((t.rank(&500), t.select(250)), (250, Some(&501)));
+    assert_eq!(t.pop_first(), Some((1, 1)));
+    assert_eq!(t.pop_last(), Some((999, 998001)));
+    assert_eq!(t.iter().len(), 498);
+    assert_eq!(t.keys().take(3).collect::<Vec<_>>(), vec![&3, &5, &7]);
+}
+
+#[cfg(test)]
+impl_ordered_table!(AVLTree<u8, u16>);
+
+#[test]
+fn quicktest_avl_tree() {
+    fn prop(ops: Vec<(bool, u8, u16)>, probe: u8) -> bool {
+        crate::testing::model_check(AVLTree::new(), &ops, probe)
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u16)>, u8) -> bool);
+}
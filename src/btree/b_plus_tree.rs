@@ -0,0 +1,873 @@
+use super::{search, DEFAULT_FANOUT};
+use std::borrow::Borrow;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, Index, RangeBounds};
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    // entries in a leaf, separators in an internal node:
+    // keys[i] is the smallest key that may live under children[i + 1]
+    keys: Vec<K>,
+    vals: Vec<V>,
+    // empty in a leaf, one more than the keys otherwise
+    children: Vec<usize>,
+    // neighbouring leaves
+    prev: Option<usize>,
+    next: Option<usize>,
+    // number of entries in the subtree
+    n: usize,
+}
+
+impl<K, V> Node<K, V> {
+    fn new() -> Node<K, V> {
+        Node {
+            keys: Vec::new(),
+            vals: Vec::new(),
+            children: Vec::new(),
+            prev: None,
+            next: None,
+            n: 0,
+        }
+    }
+
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+// child of an internal node to descend into for key
+fn child_index<K: Borrow<Q>, Q: PartialOrd + ?Sized>(keys: &[K], key: &Q) -> usize {
+    keys.partition_point(|s| s.borrow() <= key)
+}
+
+/// Symbol table implementation with a B+-tree.
+///
+/// Entries are only kept in the leaves, which are linked in key order, the
+/// internal nodes hold copies of keys to route searches. Range scans walk the
+/// leaves without going back up the tree. Nodes are kept in an arena and
+/// refer to each other by index.
+#[derive(Clone)]
+pub struct BPlusTree<K, V> {
+    nodes: Vec<Node<K, V>>,
+    // unused slots in nodes
+    free: Vec<usize>,
+    root: usize,
+    // maximum number of children of an internal node, and of entries in a leaf plus one
+    m: usize,
+}
+
+impl<K: PartialOrd + Clone, V> Default for BPlusTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone, V> BPlusTree<K, V> {
+    pub fn new() -> BPlusTree<K, V> {
+        BPlusTree::with_fanout(DEFAULT_FANOUT)
+    }
+
+    /// create a B+-tree of nodes with up to `fanout` children
+    pub fn with_fanout(fanout: usize) -> BPlusTree<K, V> {
+        assert!(fanout >= 4, "a B+-tree needs a fanout of at least 4");
+        BPlusTree {
+            nodes: vec![Node::new()],
+            free: Vec::new(),
+            root: 0,
+            m: fanout,
+        }
+    }
+
+    /// maximum number of children per node
+    pub fn fanout(&self) -> usize {
+        self.m
+    }
+
+    /// number of key-value pairs in the table
+    pub fn size(&self) -> usize {
+        self.nodes[self.root].n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    /// number of levels below the root
+    pub fn height(&self) -> usize {
+        let mut h = 0;
+        let mut x = &self.nodes[self.root];
+        while let Some(&child) = x.children.first() {
+            h += 1;
+            x = &self.nodes[child];
+        }
+        h
+    }
+
+    #[inline]
+    fn min_keys(&self) -> usize {
+        (self.m - 1) / 2
+    }
+
+    fn alloc(&mut self, node: Node<K, V>) -> usize {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn release(&mut self, id: usize) -> Node<K, V> {
+        self.free.push(id);
+        mem::replace(&mut self.nodes[id], Node::new())
+    }
+
+    // leaf where key is or would be
+    fn find_leaf<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut id = self.root;
+        while !self.nodes[id].is_leaf() {
+            let x = &self.nodes[id];
+            id = x.children[child_index(&x.keys, key)];
+        }
+        id
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let x = &self.nodes[self.find_leaf(key)];
+        search(&x.keys, key).ok().map(|i| &x.vals[i])
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let id = self.find_leaf(key);
+        let x = &mut self.nodes[id];
+        search(&x.keys, key).ok().map(move |i| &mut x.vals[i])
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        self.insert(key, val);
+    }
+
+    /// insert a key-value pair, returns the value the key had before
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let (old, split) = self.insert_at(self.root, key, val);
+        // a split root gets a new root above it, the only way the tree grows
+        if let Some((sep, right)) = split {
+            let mut root = Node::new();
+            root.keys.push(sep);
+            root.n = self.nodes[self.root].n + self.nodes[right].n;
+            root.children = vec![self.root, right];
+            self.root = self.alloc(root);
+        }
+        old
+    }
+
+    // insert under node id, returns the old value and the separator and new
+    // right sibling if the node had to split
+    fn insert_at(&mut self, id: usize, key: K, val: V) -> (Option<V>, Option<(K, usize)>) {
+        if self.nodes[id].is_leaf() {
+            let x = &mut self.nodes[id];
+            match search(&x.keys, &key) {
+                Ok(i) => return (Some(mem::replace(&mut x.vals[i], val)), None),
+                Err(i) => {
+                    x.keys.insert(i, key);
+                    x.vals.insert(i, val);
+                    x.n += 1;
+                }
+            }
+            if x.keys.len() < self.m {
+                return (None, None);
+            }
+            // split the full leaf and link the new half in after it
+            let mid = x.keys.len() / 2;
+            let right = Node {
+                keys: x.keys.split_off(mid),
+                vals: x.vals.split_off(mid),
+                children: Vec::new(),
+                prev: Some(id),
+                next: x.next,
+                n: x.n - mid,
+            };
+            x.n = mid;
+            let sep = right.keys[0].clone();
+            let next = right.next;
+            let r = self.alloc(right);
+            self.nodes[id].next = Some(r);
+            if let Some(next) = next {
+                self.nodes[next].prev = Some(r);
+            }
+            (None, Some((sep, r)))
+        } else {
+            let i = child_index(&self.nodes[id].keys, &key);
+            let (old, split) = self.insert_at(self.nodes[id].children[i], key, val);
+            if old.is_none() {
+                self.nodes[id].n += 1;
+            }
+            let (sep, r) = match split {
+                Some(split) => split,
+                None => return (old, None),
+            };
+            let x = &mut self.nodes[id];
+            x.keys.insert(i, sep);
+            x.children.insert(i + 1, r);
+            if x.children.len() <= self.m {
+                return (old, None);
+            }
+            // split the overfull internal node, moving the middle separator up
+            let mid = x.keys.len() / 2;
+            let mut right = Node::new();
+            right.keys = x.keys.split_off(mid + 1);
+            right.children = x.children.split_off(mid + 1);
+            let sep = x.keys.pop().unwrap();
+            right.n = right.children.iter().map(|&c| self.nodes[c].n).sum();
+            self.nodes[id].n -= right.n;
+            (old, Some((sep, self.alloc(right))))
+        }
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove(key);
+    }
+
+    /// remove a key, returns its value if it was in the table
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let removed = self.remove_at(self.root, key);
+        // a root left with one child hands over to it, the only way the tree shrinks
+        if !self.nodes[self.root].is_leaf() && self.nodes[self.root].keys.is_empty() {
+            let root = self.root;
+            self.root = self.release(root).children[0];
+        }
+        removed
+    }
+
+    /// delete smallest key
+    pub fn delete_min(&mut self) {
+        self.pop_first();
+    }
+
+    /// delete largest key
+    pub fn delete_max(&mut self) {
+        self.pop_last();
+    }
+
+    /// remove and return the entry with the smallest key
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let key = self.min()?.clone();
+        self.remove_entry(&key)
+    }
+
+    /// remove and return the entry with the largest key
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let key = self.max()?.clone();
+        self.remove_entry(&key)
+    }
+
+    fn remove_at<Q>(&mut self, id: usize, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let x = &mut self.nodes[id];
+        if x.is_leaf() {
+            return search(&x.keys, key).ok().map(|i| {
+                x.n -= 1;
+                (x.keys.remove(i), x.vals.remove(i))
+            });
+        }
+        let i = child_index(&x.keys, key);
+        let child = x.children[i];
+        let removed = self.remove_at(child, key);
+        if removed.is_some() {
+            self.nodes[id].n -= 1;
+            if self.nodes[child].keys.len() < self.min_keys() {
+                self.rebalance(id, i);
+            }
+        }
+        removed
+    }
+
+    // refill the underfull child i of node id from a sibling, or merge it with one
+    fn rebalance(&mut self, id: usize, i: usize) {
+        let children = &self.nodes[id].children;
+        let child = children[i];
+        let left = if i > 0 { Some(children[i - 1]) } else { None };
+        let right = children.get(i + 1).cloned();
+        let is_leaf = self.nodes[child].is_leaf();
+
+        if let Some(l) = left.filter(|&l| self.nodes[l].keys.len() > self.min_keys()) {
+            let key = self.nodes[l].keys.pop().unwrap();
+            let moved = if is_leaf {
+                let val = self.nodes[l].vals.pop().unwrap();
+                self.nodes[id].keys[i - 1] = key.clone();
+                self.nodes[child].keys.insert(0, key);
+                self.nodes[child].vals.insert(0, val);
+                1
+            } else {
+                let grandchild = self.nodes[l].children.pop().unwrap();
+                let sep = mem::replace(&mut self.nodes[id].keys[i - 1], key);
+                self.nodes[child].keys.insert(0, sep);
+                self.nodes[child].children.insert(0, grandchild);
+                self.nodes[grandchild].n
+            };
+            self.nodes[l].n -= moved;
+            self.nodes[child].n += moved;
+        } else if let Some(r) = right.filter(|&r| self.nodes[r].keys.len() > self.min_keys()) {
+            let key = self.nodes[r].keys.remove(0);
+            let moved = if is_leaf {
+                let val = self.nodes[r].vals.remove(0);
+                self.nodes[id].keys[i] = self.nodes[r].keys[0].clone();
+                self.nodes[child].keys.push(key);
+                self.nodes[child].vals.push(val);
+                1
+            } else {
+                let grandchild = self.nodes[r].children.remove(0);
+                let sep = mem::replace(&mut self.nodes[id].keys[i], key);
+                self.nodes[child].keys.push(sep);
+                self.nodes[child].children.push(grandchild);
+                self.nodes[grandchild].n
+            };
+            self.nodes[r].n -= moved;
+            self.nodes[child].n += moved;
+        } else if left.is_some() {
+            self.merge(id, i - 1);
+        } else {
+            self.merge(id, i);
+        }
+    }
+
+    // merge child i + 1 of node id into child i
+    fn merge(&mut self, id: usize, i: usize) {
+        let sep = self.nodes[id].keys.remove(i);
+        let r = self.nodes[id].children.remove(i + 1);
+        let l = self.nodes[id].children[i];
+        let right = self.release(r);
+        if right.is_leaf() {
+            if let Some(next) = right.next {
+                self.nodes[next].prev = Some(l);
+            }
+            self.nodes[l].next = right.next;
+        } else {
+            self.nodes[l].keys.push(sep);
+        }
+        let left = &mut self.nodes[l];
+        left.n += right.n;
+        left.keys.extend(right.keys);
+        left.vals.extend(right.vals);
+        left.children.extend(right.children);
+    }
+
+    fn first_leaf(&self) -> usize {
+        let mut id = self.root;
+        while let Some(&child) = self.nodes[id].children.first() {
+            id = child;
+        }
+        id
+    }
+
+    fn last_leaf(&self) -> usize {
+        let mut id = self.root;
+        while let Some(&child) = self.nodes[id].children.last() {
+            id = child;
+        }
+        id
+    }
+
+    /// smallest key
+    pub fn min(&self) -> Option<&K> {
+        self.nodes[self.first_leaf()].keys.first()
+    }
+
+    /// largest key
+    pub fn max(&self) -> Option<&K> {
+        self.nodes[self.last_leaf()].keys.last()
+    }
+
+    /// entry with the smallest key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let x = &self.nodes[self.first_leaf()];
+        x.keys.first().zip(x.vals.first())
+    }
+
+    /// entry with the largest key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let x = &self.nodes[self.last_leaf()];
+        x.keys.last().zip(x.vals.last())
+    }
+
+    /// number of keys less than key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut rank = 0;
+        let mut x = &self.nodes[self.root];
+        while !x.is_leaf() {
+            let i = child_index(&x.keys, key);
+            rank += x.children[..i].iter().map(|&c| self.nodes[c].n).sum::<usize>();
+            x = &self.nodes[x.children[i]];
+        }
+        rank + x.keys.partition_point(|k| k.borrow() < key)
+    }
+
+    /// key of rank k
+    pub fn select(&self, k: usize) -> Option<&K> {
+        if k >= self.size() {
+            return None;
+        }
+        let mut k = k;
+        let mut x = &self.nodes[self.root];
+        while !x.is_leaf() {
+            let mut i = 0;
+            while k >= self.nodes[x.children[i]].n {
+                k -= self.nodes[x.children[i]].n;
+                i += 1;
+            }
+            x = &self.nodes[x.children[i]];
+        }
+        Some(&x.keys[k])
+    }
+
+    /// largest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Included(key)))
+            .next_back()
+            .map(|(k, _)| k)
+    }
+
+    /// smallest key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.range((Bound::Included(key), Bound::Unbounded))
+            .next()
+            .map(|(k, _)| k)
+    }
+
+    /// iterator over the entries, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let last = self.last_leaf();
+        Iter {
+            nodes: &self.nodes,
+            front: Some((self.first_leaf(), 0)),
+            back: Some((last, self.nodes[last].keys.len())),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// iterator over the entries with keys in the range, in key order
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let after_start = |key: &K| match range.start_bound() {
+            Bound::Included(lo) => key.borrow() >= lo,
+            Bound::Excluded(lo) => key.borrow() > lo,
+            Bound::Unbounded => true,
+        };
+        let before_end = |key: &K| match range.end_bound() {
+            Bound::Included(hi) => key.borrow() <= hi,
+            Bound::Excluded(hi) => key.borrow() < hi,
+            Bound::Unbounded => true,
+        };
+
+        // the leaves holding either bound, the scan itself follows the links
+        let mut lo = self.root;
+        while !self.nodes[lo].is_leaf() {
+            let x = &self.nodes[lo];
+            lo = x.children[x.keys.partition_point(|k| !after_start(k))];
+        }
+        let mut hi = self.root;
+        while !self.nodes[hi].is_leaf() {
+            let x = &self.nodes[hi];
+            hi = x.children[x.keys.partition_point(|k| before_end(k))];
+        }
+        Iter {
+            nodes: &self.nodes,
+            front: Some((lo, self.nodes[lo].keys.partition_point(|k| !after_start(k)))),
+            back: Some((hi, self.nodes[hi].keys.partition_point(|k| before_end(k)))),
+        }
+    }
+
+    /// check the B+-tree invariants, naming the first one violated
+    pub fn check(&self) -> Result<(), &'static str> {
+        // depth of the leaves under id, if they are all at the same depth
+        // and the keys respect the separators lo and hi above them
+        fn check_node<K: PartialOrd + Clone, V>(
+            t: &BPlusTree<K, V>,
+            id: usize,
+            lo: Option<&K>,
+            hi: Option<&K>,
+        ) -> Result<usize, &'static str> {
+            let x = &t.nodes[id];
+            if x.is_leaf() && x.keys.len() != x.vals.len() {
+                return Err("entries do not match");
+            }
+            if !x.is_leaf() && (!x.vals.is_empty() || x.children.len() != x.keys.len() + 1) {
+                return Err("separators and children do not match");
+            }
+            if x.keys.len() >= t.m || (id != t.root && x.keys.len() < t.min_keys()) {
+                return Err("node size out of bounds");
+            }
+            let n = if x.is_leaf() {
+                x.keys.len()
+            } else {
+                x.children.iter().map(|&c| t.nodes[c].n).sum()
+            };
+            if x.n != n {
+                return Err("subtree counts not consistent");
+            }
+            if x.keys
+                .iter()
+                .any(|k| lo.is_some_and(|lo| k < lo) || hi.is_some_and(|hi| k >= hi))
+            {
+                return Err("key outside of its separators");
+            }
+            let mut depth = None;
+            for (i, &child) in x.children.iter().enumerate() {
+                let lo = if i == 0 { lo } else { Some(&x.keys[i - 1]) };
+                let hi = x.keys.get(i).or(hi);
+                let d = check_node(t, child, lo, hi)?;
+                if depth.is_some_and(|depth| depth != d) {
+                    return Err("not balanced");
+                }
+                depth = Some(d);
+            }
+            Ok(depth.map_or(0, |d| d + 1))
+        }
+
+        if !self.nodes[self.root].is_leaf() && self.nodes[self.root].keys.is_empty() {
+            return Err("internal root with one child");
+        }
+        check_node(self, self.root, None, None)?;
+
+        // walk the leaf links both ways
+        let mut keys = Vec::new();
+        let (mut id, mut prev) = (Some(self.first_leaf()), None);
+        while let Some(leaf) = id {
+            if self.nodes[leaf].prev != prev {
+                return Err("leaf links not consistent");
+            }
+            keys.extend(self.nodes[leaf].keys.iter());
+            prev = id;
+            id = self.nodes[leaf].next;
+        }
+        if prev != Some(self.last_leaf()) {
+            Err("leaf links not consistent")
+        } else if keys.windows(2).any(|w| w[0] >= w[1]) {
+            Err("not in symmetric order")
+        } else if keys.len() != self.size() {
+            Err("size not consistent")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<K: PartialOrd + Clone, V> FromIterator<(K, V)> for BPlusTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut t = BPlusTree::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<K: PartialOrd + Clone, V> Extend<(K, V)> for BPlusTree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+impl<K, Q, V> Index<&Q> for BPlusTree<K, V>
+where
+    K: PartialOrd + Clone + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    fn index(&self, index: &Q) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+impl<K: PartialOrd + Clone, V: PartialEq> PartialEq for BPlusTree<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size() == other.size() && self.iter().eq(other.iter())
+    }
+}
+
+/// Iterator over the entries of a `BPlusTree` in key order, from either end.
+pub struct Iter<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    // leaf and position of the next entry from the front
+    front: Option<(usize, usize)>,
+    // leaf and number of entries left in it from the back
+    back: Option<(usize, usize)>,
+}
+
+impl<K, V> Iter<'_, K, V> {
+    // next entry from the front, as its leaf and position
+    fn peek_front(&mut self) -> Option<(usize, usize)> {
+        while let Some((leaf, i)) = self.front {
+            if i < self.nodes[leaf].keys.len() {
+                return Some((leaf, i));
+            }
+            self.front = self.nodes[leaf].next.map(|next| (next, 0));
+        }
+        None
+    }
+
+    // next entry from the back, as its leaf and position
+    fn peek_back(&mut self) -> Option<(usize, usize)> {
+        while let Some((leaf, j)) = self.back {
+            if j > 0 {
+                return Some((leaf, j - 1));
+            }
+            self.back = self.nodes[leaf].prev.map(|prev| (prev, self.nodes[prev].keys.len()));
+        }
+        None
+    }
+}
+
+impl<'a, K: PartialOrd, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, i) = self.peek_front()?;
+        let (y, j) = self.peek_back()?;
+        let nodes = self.nodes;
+        // the two ends crossed
+        if nodes[x].keys[i] > nodes[y].keys[j] {
+            return None;
+        }
+        self.front = Some((x, i + 1));
+        Some((&nodes[x].keys[i], &nodes[x].vals[i]))
+    }
+}
+
+impl<K: PartialOrd, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (x, i) = self.peek_front()?;
+        let (y, j) = self.peek_back()?;
+        let nodes = self.nodes;
+        if nodes[x].keys[i] > nodes[y].keys[j] {
+            return None;
+        }
+        self.back = Some((y, j));
+        Some((&nodes[y].keys[j], &nodes[y].vals[j]))
+    }
+}
+
+impl<'a, K: PartialOrd + Clone, V> IntoIterator for &'a BPlusTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+impl<K: PartialOrd + Clone, V> BPlusTree<K, V> {
+    // keys of every leaf, following the links from the first one
+    fn leaves(&self) -> Vec<Vec<K>> {
+        let mut leaves = Vec::new();
+        let mut id = Some(self.first_leaf());
+        while let Some(leaf) = id {
+            leaves.push(self.nodes[leaf].keys.clone());
+            id = self.nodes[leaf].next;
+        }
+        leaves
+    }
+}
+
+#[test]
+fn test_b_plus_tree() {
+    let mut t = BPlusTree::with_fanout(4);
+    for (i, c) in "SEARCHEXAMPLE".chars().enumerate() {
+        t.put(c, i);
+    }
+    assert_eq!(t.check(), Ok(()));
+    assert_eq!(t.keys().collect::<String>(), "ACEHLMPRSX");
+    // internal nodes only route, every entry is in a leaf
+    assert_eq!(t.leaves().concat().into_iter().collect::<String>(), "ACEHLMPRSX");
+    assert_eq!(t.insert('A', 0), Some(8));
+    *t.get_mut(&'X').unwrap() += 10;
+    assert_eq!(t.first_key_value(), Some((&'A', &0)));
+    assert_eq!(t.last_key_value(), Some((&'X', &17)));
+    assert_eq!((t.rank(&'L'), t.rank(&'N'), t.rank(&'Z')), (4, 6, 10));
+    assert_eq!((t.select(0), t.select(4), t.select(10)), (Some(&'A'), Some(&'L'), None));
+    // a separator copied up from a removed key still routes correctly
+    let sep = t.nodes[t.root].keys[0];
+    t.delete(&sep);
+    assert_eq!((t.get(&sep), t.ceiling(&sep)), (None, t.select(t.rank(&sep))));
+    assert_eq!(t.check(), Ok(()));
+
+    while let Some((k, _)) = t.pop_last() {
+        assert!(!t.contains(&k));
+        assert_eq!(t.check(), Ok(()));
+    }
+    assert_eq!(t.height(), 0);
+    assert_eq!(t.iter().next(), None);
+}
+
+#[test]
+fn test_b_plus_tree_leaf_links() {
+    // ascending inserts into fanout 6 leave four leaves of three
+    let mut t = BPlusTree::with_fanout(6);
+    t.extend((0..12).map(|i| (i, i)));
+    assert_eq!(t.leaves(), vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8], vec![9, 10, 11]]);
+
+    let steps: Vec<(i32, Vec<Vec<i32>>)> = vec![
+        (4, vec![vec![0, 1, 2], vec![3, 5], vec![6, 7, 8], vec![9, 10, 11]]),
+        // borrow from the left sibling
+        (5, vec![vec![0, 1], vec![2, 3], vec![6, 7, 8], vec![9, 10, 11]]),
+        // no left sibling, merge with the right one
+        (0, vec![vec![1, 2, 3], vec![6, 7, 8], vec![9, 10, 11]]),
+        (1, vec![vec![2, 3], vec![6, 7, 8], vec![9, 10, 11]]),
+        (7, vec![vec![2, 3], vec![6, 8], vec![9, 10, 11]]),
+        // borrow from the right sibling
+        (8, vec![vec![2, 3], vec![6, 9], vec![10, 11]]),
+        (2, vec![vec![3, 6, 9], vec![10, 11]]),
+        (3, vec![vec![6, 9], vec![10, 11]]),
+        // merge into the left sibling, the root collapses into the last leaf
+        (10, vec![vec![6, 9, 11]]),
+    ];
+    for (key, leaves) in steps {
+        assert_eq!(t.remove(&key), Some(key));
+        assert_eq!(t.leaves(), leaves, "after removing {}", key);
+        // check walks the links both ways
+        assert_eq!(t.check(), Ok(()));
+        let forward = t.keys().cloned().collect::<Vec<_>>();
+        let mut backward = t.keys().rev().cloned().collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(forward, leaves.concat());
+        assert_eq!(backward, forward);
+    }
+    assert_eq!(t.height(), 0);
+}
+
+#[test]
+fn test_b_plus_tree_range_across_leaves() {
+    let mut t = BPlusTree::with_fanout(4);
+    t.extend((0..200).step_by(2).map(|i| (i, i)));
+    let leaves = t.leaves();
+    assert!(leaves.len() > 20);
+
+    // bounds on the first and last keys of leaves, in and out of the range
+    let firsts = leaves.iter().map(|leaf| leaf[0]).collect::<Vec<_>>();
+    let lasts = leaves.iter().map(|leaf| *leaf.last().unwrap()).collect::<Vec<_>>();
+    for (&lo, &hi) in firsts.iter().zip(lasts.iter().skip(3)) {
+        let expected = (lo..=hi).step_by(2).collect::<Vec<_>>();
+        assert_eq!(t.range(lo..=hi).map(|(&k, _)| k).collect::<Vec<_>>(), expected);
+        assert_eq!(t.range(lo + 1..hi).map(|(&k, _)| k).collect::<Vec<_>>(), expected[1..expected.len() - 1]);
+        assert_eq!(t.range(lo..=hi).rev().count(), expected.len());
+        // bounds between the keys of neighbouring leaves
+        assert_eq!(t.range(lo - 1..hi + 1).count(), expected.len());
+    }
+
+    // both ends meet in the middle of a leaf several leaves away
+    let mut range = t.range(17..=121);
+    let mut keys = vec![];
+    loop {
+        match (range.next(), range.next_back()) {
+            (Some((&a, _)), Some((&b, _))) => keys.extend(&[a, b]),
+            (Some((&a, _)), None) => keys.push(a),
+            _ => break,
+        }
+    }
+    keys.sort();
+    assert_eq!(keys, (18..=120).step_by(2).collect::<Vec<_>>());
+    assert_eq!(t.range(201..).next(), None);
+    assert_eq!(t.range(..0).next_back(), None);
+}
+
+#[cfg(test)]
+impl_ordered_table!(BPlusTree<u8, u16>);
+
+#[test]
+fn quicktest_b_plus_tree() {
+    use crate::testing::{matches, queries_match, replay, Model};
+
+    fn prop(ops: Vec<(bool, u8, u16)>, lo: u8, hi: u8) -> bool {
+        // odd fanouts are fine, leaves split at the fanout
+        let mut t = BPlusTree::with_fanout(4 + lo as usize % 3);
+        let mut model = Model::new();
+        if replay(&mut t, &mut model, &ops).is_none() || !matches(&t, &model) || !queries_match(&t, &model, hi) {
+            return false;
+        }
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        if !(t.range(lo..hi).rev().eq(model.range(lo..hi).rev()) && t.range(lo..=hi).eq(model.range(lo..=hi))) {
+            return false;
+        }
+        while !model.is_empty() {
+            let (first, last) = (t.pop_first(), t.pop_last());
+            if first != model.pop_first() || last != model.pop_last() || t.check().is_err() {
+                return false;
+            }
+        }
+        t.is_empty()
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u16)>, u8, u8) -> bool);
+}
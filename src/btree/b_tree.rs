@@ -0,0 +1,774 @@
+use super::{search, DEFAULT_FANOUT};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, Index, RangeBounds};
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    keys: Vec<K>,
+    vals: Vec<V>,
+    // empty in a leaf, one more than the keys otherwise
+    children: Vec<Node<K, V>>,
+    // number of keys in the subtree
+    n: usize,
+}
+
+impl<K, V> Node<K, V> {
+    fn new() -> Node<K, V> {
+        Node {
+            keys: Vec::new(),
+            vals: Vec::new(),
+            children: Vec::new(),
+            n: 0,
+        }
+    }
+
+    #[inline]
+    fn size(x: Option<&Node<K, V>>) -> usize {
+        x.map_or(0, |x| x.n)
+    }
+
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    // split the full child i, moving its median up into this node
+    fn split_child(&mut self, i: usize, t: usize) {
+        let child = &mut self.children[i];
+        let mut right = Node {
+            keys: child.keys.split_off(t),
+            vals: child.vals.split_off(t),
+            children: if child.is_leaf() {
+                Vec::new()
+            } else {
+                child.children.split_off(t)
+            },
+            n: 0,
+        };
+        right.n = right.keys.len() + right.children.iter().map(|c| c.n).sum::<usize>();
+        child.n -= right.n + 1;
+        let (key, val) = (child.keys.pop().unwrap(), child.vals.pop().unwrap());
+        self.keys.insert(i, key);
+        self.vals.insert(i, val);
+        self.children.insert(i + 1, right);
+    }
+
+    // merge child i + 1 and the key between them into child i
+    fn merge(&mut self, i: usize) {
+        let right = self.children.remove(i + 1);
+        let (key, val) = (self.keys.remove(i), self.vals.remove(i));
+        let child = &mut self.children[i];
+        child.keys.push(key);
+        child.vals.push(val);
+        child.n += right.n + 1;
+        let Node {
+            keys, vals, children, ..
+        } = right;
+        child.keys.extend(keys);
+        child.vals.extend(vals);
+        child.children.extend(children);
+    }
+
+    // make sure child i has at least t keys before descending into it,
+    // returns where the child ended up
+    fn fill(&mut self, i: usize, t: usize) -> usize {
+        if self.children[i].keys.len() >= t {
+            i
+        } else if i > 0 && self.children[i - 1].keys.len() >= t {
+            // rotate the last key of the left sibling through this node
+            let (left, right) = self.children.split_at_mut(i);
+            let (left, child) = (&mut left[i - 1], &mut right[0]);
+            let key = mem::replace(&mut self.keys[i - 1], left.keys.pop().unwrap());
+            let val = mem::replace(&mut self.vals[i - 1], left.vals.pop().unwrap());
+            child.keys.insert(0, key);
+            child.vals.insert(0, val);
+            let moved = 1 + Node::size(left.children.last());
+            if let Some(c) = left.children.pop() {
+                child.children.insert(0, c);
+            }
+            left.n -= moved;
+            child.n += moved;
+            i
+        } else if i + 1 < self.children.len() && self.children[i + 1].keys.len() >= t {
+            // rotate the first key of the right sibling through this node
+            let (left, right) = self.children.split_at_mut(i + 1);
+            let (child, right) = (&mut left[i], &mut right[0]);
+            let key = mem::replace(&mut self.keys[i], right.keys.remove(0));
+            let val = mem::replace(&mut self.vals[i], right.vals.remove(0));
+            child.keys.push(key);
+            child.vals.push(val);
+            let moved = 1 + Node::size(right.children.first());
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+            right.n -= moved;
+            child.n += moved;
+            i
+        } else if i + 1 < self.children.len() {
+            self.merge(i);
+            i
+        } else {
+            self.merge(i - 1);
+            i - 1
+        }
+    }
+}
+
+impl<K: PartialOrd, V> Node<K, V> {
+    // insert into a node that is not full
+    fn insert(&mut self, key: K, val: V, t: usize) -> Option<V> {
+        let mut i = match search(&self.keys, &key) {
+            Ok(i) => return Some(mem::replace(&mut self.vals[i], val)),
+            Err(i) => i,
+        };
+        if self.is_leaf() {
+            self.keys.insert(i, key);
+            self.vals.insert(i, val);
+            self.n += 1;
+            return None;
+        }
+        if self.children[i].keys.len() == 2 * t - 1 {
+            self.split_child(i, t);
+            match key.partial_cmp(&self.keys[i]).unwrap() {
+                Ordering::Less => {}
+                Ordering::Greater => i += 1,
+                Ordering::Equal => return Some(mem::replace(&mut self.vals[i], val)),
+            }
+        }
+        let old = self.children[i].insert(key, val, t);
+        if old.is_none() {
+            self.n += 1;
+        }
+        old
+    }
+
+    // remove from a node with at least t keys, or the root
+    fn remove<Q>(&mut self, key: &Q, t: usize) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let removed = match search(&self.keys, key) {
+            Ok(i) if self.is_leaf() => Some((self.keys.remove(i), self.vals.remove(i))),
+            Ok(i) => {
+                // replace with the predecessor or successor, if a child can spare it
+                if self.children[i].keys.len() >= t {
+                    let (k, v) = self.children[i].remove_max(t);
+                    Some((mem::replace(&mut self.keys[i], k), mem::replace(&mut self.vals[i], v)))
+                } else if self.children[i + 1].keys.len() >= t {
+                    let (k, v) = self.children[i + 1].remove_min(t);
+                    Some((mem::replace(&mut self.keys[i], k), mem::replace(&mut self.vals[i], v)))
+                } else {
+                    self.merge(i);
+                    self.children[i].remove(key, t)
+                }
+            }
+            Err(_) if self.is_leaf() => None,
+            Err(i) => {
+                let i = self.fill(i, t);
+                self.children[i].remove(key, t)
+            }
+        };
+        if removed.is_some() {
+            self.n -= 1;
+        }
+        removed
+    }
+
+    fn remove_min(&mut self, t: usize) -> (K, V) {
+        self.n -= 1;
+        if self.is_leaf() {
+            (self.keys.remove(0), self.vals.remove(0))
+        } else {
+            let i = self.fill(0, t);
+            self.children[i].remove_min(t)
+        }
+    }
+
+    fn remove_max(&mut self, t: usize) -> (K, V) {
+        self.n -= 1;
+        if self.is_leaf() {
+            (self.keys.pop().unwrap(), self.vals.pop().unwrap())
+        } else {
+            let i = self.fill(self.children.len() - 1, t);
+            self.children[i].remove_max(t)
+        }
+    }
+}
+
+/// Symbol table implementation with a B-tree.
+///
+/// Every node holds between `fanout / 2 - 1` and `fanout - 1` sorted entries
+/// (the root may hold fewer) next to each other in memory, so a lookup reads
+/// O(log n / log fanout) nodes instead of O(log n) scattered binary nodes.
+/// Entries live in internal nodes too, see `BPlusTree` for leaf-only entries.
+#[derive(Clone)]
+pub struct BTree<K, V> {
+    root: Node<K, V>,
+    // minimum degree, half the fanout
+    t: usize,
+}
+
+impl<K: PartialOrd, V> Default for BTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd, V> BTree<K, V> {
+    pub fn new() -> BTree<K, V> {
+        BTree::with_fanout(DEFAULT_FANOUT)
+    }
+
+    /// create a B-tree of nodes with up to `fanout` children, which must be even
+    /// so a full node splits into two halves of legal size
+    pub fn with_fanout(fanout: usize) -> BTree<K, V> {
+        assert!(fanout >= 4, "a B-tree needs a fanout of at least 4");
+        assert!(fanout.is_multiple_of(2), "a B-tree needs an even fanout");
+        BTree {
+            root: Node::new(),
+            t: fanout / 2,
+        }
+    }
+
+    /// maximum number of children per node
+    pub fn fanout(&self) -> usize {
+        2 * self.t
+    }
+
+    /// number of key-value pairs in the table
+    pub fn size(&self) -> usize {
+        self.root.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.n == 0
+    }
+
+    /// number of levels below the root
+    pub fn height(&self) -> usize {
+        let mut h = 0;
+        let mut x = &self.root;
+        while let Some(child) = x.children.first() {
+            h += 1;
+            x = child;
+        }
+        h
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = &self.root;
+        loop {
+            match search(&x.keys, key) {
+                Ok(i) => return Some(&x.vals[i]),
+                Err(_) if x.is_leaf() => return None,
+                Err(i) => x = &x.children[i],
+            }
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = &mut self.root;
+        loop {
+            match search(&x.keys, key) {
+                Ok(i) => return Some(&mut x.vals[i]),
+                Err(_) if x.is_leaf() => return None,
+                Err(i) => x = &mut x.children[i],
+            }
+        }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        self.insert(key, val);
+    }
+
+    /// insert a key-value pair, returns the value the key had before
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        // split a full root on the way down, the only way the tree grows
+        if self.root.keys.len() == 2 * self.t - 1 {
+            let old_root = mem::replace(&mut self.root, Node::new());
+            self.root.n = old_root.n;
+            self.root.children.push(old_root);
+            self.root.split_child(0, self.t);
+        }
+        self.root.insert(key, val, self.t)
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove(key);
+    }
+
+    /// remove a key, returns its value if it was in the table
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let removed = self.root.remove(key, self.t);
+        self.shrink();
+        removed.map(|(_, v)| v)
+    }
+
+    // an empty internal root hands over to its only child, the only way the tree shrinks
+    fn shrink(&mut self) {
+        if self.root.keys.is_empty() && !self.root.is_leaf() {
+            self.root = self.root.children.pop().unwrap();
+        }
+    }
+
+    /// delete smallest key
+    pub fn delete_min(&mut self) {
+        self.pop_first();
+    }
+
+    /// delete largest key
+    pub fn delete_max(&mut self) {
+        self.pop_last();
+    }
+
+    /// remove and return the entry with the smallest key
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let entry = self.root.remove_min(self.t);
+        self.shrink();
+        Some(entry)
+    }
+
+    /// remove and return the entry with the largest key
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let entry = self.root.remove_max(self.t);
+        self.shrink();
+        Some(entry)
+    }
+
+    /// smallest key
+    pub fn min(&self) -> Option<&K> {
+        self.first_key_value().map(|(k, _)| k)
+    }
+
+    /// largest key
+    pub fn max(&self) -> Option<&K> {
+        self.last_key_value().map(|(k, _)| k)
+    }
+
+    /// entry with the smallest key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut x = &self.root;
+        while let Some(child) = x.children.first() {
+            x = child;
+        }
+        x.keys.first().map(|k| (k, &x.vals[0]))
+    }
+
+    /// entry with the largest key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut x = &self.root;
+        while let Some(child) = x.children.last() {
+            x = child;
+        }
+        x.keys.last().zip(x.vals.last())
+    }
+
+    /// number of keys less than key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut rank = 0;
+        let mut x = &self.root;
+        loop {
+            let (i, found) = match search(&x.keys, key) {
+                Ok(i) => (i, true),
+                Err(i) => (i, false),
+            };
+            // the keys before position i and everything under their left children
+            rank += i + x.children.iter().take(i).map(|c| c.n).sum::<usize>();
+            match x.children.get(i) {
+                Some(child) if found => return rank + child.n,
+                Some(child) => x = child,
+                None => return rank,
+            }
+        }
+    }
+
+    /// key of rank k
+    pub fn select(&self, k: usize) -> Option<&K> {
+        if k >= self.size() {
+            return None;
+        }
+        let mut k = k;
+        let mut x = &self.root;
+        loop {
+            if x.is_leaf() {
+                return Some(&x.keys[k]);
+            }
+            let mut i = 0;
+            while k >= x.children[i].n {
+                if k == x.children[i].n {
+                    return Some(&x.keys[i]);
+                }
+                k -= x.children[i].n + 1;
+                i += 1;
+            }
+            x = &x.children[i];
+        }
+    }
+
+    /// largest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Included(key)))
+            .next_back()
+            .map(|(k, _)| k)
+    }
+
+    /// smallest key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.range((Bound::Included(key), Bound::Unbounded))
+            .next()
+            .map(|(k, _)| k)
+    }
+
+    /// iterator over the entries, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+        };
+        iter.push_left(&self.root);
+        iter.push_right(&self.root);
+        iter
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// iterator over the entries with keys in the range, in key order
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let after_start = |key: &K| match range.start_bound() {
+            Bound::Included(lo) => key.borrow() >= lo,
+            Bound::Excluded(lo) => key.borrow() > lo,
+            Bound::Unbounded => true,
+        };
+        let before_end = |key: &K| match range.end_bound() {
+            Bound::Included(hi) => key.borrow() <= hi,
+            Bound::Excluded(hi) => key.borrow() < hi,
+            Bound::Unbounded => true,
+        };
+
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+        };
+        // the search paths to either bound, skipping the keys outside
+        let mut x = &self.root;
+        loop {
+            let i = x.keys.partition_point(|k| !after_start(k));
+            iter.front.push((x, i));
+            match x.children.get(i) {
+                Some(child) => x = child,
+                None => break,
+            }
+        }
+        let mut x = &self.root;
+        loop {
+            let j = x.keys.partition_point(|k| before_end(k));
+            iter.back.push((x, j));
+            match x.children.get(j) {
+                Some(child) => x = child,
+                None => break,
+            }
+        }
+        iter
+    }
+
+    /// check the B-tree invariants, naming the first one violated
+    pub fn check(&self) -> Result<(), &'static str> {
+        // depth of the leaves under x, if they are all at the same depth
+        fn check_node<K, V>(x: &Node<K, V>, t: usize, is_root: bool) -> Result<usize, &'static str> {
+            if x.keys.len() != x.vals.len() || (!x.is_leaf() && x.children.len() != x.keys.len() + 1) {
+                return Err("entries and children do not match");
+            }
+            if x.n != x.keys.len() + x.children.iter().map(|c| c.n).sum::<usize>() {
+                return Err("subtree counts not consistent");
+            }
+            if x.keys.len() > 2 * t - 1 || (!is_root && x.keys.len() < t - 1) {
+                return Err("node size out of bounds");
+            }
+            let mut depth = None;
+            for child in x.children.iter() {
+                let d = check_node(child, t, false)?;
+                if depth.is_some_and(|depth| depth != d) {
+                    return Err("not balanced");
+                }
+                depth = Some(d);
+            }
+            Ok(depth.map_or(0, |d| d + 1))
+        }
+
+        if self.root.keys.is_empty() && !self.root.is_leaf() {
+            return Err("empty internal root");
+        }
+        check_node(&self.root, self.t, true)?;
+        let keys = self.keys().collect::<Vec<_>>();
+        if keys.windows(2).any(|w| w[0] >= w[1]) {
+            Err("not in symmetric order")
+        } else if keys.len() != self.size() {
+            Err("size not consistent")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<K: PartialOrd, V> FromIterator<(K, V)> for BTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut t = BTree::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<K: PartialOrd, V> Extend<(K, V)> for BTree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+impl<K, Q, V> Index<&Q> for BTree<K, V>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    fn index(&self, index: &Q) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+impl<K: PartialOrd, V: PartialEq> PartialEq for BTree<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size() == other.size() && self.iter().eq(other.iter())
+    }
+}
+
+/// Iterator over the entries of a `BTree` in key order, from either end.
+pub struct Iter<'a, K, V> {
+    // nodes with the position of the next key to visit from the front
+    front: Vec<(&'a Node<K, V>, usize)>,
+    // nodes with the number of keys left to visit from the back
+    back: Vec<(&'a Node<K, V>, usize)>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut x: &'a Node<K, V>) {
+        loop {
+            self.front.push((x, 0));
+            match x.children.first() {
+                Some(child) => x = child,
+                None => break,
+            }
+        }
+    }
+
+    fn push_right(&mut self, mut x: &'a Node<K, V>) {
+        loop {
+            self.back.push((x, x.keys.len()));
+            match x.children.last() {
+                Some(child) => x = child,
+                None => break,
+            }
+        }
+    }
+
+    // next key from the front, as its node and position
+    fn peek_front(&mut self) -> Option<(&'a Node<K, V>, usize)> {
+        while let Some(&(x, i)) = self.front.last() {
+            if i < x.keys.len() {
+                return Some((x, i));
+            }
+            self.front.pop();
+        }
+        None
+    }
+
+    // next key from the back, as its node and position
+    fn peek_back(&mut self) -> Option<(&'a Node<K, V>, usize)> {
+        while let Some(&(x, j)) = self.back.last() {
+            if j > 0 {
+                return Some((x, j - 1));
+            }
+            self.back.pop();
+        }
+        None
+    }
+}
+
+impl<'a, K: PartialOrd, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, i) = self.peek_front()?;
+        let (y, j) = self.peek_back()?;
+        // the two ends crossed
+        if x.keys[i] > y.keys[j] {
+            return None;
+        }
+        self.front.last_mut().unwrap().1 += 1;
+        if let Some(child) = x.children.get(i + 1) {
+            self.push_left(child);
+        }
+        Some((&x.keys[i], &x.vals[i]))
+    }
+}
+
+impl<K: PartialOrd, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (x, i) = self.peek_front()?;
+        let (y, j) = self.peek_back()?;
+        if x.keys[i] > y.keys[j] {
+            return None;
+        }
+        self.back.last_mut().unwrap().1 -= 1;
+        if let Some(child) = y.children.get(j) {
+            self.push_right(child);
+        }
+        Some((&y.keys[j], &y.vals[j]))
+    }
+}
+
+impl<'a, K: PartialOrd, V> IntoIterator for &'a BTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_b_tree() {
+    let mut t = BTree::with_fanout(4);
+    for (i, c) in "SEARCHEXAMPLE".chars().enumerate() {
+        t.put(c, i);
+    }
+    assert_eq!(t.size(), 10);
+    assert_eq!(t.check(), Ok(()));
+    assert!(t.height() >= 2);
+    assert_eq!(t[&'E'], 12);
+    assert_eq!(t.insert('A', 0), Some(8));
+    *t.get_mut(&'X').unwrap() += 10;
+    assert_eq!(t.get(&'X'), Some(&17));
+    assert_eq!(t.keys().collect::<String>(), "ACEHLMPRSX");
+    assert_eq!((t.min(), t.max()), (Some(&'A'), Some(&'X')));
+    assert_eq!((t.floor(&'O'), t.ceiling(&'Q')), (Some(&'M'), Some(&'R')));
+    assert_eq!(t.floor(&'0'), None);
+    assert_eq!(t.range('E'..='P').rev().map(|(k, _)| *k).collect::<String>(), "PMLHE");
+    assert_eq!((t.rank(&'L'), t.rank(&'N'), t.rank(&'Z')), (4, 6, 10));
+    assert_eq!((t.select(0), t.select(4), t.select(10)), (Some(&'A'), Some(&'L'), None));
+    assert_eq!(t.first_key_value(), Some((&'A', &0)));
+    assert_eq!(t.last_key_value(), Some((&'X', &17)));
+
+    for c in "SEARCH".chars() {
+        assert!(t.remove(&c).is_some());
+        assert_eq!(t.check(), Ok(()));
+    }
+    assert_eq!(t.remove(&'S'), None);
+    assert_eq!(t.keys().collect::<String>(), "LMPX");
+    assert_eq!(t.pop_first(), Some(('L', 11)));
+    t.delete_max();
+    assert_eq!(t.keys().collect::<String>(), "MP");
+    assert_eq!(t.check(), Ok(()));
+
+    let words = "it was the best of times".split(' ').map(|w| (w.to_string(), w.len()));
+    let t = words.collect::<BTree<_, _>>();
+    assert_eq!(t.get("best"), Some(&4));
+    assert_eq!(t.fanout(), DEFAULT_FANOUT);
+}
+
+#[test]
+#[should_panic(expected = "even fanout")]
+fn test_b_tree_odd_fanout() {
+    BTree::<u8, u8>::with_fanout(5);
+}
+
+#[cfg(test)]
+impl_ordered_table!(BTree<u8, u16>);
+
+#[test]
+fn quicktest_b_tree() {
+    use crate::testing::{matches, queries_match, replay, Model};
+
+    fn prop(ops: Vec<(bool, u8, u16)>, lo: u8, hi: u8) -> bool {
+        let mut t = BTree::with_fanout(4 + 2 * (lo as usize % 3));
+        let mut model = Model::new();
+        if replay(&mut t, &mut model, &ops).is_none() || !matches(&t, &model) || !queries_match(&t, &model, hi) {
+            return false;
+        }
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        if !t.range(lo..hi).rev().eq(model.range(lo..hi).rev()) {
+            return false;
+        }
+        // take the entries from both ends
+        while !model.is_empty() {
+            let (first, last) = (t.pop_first(), t.pop_last());
+            if first != model.pop_first() || last != model.pop_last() || t.check().is_err() {
+                return false;
+            }
+        }
+        t.is_empty()
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u16)>, u8, u8) -> bool);
+}
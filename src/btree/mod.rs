@@ -0,0 +1,16 @@
+use std::borrow::Borrow;
+
+pub mod b_plus_tree;
+pub mod b_tree;
+
+// reexports
+pub use self::b_plus_tree::BPlusTree;
+pub use self::b_tree::BTree;
+
+/// Maximum number of children per node unless another fanout is given.
+pub const DEFAULT_FANOUT: usize = 32;
+
+// position of the key in a sorted node, or where it would be inserted
+fn search<K: Borrow<Q>, Q: PartialOrd + ?Sized>(keys: &[K], key: &Q) -> Result<usize, usize> {
+    keys.binary_search_by(|k| k.borrow().partial_cmp(key).unwrap())
+}
@@ -0,0 +1,292 @@
+use super::hashst::{DefaultHashBuilder, HashST};
+use super::rbtree::RedBlackBST;
+use std::hash::{BuildHasher, Hash};
+
+/// Assignment of keys to nodes.
+pub trait NodeLocator<N> {
+    /// node owning the key, if there are any nodes
+    fn locate<T: Hash + ?Sized>(&self, key: &T) -> Option<&N>;
+}
+
+/// Consistent hashing: nodes own the arcs of a hash ring ending at their points.
+///
+/// Each node is placed at `replicas * weight` virtual points, and a key belongs
+/// to the first point clockwise from its hash. A joining or leaving node only
+/// moves the keys on its own arcs.
+pub struct ConsistentHashRing<N, S = DefaultHashBuilder> {
+    ring: RedBlackBST<u64, N>,
+    nodes: Vec<(N, usize)>,
+    replicas: usize,
+    hash_builder: S,
+}
+
+impl<N: Hash + PartialEq + Clone> ConsistentHashRing<N> {
+    /// create a ring placing `replicas` virtual points per unit of weight
+    pub fn new(replicas: usize) -> ConsistentHashRing<N> {
+        ConsistentHashRing::with_hasher(replicas, Default::default())
+    }
+}
+
+impl<N: Hash + PartialEq + Clone, S: BuildHasher> ConsistentHashRing<N, S> {
+    pub fn with_hasher(replicas: usize, hash_builder: S) -> ConsistentHashRing<N, S> {
+        assert!(replicas > 0, "a node needs at least one virtual point");
+        ConsistentHashRing {
+            ring: RedBlackBST::new(),
+            nodes: Vec::new(),
+            replicas,
+            hash_builder,
+        }
+    }
+
+    fn points<'a>(&'a self, node: &'a N, weight: usize) -> impl Iterator<Item = u64> + 'a {
+        (0..(self.replicas * weight) as u64).map(move |i| self.hash_builder.hash_one((node, i)))
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.add_weighted_node(node, 1)
+    }
+
+    /// add a node owning about `weight` times the share of a unit node,
+    /// replacing its weight if it is already on the ring
+    pub fn add_weighted_node(&mut self, node: N, weight: usize) {
+        self.remove_node(&node);
+        self.place(&node, weight);
+        self.nodes.push((node, weight));
+    }
+
+    /// remove a node, returns false if it is not on the ring
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        let i = match self.nodes.iter().position(|(n, _)| n == node) {
+            Some(i) => i,
+            None => return false,
+        };
+        let (node, weight) = self.nodes.remove(i);
+        let points = self.points(&node, weight).collect::<Vec<_>>();
+        for point in points {
+            // leave points the node lost to a collision with their owner
+            if self.ring.get(&point) == Some(&node) {
+                self.ring.delete(&point);
+            }
+        }
+        true
+    }
+
+    fn place(&mut self, node: &N, weight: usize) {
+        let points = self.points(node, weight).collect::<Vec<_>>();
+        for point in points {
+            // the rare colliding point stays with its first owner
+            if !self.ring.contains(&point) {
+                self.ring.put(point, node.clone());
+            }
+        }
+    }
+
+    /// node owning the key, if there are any nodes
+    pub fn node_for<T: Hash + ?Sized>(&self, key: &T) -> Option<&N> {
+        let h = self.hash_builder.hash_one(key);
+        // wrap around past the last point
+        let point = self.ring.ceiling(&h).or_else(|| self.ring.min())?;
+        self.ring.get(point)
+    }
+
+    /// nodes with their weights, in the order they joined
+    pub fn nodes(&self) -> &[(N, usize)] {
+        &self.nodes
+    }
+
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<N: Hash + PartialEq + Clone, S: BuildHasher> NodeLocator<N> for ConsistentHashRing<N, S> {
+    fn locate<T: Hash + ?Sized>(&self, key: &T) -> Option<&N> {
+        self.node_for(key)
+    }
+}
+
+/// Rendezvous (highest random weight) hashing: a key belongs to the node
+/// scoring highest for it.
+///
+/// Lookups cost O(n) in the number of nodes, but need no ring and move the
+/// least keys possible. Weighted nodes score `-w / ln(u)` for a uniform `u`
+/// derived from the key and node.
+pub struct RendezvousHasher<N, S = DefaultHashBuilder> {
+    nodes: Vec<(N, f64)>,
+    hash_builder: S,
+}
+
+impl<N: Hash + PartialEq> RendezvousHasher<N> {
+    pub fn new() -> RendezvousHasher<N> {
+        RendezvousHasher::with_hasher(Default::default())
+    }
+}
+
+impl<N: Hash + PartialEq> Default for RendezvousHasher<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Hash + PartialEq, S: BuildHasher> RendezvousHasher<N, S> {
+    pub fn with_hasher(hash_builder: S) -> RendezvousHasher<N, S> {
+        RendezvousHasher {
+            nodes: Vec::new(),
+            hash_builder,
+        }
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.add_weighted_node(node, 1.0)
+    }
+
+    /// add a node, or replace its weight if it is already present
+    pub fn add_weighted_node(&mut self, node: N, weight: f64) {
+        assert!(weight > 0.0 && weight.is_finite(), "weight must be positive");
+        self.remove_node(&node);
+        self.nodes.push((node, weight));
+    }
+
+    /// remove a node, returns false if it is not present
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        let len = self.nodes.len();
+        self.nodes.retain(|(n, _)| n != node);
+        self.nodes.len() != len
+    }
+
+    fn score<T: Hash + ?Sized>(&self, node: &N, weight: f64, key: &T) -> f64 {
+        let h = self.hash_builder.hash_one((node, key));
+        // uniform in (0, 1)
+        let u = ((h >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+        -weight / u.ln()
+    }
+
+    /// node owning the key, if there are any nodes
+    pub fn node_for<T: Hash + ?Sized>(&self, key: &T) -> Option<&N> {
+        self.nodes
+            .iter()
+            .map(|(n, w)| (n, self.score(n, *w, key)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(n, _)| n)
+    }
+
+    /// nodes with their weights, in the order they joined
+    pub fn nodes(&self) -> &[(N, f64)] {
+        &self.nodes
+    }
+
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<N: Hash + PartialEq, S: BuildHasher> NodeLocator<N> for RendezvousHasher<N, S> {
+    fn locate<T: Hash + ?Sized>(&self, key: &T) -> Option<&N> {
+        self.node_for(key)
+    }
+}
+
+/// Keys that changed owner when the nodes changed.
+#[derive(Debug)]
+pub struct MovementReport<N> {
+    /// number of keys checked
+    pub total: usize,
+    /// number of keys with a different owner
+    pub moved: usize,
+    /// (from, to, count) for each pair of owners keys moved between
+    pub transfers: Vec<(Option<N>, Option<N>, usize)>,
+}
+
+impl<N> MovementReport<N> {
+    pub fn fraction_moved(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.moved as f64 / self.total as f64
+        }
+    }
+}
+
+/// apply `change` to the nodes of a locator, and report how `keys` moved
+pub fn key_movement<N, L, T, F>(locator: &mut L, keys: &[T], change: F) -> MovementReport<N>
+where
+    N: Hash + PartialEq + Clone,
+    L: NodeLocator<N>,
+    T: Hash,
+    F: FnOnce(&mut L),
+{
+    let before = keys.iter().map(|k| locator.locate(k).cloned()).collect::<Vec<_>>();
+    change(locator);
+
+    let mut transfers = HashST::new();
+    for (k, from) in keys.iter().zip(before) {
+        let to = locator.locate(k).cloned();
+        if from != to {
+            *transfers.entry((from, to)).or_insert(0) += 1;
+        }
+    }
+    let transfers = transfers
+        .into_iter()
+        .map(|((from, to), count)| (from, to, count))
+        .collect::<Vec<_>>();
+    MovementReport {
+        total: keys.len(),
+        moved: transfers.iter().map(|t| t.2).sum(),
+        transfers,
+    }
+}
+
+#[test]
+fn test_consistent_hash_ring() {
+    let mut ring = ConsistentHashRing::new(100);
+    assert_eq!(ring.node_for("key"), None);
+    for node in ["a", "b", "c", "d"].iter() {
+        ring.add_node(node.to_string());
+    }
+    let keys = (0..10_000).collect::<Vec<u32>>();
+    for node in ["a", "b", "c", "d"].iter() {
+        let share = keys.iter().filter(|k| ring.node_for(*k).unwrap() == node).count();
+        assert!(share > 1500 && share < 3500, "{} owns {}", node, share);
+    }
+
+    // keys only move to the new node
+    let report = key_movement(&mut ring, &keys, |r| r.add_node("e".to_string()));
+    assert!(report.fraction_moved() > 0.1 && report.fraction_moved() < 0.3);
+    assert!(report.transfers.iter().all(|t| t.1.as_deref() == Some("e")));
+
+    // and back from it
+    let report = key_movement(&mut ring, &keys, |r| assert!(r.remove_node(&"e".to_string())));
+    assert!(report.transfers.iter().all(|t| t.0.as_deref() == Some("e")));
+    assert!(!ring.remove_node(&"e".to_string()));
+
+    ring.add_weighted_node("a".to_string(), 4);
+    assert_eq!(ring.size(), 4);
+    let share = keys.iter().filter(|k| ring.node_for(*k).unwrap() == "a").count();
+    assert!(share > 4500, "weighted node owns {}", share);
+}
+
+#[test]
+fn test_rendezvous_hasher() {
+    let mut hasher = RendezvousHasher::new();
+    for node in 0..5u8 {
+        hasher.add_node(node);
+    }
+    let keys = (0..10_000).map(|i| format!("key-{}", i)).collect::<Vec<_>>();
+
+    let report = key_movement(&mut hasher, &keys, |h| assert!(h.remove_node(&2)));
+    assert!(report.transfers.iter().all(|t| t.0 == Some(2)));
+    assert!(report.fraction_moved() > 0.15 && report.fraction_moved() < 0.25);
+
+    let report = key_movement(&mut hasher, &keys, |h| h.add_weighted_node(9, 4.0));
+    assert!(report.transfers.iter().all(|t| t.1 == Some(9)));
+    // 4 / (4 + 4) of the keys
+    assert!(report.fraction_moved() > 0.45 && report.fraction_moved() < 0.55);
+}
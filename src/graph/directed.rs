@@ -2,6 +2,7 @@ use super::super::bag::Bag;
 use super::super::queue::Queue;
 use super::super::stack;
 use super::super::stack::Stack;
+use super::export::GraphExporter;
 use std::iter;
 
 #[derive(Clone, Debug)]
@@ -74,6 +75,17 @@ impl Digraph {
         dot
     }
 
+    /// Configurable DOT/GraphML export with labels, weights and highlighting.
+    pub fn exporter(&self) -> GraphExporter<'_> {
+        let mut exporter = GraphExporter::new(self.v, true);
+        for (v, adj) in self.adj.iter().enumerate() {
+            for &w in adj.iter() {
+                exporter.add_edge(v, w);
+            }
+        }
+        exporter
+    }
+
     pub fn adj(&self, v: usize) -> Vec<usize> {
         self.adj[v].iter().cloned().collect()
     }
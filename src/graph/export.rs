@@ -0,0 +1,428 @@
+use std::fmt::Write;
+
+type VertexFn<'a, R> = Box<dyn Fn(usize) -> R + 'a>;
+type EdgeFn<'a, R> = Box<dyn Fn(usize, usize) -> R + 'a>;
+
+/// An edge as seen by the exporter, with its built-in attributes.
+#[derive(Clone, Debug)]
+struct ExportEdge {
+    from: usize,
+    to: usize,
+    attrs: Vec<(String, String)>,
+}
+
+/// Configurable DOT and GraphML writer for `Graph`, `Digraph` and `SuffixTree`.
+///
+/// Vertices are numbered `0..v`. Labels, extra attributes and weights are supplied
+/// by closures, so analysis results (SCC ids, distances, ...) can be rendered directly.
+pub struct GraphExporter<'a> {
+    directed: bool,
+    v: usize,
+    edges: Vec<ExportEdge>,
+    name: String,
+    graph_attrs: Vec<(String, String)>,
+    vertex_base: Vec<Vec<(String, String)>>,
+    vertex_label: Option<VertexFn<'a, String>>,
+    vertex_attrs: Option<VertexFn<'a, Vec<(String, String)>>>,
+    edge_label: Option<EdgeFn<'a, String>>,
+    edge_attrs: Option<EdgeFn<'a, Vec<(String, String)>>>,
+    edge_weight: Option<EdgeFn<'a, f64>>,
+    highlighted: Vec<bool>,
+    highlighted_edges: Vec<(usize, usize)>,
+    highlight_color: String,
+}
+
+impl<'a> GraphExporter<'a> {
+    /// An exporter for `v` isolated vertices, edges are added with `add_edge`.
+    pub fn new(v: usize, directed: bool) -> GraphExporter<'a> {
+        GraphExporter {
+            directed,
+            v,
+            edges: Vec::new(),
+            name: "G".to_string(),
+            graph_attrs: Vec::new(),
+            vertex_base: vec![Vec::new(); v],
+            vertex_label: None,
+            vertex_attrs: None,
+            edge_label: None,
+            edge_attrs: None,
+            edge_weight: None,
+            highlighted: vec![false; v],
+            highlighted_edges: Vec::new(),
+            highlight_color: "red".to_string(),
+        }
+    }
+
+    fn validate_vertex(&self, v: usize) {
+        assert!(v < self.v, "vertex {} is not less than the number of vertices {}", v, self.v)
+    }
+
+    pub fn add_edge(&mut self, v: usize, w: usize) {
+        self.validate_vertex(v);
+        self.validate_vertex(w);
+        self.edges.push(ExportEdge {
+            from: v,
+            to: w,
+            attrs: Vec::new(),
+        });
+    }
+
+    /// Add an edge carrying its own attributes, used when they are not a function of `(v, w)`.
+    pub fn add_edge_with_attrs(&mut self, v: usize, w: usize, attrs: &[(&str, &str)]) {
+        self.add_edge(v, w);
+        self.edges.last_mut().unwrap().attrs = owned_attrs(attrs);
+    }
+
+    /// Set a built-in attribute of vertex `v`. A built-in `label` is overridden by `vertex_label`.
+    pub fn set_vertex_attr(&mut self, v: usize, key: &str, value: &str) {
+        self.validate_vertex(v);
+        self.vertex_base[v].push((key.to_string(), value.to_string()));
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Graph level attribute, such as `rankdir` in DOT.
+    pub fn graph_attr(mut self, key: &str, value: &str) -> Self {
+        self.graph_attrs.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn vertex_label<F: Fn(usize) -> String + 'a>(mut self, f: F) -> Self {
+        self.vertex_label = Some(Box::new(f));
+        self
+    }
+
+    pub fn vertex_attrs<F: Fn(usize) -> Vec<(String, String)> + 'a>(mut self, f: F) -> Self {
+        self.vertex_attrs = Some(Box::new(f));
+        self
+    }
+
+    pub fn edge_label<F: Fn(usize, usize) -> String + 'a>(mut self, f: F) -> Self {
+        self.edge_label = Some(Box::new(f));
+        self
+    }
+
+    pub fn edge_attrs<F: Fn(usize, usize) -> Vec<(String, String)> + 'a>(mut self, f: F) -> Self {
+        self.edge_attrs = Some(Box::new(f));
+        self
+    }
+
+    /// Weight of edge `v -> w`, shown as the edge label when no `edge_label` is set.
+    pub fn edge_weight<F: Fn(usize, usize) -> f64 + 'a>(mut self, f: F) -> Self {
+        self.edge_weight = Some(Box::new(f));
+        self
+    }
+
+    /// Highlight a set of vertices, e.g. one strongly-connected component.
+    pub fn highlight_vertices<I: IntoIterator<Item = usize>>(mut self, vs: I) -> Self {
+        for v in vs {
+            self.validate_vertex(v);
+            self.highlighted[v] = true;
+        }
+        self
+    }
+
+    /// Highlight the vertices of a path and the edges between consecutive vertices,
+    /// e.g. the result of `SearchPaths::path_to`.
+    pub fn highlight_path(mut self, path: &[usize]) -> Self {
+        for &v in path {
+            self.validate_vertex(v);
+            self.highlighted[v] = true;
+        }
+        for pair in path.windows(2) {
+            self.highlighted_edges.push((pair[0], pair[1]));
+        }
+        self
+    }
+
+    pub fn highlight_color(mut self, color: &str) -> Self {
+        self.highlight_color = color.to_string();
+        self
+    }
+
+    fn is_highlighted_edge(&self, e: &ExportEdge) -> bool {
+        self.highlighted_edges
+            .iter()
+            .any(|&(v, w)| (v == e.from && w == e.to) || (!self.directed && v == e.to && w == e.from))
+    }
+
+    fn vertex_attributes(&self, v: usize) -> Vec<(String, String)> {
+        let mut attrs = Vec::new();
+        if let Some(ref f) = self.vertex_label {
+            attrs.push(("label".to_string(), f(v)));
+        }
+        for (key, value) in &self.vertex_base[v] {
+            if key != "label" || self.vertex_label.is_none() {
+                attrs.push((key.clone(), value.clone()));
+            }
+        }
+        if self.highlighted[v] {
+            attrs.push(("color".to_string(), self.highlight_color.clone()));
+        }
+        if let Some(ref f) = self.vertex_attrs {
+            attrs.extend(f(v));
+        }
+        last_wins(attrs)
+    }
+
+    fn edge_attributes(&self, e: &ExportEdge) -> Vec<(String, String)> {
+        let mut attrs = Vec::new();
+        let has_label = e.attrs.iter().any(|(key, _)| key == "label");
+        if let Some(ref f) = self.edge_label {
+            attrs.push(("label".to_string(), f(e.from, e.to)));
+        } else if let (Some(f), false) = (self.edge_weight.as_ref(), has_label) {
+            attrs.push(("label".to_string(), f(e.from, e.to).to_string()));
+        }
+        for (key, value) in &e.attrs {
+            if key != "label" || self.edge_label.is_none() {
+                attrs.push((key.clone(), value.clone()));
+            }
+        }
+        if self.is_highlighted_edge(e) {
+            attrs.push(("color".to_string(), self.highlight_color.clone()));
+            attrs.push(("penwidth".to_string(), "2".to_string()));
+        }
+        if let Some(ref f) = self.edge_attrs {
+            attrs.extend(f(e.from, e.to));
+        }
+        last_wins(attrs)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+
+        writeln!(dot, "{} {} {{", kind, dot_id(&self.name)).unwrap();
+        for (key, value) in &self.graph_attrs {
+            writeln!(dot, "  {}={};", dot_id(key), dot_id(value)).unwrap();
+        }
+        for v in 0..self.v {
+            writeln!(dot, "  {}{};", v, dot_attr_list(&self.vertex_attributes(v))).unwrap();
+        }
+        for e in &self.edges {
+            writeln!(dot, "  {} {} {}{};", e.from, arrow, e.to, dot_attr_list(&self.edge_attributes(e))).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// GraphML with string attributes, plus the built-in `adivon:highlighted` flags and the
+    /// `adivon:weight` of edges. The built-in keys are namespaced, so attributes of any name
+    /// are exported next to them.
+    pub fn to_graphml(&self) -> String {
+        let vertex_attrs = (0..self.v).map(|v| self.vertex_attributes(v)).collect::<Vec<_>>();
+        let edge_attrs = self.edges.iter().map(|e| self.edge_attributes(e)).collect::<Vec<_>>();
+        let vertex_keys = attribute_keys(&vertex_attrs);
+        let edge_keys = attribute_keys(&edge_attrs);
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for key in &vertex_keys {
+            writeln!(xml, "  <key id=\"v_{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"string\"/>", xml_escape(key))
+                .unwrap();
+        }
+        // ids of attributes start with `v_` or `e_`, those of the built-in keys never do
+        xml.push_str(
+            "  <key id=\"adivon:v_highlighted\" for=\"node\" attr.name=\"adivon:highlighted\" attr.type=\"boolean\"/>\n",
+        );
+        for key in &edge_keys {
+            writeln!(xml, "  <key id=\"e_{0}\" for=\"edge\" attr.name=\"{0}\" attr.type=\"string\"/>", xml_escape(key))
+                .unwrap();
+        }
+        if self.edge_weight.is_some() {
+            xml.push_str(
+                "  <key id=\"adivon:e_weight\" for=\"edge\" attr.name=\"adivon:weight\" attr.type=\"double\"/>\n",
+            );
+        }
+        xml.push_str(
+            "  <key id=\"adivon:e_highlighted\" for=\"edge\" attr.name=\"adivon:highlighted\" attr.type=\"boolean\"/>\n",
+        );
+
+        let edgedefault = if self.directed { "directed" } else { "undirected" };
+        writeln!(xml, "  <graph id=\"{}\" edgedefault=\"{}\">", xml_escape(&self.name), edgedefault).unwrap();
+        for (v, attrs) in vertex_attrs.iter().enumerate() {
+            writeln!(xml, "    <node id=\"n{}\">", v).unwrap();
+            for (key, value) in attrs {
+                writeln!(xml, "      <data key=\"v_{}\">{}</data>", xml_escape(key), xml_escape(value)).unwrap();
+            }
+            writeln!(xml, "      <data key=\"adivon:v_highlighted\">{}</data>", self.highlighted[v]).unwrap();
+            xml.push_str("    </node>\n");
+        }
+        for (i, (e, attrs)) in self.edges.iter().zip(edge_attrs.iter()).enumerate() {
+            writeln!(xml, "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">", i, e.from, e.to).unwrap();
+            for (key, value) in attrs {
+                writeln!(xml, "      <data key=\"e_{}\">{}</data>", xml_escape(key), xml_escape(value)).unwrap();
+            }
+            if let Some(ref f) = self.edge_weight {
+                writeln!(xml, "      <data key=\"adivon:e_weight\">{}</data>", f(e.from, e.to)).unwrap();
+            }
+            writeln!(xml, "      <data key=\"adivon:e_highlighted\">{}</data>", self.is_highlighted_edge(e)).unwrap();
+            xml.push_str("    </edge>\n");
+        }
+        xml.push_str("  </graph>\n");
+        xml.push_str("</graphml>\n");
+        xml
+    }
+}
+
+fn owned_attrs(attrs: &[(&str, &str)]) -> Vec<(String, String)> {
+    attrs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+/// Attributes with distinct names, each where it first appears with the value it was last set to.
+fn last_wins(attrs: Vec<(String, String)>) -> Vec<(String, String)> {
+    let mut distinct: Vec<(String, String)> = Vec::with_capacity(attrs.len());
+    for (key, value) in attrs {
+        match distinct.iter_mut().find(|(k, _)| *k == key) {
+            Some(attr) => attr.1 = value,
+            None => distinct.push((key, value)),
+        }
+    }
+    distinct
+}
+
+/// Distinct attribute names in order of first appearance.
+fn attribute_keys(attrs: &[Vec<(String, String)>]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for (key, _) in attrs.iter().flatten() {
+        if !keys.contains(key) {
+            keys.push(key.clone());
+        }
+    }
+    keys
+}
+
+fn dot_id(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        s.to_string()
+    } else {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn dot_attr_list(attrs: &[(String, String)]) -> String {
+    if attrs.is_empty() {
+        String::new()
+    } else {
+        let items = attrs
+            .iter()
+            .map(|(k, v)| format!("{}={}", dot_id(k), dot_id(v)))
+            .collect::<Vec<_>>();
+        format!(" [{}]", items.join(", "))
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_exporter_dot() {
+    use super::Digraph;
+
+    let mut g = Digraph::new(4);
+    g.add_edge(0, 1);
+    g.add_edge(1, 2);
+    g.add_edge(2, 3);
+
+    let path = g.bfs(0).path_to(2).unwrap();
+    let dot = g
+        .exporter()
+        .vertex_label(|v| format!("v{}", v))
+        .edge_weight(|v, w| (v + w) as f64 / 2.0)
+        .highlight_path(&path)
+        .to_dot();
+
+    assert!(dot.starts_with("digraph G {\n"));
+    assert!(dot.contains("  0 [label=v0, color=red];\n"));
+    assert!(dot.contains("  3 [label=v3];\n"));
+    assert!(dot.contains("  0 -> 1 [label=\"0.5\", color=red, penwidth=2];\n"));
+    assert!(dot.contains("  2 -> 3 [label=\"2.5\"];\n"));
+}
+
+#[test]
+fn test_exporter_graphml() {
+    use super::Graph;
+
+    let mut g = Graph::new(3);
+    g.add_edge(0, 1);
+    g.add_edge(1, 2);
+    g.add_edge(2, 2);
+
+    let exporter = g
+        .exporter()
+        .vertex_label(|v| format!("<{}>", v))
+        .edge_weight(|_, _| 1.5)
+        .highlight_vertices(vec![2]);
+    let xml = exporter.to_graphml();
+
+    assert!(xml.contains("edgedefault=\"undirected\""));
+    assert!(xml.contains("<data key=\"v_label\">&lt;1&gt;</data>"));
+    assert!(xml.contains("<data key=\"adivon:e_weight\">1.5</data>"));
+    assert!(xml.contains("<data key=\"adivon:v_highlighted\">true</data>"));
+    // undirected edges are exported once, self-loops included
+    assert_eq!(xml.matches("<edge ").count(), 3);
+    assert_eq!(exporter.to_dot().matches(" -- ").count(), 3);
+}
+
+#[test]
+fn test_exporter_attribute_overrides() {
+    let mut exporter = GraphExporter::new(2, true)
+        .highlight_vertices(vec![0])
+        .vertex_attrs(|v| vec![("color".to_string(), "blue".to_string()); v + 1]);
+    exporter.set_vertex_attr(1, "color", "green");
+    exporter.add_edge_with_attrs(0, 1, &[("color", "gray"), ("style", "dashed")]);
+    let exporter = exporter
+        .highlight_path(&[0, 1])
+        .edge_attrs(|_, _| vec![("penwidth".to_string(), "3".to_string())]);
+
+    // the closures are applied last and win, each attribute is written once
+    let dot = exporter.to_dot();
+    assert!(dot.contains("  0 [color=blue];\n"));
+    assert!(dot.contains("  1 [color=blue];\n"));
+    assert!(dot.contains("  0 -> 1 [color=red, style=dashed, penwidth=3];\n"));
+    let xml = exporter.to_graphml();
+    assert_eq!(xml.matches("<data key=\"v_color\">").count(), 2);
+    assert_eq!(xml.matches("<data key=\"e_penwidth\">3</data>").count(), 1);
+}
+
+#[test]
+fn test_exporter_graphml_builtin_names() {
+    let mut exporter = GraphExporter::new(2, false)
+        .edge_weight(|_, _| 1.0)
+        .vertex_attrs(|_| vec![("highlighted".to_string(), "no".to_string())])
+        .edge_attrs(|_, _| vec![("weight".to_string(), "heavy".to_string())]);
+    exporter.add_edge(0, 1);
+    let xml = exporter.to_graphml();
+
+    // attributes named like the built-in keys get keys of their own
+    assert!(xml.contains("<key id=\"v_highlighted\" for=\"node\" attr.name=\"highlighted\""));
+    assert!(xml.contains("<key id=\"adivon:v_highlighted\" for=\"node\" attr.name=\"adivon:highlighted\""));
+    assert!(xml.contains("<data key=\"e_weight\">heavy</data>"));
+    assert!(xml.contains("<data key=\"adivon:e_weight\">1</data>"));
+}
+
+#[test]
+#[should_panic(expected = "vertex 0 is not less than the number of vertices 0")]
+fn test_exporter_no_vertices() {
+    GraphExporter::new(0, false).add_edge(0, 0);
+}
@@ -1,6 +1,8 @@
 pub mod directed;
+pub mod export;
 pub mod undirected;
 
 // reexports
 pub use self::directed::Digraph;
+pub use self::export::GraphExporter;
 pub use self::undirected::Graph;
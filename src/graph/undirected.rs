@@ -1,5 +1,6 @@
 use super::super::bag;
 use super::super::bag::Bag;
+use super::export::GraphExporter;
 use std::iter;
 
 #[derive(Clone, Debug)]
@@ -70,6 +71,25 @@ impl Graph {
         dot
     }
 
+    /// Configurable DOT/GraphML export, each undirected edge is written once.
+    pub fn exporter(&self) -> GraphExporter<'_> {
+        let mut exporter = GraphExporter::new(self.v, false);
+        for (v, adj) in self.adj.iter().enumerate() {
+            let mut self_loops = 0;
+            for &w in adj.iter() {
+                if v < w {
+                    exporter.add_edge(v, w);
+                } else if v == w {
+                    self_loops += 1;
+                }
+            }
+            for _ in 0..self_loops / 2 {
+                exporter.add_edge(v, v);
+            }
+        }
+        exporter
+    }
+
     pub fn adj(&self, v: usize) -> bag::Iter<usize> {
         self.adj[v].iter()
     }
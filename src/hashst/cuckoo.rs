@@ -0,0 +1,859 @@
+use super::DefaultHashBuilder;
+#[cfg(test)]
+use quickcheck::quickcheck;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Index, IndexMut};
+use std::slice;
+use std::vec;
+
+const BUCKET_SIZE: usize = 4;
+const INIT_BUCKETS: usize = 4;
+const STASH_SIZE: usize = 4;
+const MAX_KICKS: usize = 256;
+// failed rehashes at one size before the table grows anyway
+const REHASHES_PER_SIZE: usize = 8;
+// failed rehashes in one rebuild before keys overflow into the stash
+const MAX_REHASHES: usize = 4 * REHASHES_PER_SIZE;
+
+/// Counters of the work done by insertions into a `CuckooHashST`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CuckooStats {
+    /// keys evicted from their slot to make room for another
+    pub kicks: usize,
+    /// rebuilds with fresh hash functions after an insertion cycle
+    pub rehashes: usize,
+    /// rebuilds into a larger or smaller table
+    pub resizes: usize,
+    /// keys currently in the stash, more than `STASH_SIZE` once it overflows
+    pub stashed: usize,
+}
+
+/// Symbol table implementation with bucketized cuckoo hashing
+///
+/// Each key lives in one of the `BUCKET_SIZE` slots of one of its `d`
+/// candidate buckets, or in a small stash, so a lookup probes at most
+/// `d * BUCKET_SIZE + STASH_SIZE` slots. Insertion evicts keys to their
+/// alternative buckets, and rebuilds with new hash functions on a cycle.
+///
+/// When rebuilds keep failing, as with a hasher that maps many keys to the
+/// same value, the stash grows past `STASH_SIZE` instead and lookups scan it
+/// until a later resize places its keys in the table.
+pub struct CuckooHashST<K, V, S = DefaultHashBuilder> {
+    table: Vec<Option<(K, V)>>,
+    stash: Vec<(K, V)>,
+    // one seed per hash function
+    seeds: Vec<u64>,
+    n: usize,
+    hash_builder: S,
+    rng: StdRng,
+    stats: CuckooStats,
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher + Default> Default for CuckooHashST<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V> CuckooHashST<K, V> {
+    pub fn new() -> CuckooHashST<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// create a table holding `capacity` keys without resizing
+    pub fn with_capacity(capacity: usize) -> CuckooHashST<K, V> {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> CuckooHashST<K, V, S> {
+    /// create a table using `hash_builder` to hash keys
+    pub fn with_hasher(hash_builder: S) -> CuckooHashST<K, V, S> {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> CuckooHashST<K, V, S> {
+        Self::with_hash_functions(2, capacity, hash_builder)
+    }
+
+    /// create a table where each key has `d` candidate buckets
+    pub fn with_hash_functions(d: usize, capacity: usize, hash_builder: S) -> CuckooHashST<K, V, S> {
+        assert!(d >= 2, "cuckoo hashing needs at least 2 hash functions");
+        let buckets = INIT_BUCKETS.max((capacity * 10 / 9) / BUCKET_SIZE + 1);
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        CuckooHashST {
+            table: (0..buckets * BUCKET_SIZE).map(|_| None).collect(),
+            stash: Vec::with_capacity(STASH_SIZE),
+            seeds: (0..d).map(|_| rng.gen()).collect(),
+            n: 0,
+            hash_builder,
+            rng,
+            stats: CuckooStats::default(),
+        }
+    }
+
+    #[inline]
+    fn buckets(&self) -> usize {
+        self.table.len() / BUCKET_SIZE
+    }
+
+    // first slot of the bucket of `key` under the `j`-th hash function
+    #[inline]
+    fn bucket(&self, j: usize, key: &K) -> usize {
+        let h = self.hash_builder.hash_one((self.seeds[j], key));
+        (h % self.buckets() as u64) as usize * BUCKET_SIZE
+    }
+
+    // slot of `key`, where indices past the table point into the stash
+    fn find(&self, key: &K) -> Option<usize> {
+        for j in 0..self.seeds.len() {
+            let b = self.bucket(j, key);
+            for i in b..b + BUCKET_SIZE {
+                if let Some((ref k, _)) = self.table[i] {
+                    if k == key {
+                        return Some(i);
+                    }
+                }
+            }
+        }
+        self.stash
+            .iter()
+            .position(|(k, _)| k == key)
+            .map(|i| self.table.len() + i)
+    }
+
+    fn slot(&self, i: usize) -> Option<&(K, V)> {
+        match self.table.get(i) {
+            Some(slot) => slot.as_ref(),
+            None => self.stash.get(i - self.table.len()),
+        }
+    }
+
+    fn slot_mut(&mut self, i: usize) -> Option<&mut (K, V)> {
+        let m = self.table.len();
+        match self.table.get_mut(i) {
+            Some(slot) => slot.as_mut(),
+            None => self.stash.get_mut(i - m),
+        }
+    }
+
+    // a free slot in one of the buckets of `key`
+    fn free_slot(&self, key: &K) -> Option<usize> {
+        (0..self.seeds.len())
+            .map(|j| self.bucket(j, key))
+            .flat_map(|b| b..b + BUCKET_SIZE)
+            .find(|&i| self.table[i].is_none())
+    }
+
+    // place an absent key by random-walk eviction, falling back to the stash. One key
+    // is followed through the evictions: the key at slot `followed`, or the one being
+    // placed if that is `None`. Returns the followed key's slot; on failure the key
+    // left homeless is handed back, with the followed key's slot unless it is the homeless one
+    fn place(&mut self, item: (K, V), followed: Option<usize>) -> Result<usize, (Option<usize>, (K, V))> {
+        let mut item = item;
+        let mut followed = followed;
+        for _ in 0..MAX_KICKS {
+            if let Some(i) = self.free_slot(&item.0) {
+                self.table[i] = Some(item);
+                return Ok(followed.unwrap_or(i));
+            }
+            let j = self.rng.gen_range(0, self.seeds.len());
+            let i = self.bucket(j, &item.0) + self.rng.gen_range(0, BUCKET_SIZE);
+            item = self.table[i].replace(item).unwrap();
+            // the followed key is now the one carried, or was just put down at `i`
+            followed = if followed == Some(i) {
+                None
+            } else {
+                followed.or(Some(i))
+            };
+            self.stats.kicks += 1;
+        }
+        if self.stash.len() < STASH_SIZE {
+            self.stash.push(item);
+            Ok(followed.unwrap_or(self.table.len() + self.stash.len() - 1))
+        } else {
+            Err((followed, item))
+        }
+    }
+
+    // rebuild into `buckets` buckets with fresh hash functions, returning the slot of
+    // the last of `items`. Growing did not help if the last attempt is reached, it
+    // goes back to `buckets` and overflows the stash rather than fail
+    fn rebuild(&mut self, buckets: usize, items: Vec<(K, V)>) -> usize {
+        let requested = buckets;
+        let mut buckets = buckets;
+        let mut items = items;
+        for attempt in 0..MAX_REHASHES {
+            for seed in self.seeds.iter_mut() {
+                *seed = self.rng.gen();
+            }
+            self.table = (0..buckets * BUCKET_SIZE).map(|_| None).collect();
+            let mut followed = None;
+            let mut failed = None;
+            while let Some(item) = items.pop() {
+                match self.place(item, followed) {
+                    Ok(i) => followed = Some(i),
+                    Err(e) => {
+                        failed = Some(e);
+                        break;
+                    }
+                }
+            }
+            match failed {
+                None => return followed.unwrap_or(0),
+                Some((followed, homeless)) => {
+                    let placed = self.take_all_following(followed, homeless);
+                    items.extend(placed);
+                    self.stats.rehashes += 1;
+                    if (attempt + 1) % REHASHES_PER_SIZE == 0 {
+                        buckets *= 2;
+                    }
+                }
+            }
+        }
+
+        self.table = (0..requested * BUCKET_SIZE).map(|_| None).collect();
+        let mut followed = None;
+        while let Some(item) = items.pop() {
+            match self.place(item, followed) {
+                Ok(i) => followed = Some(i),
+                Err((f, homeless)) => {
+                    self.stash.push(homeless);
+                    followed = f.or(Some(self.table.len() + self.stash.len() - 1));
+                    for item in items.drain(..) {
+                        self.overflow(item);
+                    }
+                }
+            }
+        }
+        followed.unwrap_or(0)
+    }
+
+    // place an absent key in a free slot of its buckets or else the stash, without
+    // evictions, and return its slot
+    fn overflow(&mut self, item: (K, V)) -> usize {
+        match self.free_slot(&item.0) {
+            Some(i) => {
+                self.table[i] = Some(item);
+                i
+            }
+            None => {
+                self.stash.push(item);
+                self.table.len() + self.stash.len() - 1
+            }
+        }
+    }
+
+    // empty the table after a failed `place`, with the followed key last
+    fn take_all_following(&mut self, followed: Option<usize>, homeless: (K, V)) -> Vec<(K, V)> {
+        let followed = match followed {
+            Some(i) if i < self.table.len() => self.table[i].take(),
+            Some(i) => Some(self.stash.swap_remove(i - self.table.len())),
+            None => None,
+        };
+        let mut items = self.take_all();
+        items.push(homeless);
+        items.extend(followed);
+        items
+    }
+
+    fn take_all(&mut self) -> Vec<(K, V)> {
+        let mut items = self.stash.drain(..).collect::<Vec<_>>();
+        items.extend(self.table.iter_mut().filter_map(Option::take));
+        items
+    }
+
+    fn resize(&mut self, buckets: usize) {
+        self.stats.resizes += 1;
+        let items = self.take_all();
+        self.rebuild(buckets, items);
+    }
+
+    pub fn get<T: Borrow<K>>(&self, key: T) -> Option<&V> {
+        self.find(key.borrow()).and_then(|i| self.slot(i)).map(|(_, v)| v)
+    }
+
+    pub fn get_mut<T: Borrow<K>>(&mut self, key: T) -> Option<&mut V> {
+        match self.find(key.borrow()) {
+            Some(i) => self.slot_mut(i).map(|(_, v)| v),
+            None => None,
+        }
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        match self.get_mut(&key) {
+            Some(v) => *v = val,
+            None => {
+                self.insert((key, val));
+            }
+        }
+    }
+
+    // insert an absent key and return its slot
+    fn insert(&mut self, item: (K, V)) -> usize {
+        // keep the load factor under 90%
+        if (self.n + 1) * 10 > self.table.len() * 9 {
+            let buckets = self.buckets();
+            self.resize(2 * buckets);
+        }
+        self.n += 1;
+        if self.stash.len() > STASH_SIZE {
+            // the last rebuild overflowed, evictions and rebuilds would fail the same way
+            return self.overflow(item);
+        }
+        match self.place(item, None) {
+            Ok(i) => i,
+            Err((followed, homeless)) => {
+                self.stats.rehashes += 1;
+                let items = self.take_all_following(followed, homeless);
+                let buckets = self.buckets();
+                self.rebuild(buckets, items)
+            }
+        }
+    }
+
+    /// remove a key from the table, returning its value
+    pub fn delete(&mut self, key: &K) -> Option<V> {
+        let i = self.find(key)?;
+        let removed = match self.table.get_mut(i) {
+            Some(slot) => slot.take().map(|(_, v)| v),
+            None => Some(self.stash.swap_remove(i - self.table.len()).1),
+        };
+        self.n -= 1;
+
+        // move stashed keys back into the table when there is room
+        let mut s = 0;
+        while s < self.stash.len() {
+            match self.free_slot(&self.stash[s].0) {
+                Some(i) => self.table[i] = Some(self.stash.swap_remove(s)),
+                None => s += 1,
+            }
+        }
+
+        let buckets = self.buckets();
+        if buckets > INIT_BUCKETS && self.n <= self.table.len() / 8 {
+            self.resize(buckets / 2);
+        }
+        removed
+    }
+
+    /// get the entry of a key for in-place manipulation
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        match self.find(&key) {
+            Some(i) => Entry::Occupied(OccupiedEntry {
+                item: self.slot_mut(i).unwrap(),
+            }),
+            None => Entry::Vacant(VacantEntry { table: self, key }),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// number of slots in the table, not counting the stash
+    pub fn capacity(&self) -> usize {
+        self.table.len()
+    }
+
+    /// number of hash functions
+    pub fn hash_functions(&self) -> usize {
+        self.seeds.len()
+    }
+
+    /// instrumentation of kicks, rehashes and resizes so far
+    pub fn stats(&self) -> CuckooStats {
+        CuckooStats {
+            stashed: self.stash.len(),
+            ..self.stats
+        }
+    }
+}
+
+impl<K, V, S> CuckooHashST<K, V, S> {
+    /// iterate over key-value pairs in arbitrary order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            table: self.table.iter(),
+            stash: self.stash.iter(),
+            remaining: self.n,
+        }
+    }
+
+    /// iterate over key-value pairs, with mutable values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            table: self.table.iter_mut(),
+            stash: self.stash.iter_mut(),
+            remaining: self.n,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// remove all key-value pairs, keeping the allocated table
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let remaining = mem::replace(&mut self.n, 0);
+        Drain {
+            table: self.table.iter_mut(),
+            stash: self.stash.drain(..),
+            remaining,
+        }
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> Index<K> for CuckooHashST<K, V, S> {
+    type Output = V;
+    fn index(&self, index: K) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> IndexMut<K> for CuckooHashST<K, V, S> {
+    fn index_mut(&mut self, index: K) -> &mut V {
+        self.get_mut(index).expect("key not exists")
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher + Default> FromIterator<(K, V)> for CuckooHashST<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut st = CuckooHashST::default();
+        st.extend(iter);
+        st
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> Extend<(K, V)> for CuckooHashST<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+/// A view into a single entry of a `CuckooHashST`, which may be vacant or occupied
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    item: &'a mut (K, V),
+}
+
+pub struct VacantEntry<'a, K, V, S> {
+    table: &'a mut CuckooHashST<K, V, S>,
+    key: K,
+}
+
+impl<'a, K: Hash + PartialEq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref e) => e.key(),
+            Entry::Vacant(ref e) => e.key(),
+        }
+    }
+
+    /// insert `default` if the entry is vacant, and return the value
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// insert the result of `default` if the entry is vacant, and return the value
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// modify the value in place if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(e) => {
+                f(&mut e.item.1);
+                Entry::Occupied(e)
+            }
+            vacant => vacant,
+        }
+    }
+}
+
+impl<'a, K: Hash + PartialEq, V: Default, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.item.0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.item.1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.item.1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.item.1
+    }
+
+    /// replace the value, returning the old one
+    pub fn insert(&mut self, val: V) -> V {
+        mem::replace(&mut self.item.1, val)
+    }
+}
+
+impl<'a, K: Hash + PartialEq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn insert(self, val: V) -> &'a mut V {
+        let table = self.table;
+        let i = table.insert((self.key, val));
+        &mut table.slot_mut(i).unwrap().1
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    table: slice::Iter<'a, Option<(K, V)>>,
+    stash: slice::Iter<'a, (K, V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = match self.table.find_map(Option::as_ref) {
+            Some(item) => item,
+            None => self.stash.next()?,
+        };
+        self.remaining -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    table: slice::IterMut<'a, Option<(K, V)>>,
+    stash: slice::IterMut<'a, (K, V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = match self.table.find_map(Option::as_mut) {
+            Some(item) => item,
+            None => self.stash.next()?,
+        };
+        self.remaining -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {}
+
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct IntoIter<K, V> {
+    table: vec::IntoIter<Option<(K, V)>>,
+    stash: vec::IntoIter<(K, V)>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let item = match self.table.find_map(|slot| slot) {
+            Some(item) => item,
+            None => self.stash.next()?,
+        };
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+/// Draining iterator; the table is empty once it is dropped
+pub struct Drain<'a, K, V> {
+    table: slice::IterMut<'a, Option<(K, V)>>,
+    stash: vec::Drain<'a, (K, V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Drain<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let item = match self.table.find_map(Option::take) {
+            Some(item) => item,
+            None => self.stash.next()?,
+        };
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Drain<'a, K, V> {}
+
+impl<'a, K, V> Drop for Drain<'a, K, V> {
+    fn drop(&mut self) {
+        for slot in &mut self.table {
+            slot.take();
+        }
+    }
+}
+
+impl<K, V, S> IntoIterator for CuckooHashST<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            table: self.table.into_iter(),
+            stash: self.stash.into_iter(),
+            remaining: self.n,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a CuckooHashST<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut CuckooHashST<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[test]
+fn test_cuckoo_hash_st() {
+    let mut m: CuckooHashST<u32, u32> = (0..10_000).map(|i| (i, i + 1)).collect();
+    assert_eq!(m.size(), 10_000);
+    assert!((0..10_000).all(|i| m[i] == i + 1));
+    assert_eq!(m.get(10_000), None);
+    assert_eq!(m.iter().count(), 10_000);
+
+    let stats = m.stats();
+    assert!(stats.kicks > 0 && stats.resizes > 0);
+    assert!(stats.stashed <= STASH_SIZE);
+    assert!(m.capacity() * 9 >= 10_000 * 10);
+
+    m[42] = 0;
+    assert_eq!(m.delete(&42), Some(0));
+    assert_eq!(m.delete(&42), None);
+    for i in 0..9_990 {
+        m.delete(&i);
+    }
+    assert_eq!(m.size(), 10);
+    assert!(m.capacity() <= 1024);
+    assert_eq!(m.get(9_999), Some(&10_000));
+
+    let mut m = CuckooHashST::with_hash_functions(3, 100, DefaultHashBuilder::default());
+    for w in "it was the best of times it was the worst of times".split(' ') {
+        let n = m.get(w).map_or(1, |n| n + 1);
+        m.put(w, n);
+    }
+    assert_eq!(m.hash_functions(), 3);
+    assert_eq!(m.size(), 7);
+    assert_eq!(m["times"], 2);
+}
+
+#[test]
+fn test_cuckoo_hash_st_entry_and_iter() {
+    let text = "it was the best of times it was the worst of times";
+    let mut freq: CuckooHashST<&str, usize> = text.split(' ').map(|w| (w, 0)).collect();
+    for w in text.split(' ') {
+        *freq.entry(w).or_insert(0) += 1;
+    }
+    freq.entry("age").and_modify(|c| *c += 1).or_insert_with(|| 42);
+    freq.entry("it").and_modify(|c| *c *= 10).or_default();
+    assert_eq!(freq.size(), 8);
+    assert_eq!(freq["it"], 20);
+    assert_eq!(freq["age"], 42);
+
+    freq["times"] = 0;
+    for (_, c) in freq.iter_mut() {
+        *c += 1;
+    }
+    assert_eq!(freq.values().sum::<usize>(), 21 + 3 + 3 + 2 + 3 + 1 + 2 + 43);
+    let mut keys = freq.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, vec!["age", "best", "it", "of", "the", "times", "was", "worst"]);
+
+    assert_eq!(freq.delete(&"age"), Some(43));
+    assert_eq!(freq.iter().len(), 7);
+
+    let drained = freq.drain().take(3).count();
+    assert_eq!(drained, 3);
+    assert!(freq.is_empty() && freq.iter().next().is_none());
+
+    freq.extend(vec![("a", 1), ("b", 2), ("a", 3)]);
+    let mut pairs = freq.into_iter().collect::<Vec<_>>();
+    pairs.sort();
+    assert_eq!(pairs, vec![("a", 3), ("b", 2)]);
+}
+
+#[test]
+fn test_cuckoo_hash_st_colliding_hasher() {
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn write(&mut self, _: &[u8]) {}
+
+        fn finish(&self) -> u64 {
+            0
+        }
+    }
+
+    // every key has the same buckets, the keys past them overflow the stash
+    let mut m = CuckooHashST::<u32, u32, BuildHasherDefault<Collide>>::default();
+    for i in 0..100 {
+        *m.entry(i).or_insert(0) += i + 1;
+    }
+    assert_eq!(m.size(), 100);
+    assert!((0..100).all(|i| m[i] == i + 1));
+    assert!(m.stats().stashed > STASH_SIZE);
+
+    for i in 0..95 {
+        assert_eq!(m.delete(&i), Some(i + 1));
+    }
+    assert_eq!(m.size(), 5);
+    assert!((95..100).all(|i| m[i] == i + 1));
+}
+
+#[test]
+fn quicktest_cuckoo_hash_st() {
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // few distinct hashes, so placements fail and rebuilds overflow the stash
+    #[derive(Default)]
+    struct Weak(u64);
+
+    impl Hasher for Weak {
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(b as u64);
+            }
+        }
+
+        fn finish(&self) -> u64 {
+            self.0 % 8
+        }
+    }
+
+    fn check<S: BuildHasher + Default>(ops: &[(bool, u16, u32)]) -> bool {
+        let mut m = CuckooHashST::<u16, u32, S>::default();
+        let mut model = HashMap::new();
+        for &(put, k, v) in ops {
+            // a narrow key space so deletes hit
+            let k = k % 512;
+            if put {
+                // the value an entry hands back must be the key's own
+                if v % 2 == 0 {
+                    m.put(k, v);
+                } else {
+                    *m.entry(k).or_insert(0) = v;
+                }
+                model.insert(k, v);
+            } else if m.delete(&k) != model.remove(&k) {
+                return false;
+            }
+        }
+        m.size() == model.len() && model.iter().all(|(k, v)| m.get(k) == Some(v))
+    }
+
+    fn prop(ops: Vec<(bool, u16, u32)>) -> bool {
+        check::<DefaultHashBuilder>(&ops) && check::<BuildHasherDefault<Weak>>(&ops)
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u16, u32)>) -> bool);
+}
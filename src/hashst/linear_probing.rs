@@ -0,0 +1,186 @@
+use super::DefaultHashBuilder;
+#[cfg(test)]
+use quickcheck::quickcheck;
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::ops::Index;
+
+const INIT_CAPACITY: usize = 16;
+
+/// Symbol table implementation with linear probing
+///
+/// Keys and values live inline in one array, kept at most half full, so a
+/// lookup usually touches a single cache line.
+pub struct LinearProbingHashST<K, V, S = DefaultHashBuilder> {
+    st: Vec<Option<(K, V)>>,
+    n: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher + Default> Default for LinearProbingHashST<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V> LinearProbingHashST<K, V> {
+    pub fn new() -> LinearProbingHashST<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// create a table holding `capacity` keys without resizing
+    pub fn with_capacity(capacity: usize) -> LinearProbingHashST<K, V> {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> LinearProbingHashST<K, V, S> {
+    /// create a table using `hash_builder` to hash keys
+    pub fn with_hasher(hash_builder: S) -> LinearProbingHashST<K, V, S> {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> LinearProbingHashST<K, V, S> {
+        let m = INIT_CAPACITY.max(2 * capacity + 1);
+        let st = (0..m).map(|_| None).collect();
+        LinearProbingHashST { st, n: 0, hash_builder }
+    }
+
+    fn hash(&self, key: &K) -> usize {
+        (self.hash_builder.hash_one(key) % self.st.len() as u64) as usize
+    }
+
+    // rehash all keys into a table of size `m`
+    fn resize(&mut self, m: usize) {
+        let st = (0..m).map(|_| None).collect();
+        let old = mem::replace(&mut self.st, st);
+        for (key, val) in old.into_iter().flatten() {
+            let i = self.probe(&key);
+            self.st[i] = Some((key, val));
+        }
+    }
+
+    // slot holding `key`, or the empty slot ending its cluster
+    fn probe(&self, key: &K) -> usize {
+        let m = self.st.len();
+        let mut i = self.hash(key);
+        while let Some((ref k, _)) = self.st[i] {
+            if k == key {
+                break;
+            }
+            i = (i + 1) % m;
+        }
+        i
+    }
+
+    pub fn get<T: Borrow<K>>(&self, key: T) -> Option<&V> {
+        let i = self.probe(key.borrow());
+        self.st[i].as_ref().map(|(_, v)| v)
+    }
+
+    pub fn get_mut<T: Borrow<K>>(&mut self, key: T) -> Option<&mut V> {
+        let i = self.probe(key.borrow());
+        self.st[i].as_mut().map(|(_, v)| v)
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        // double table size if 50% full
+        if self.n >= self.st.len() / 2 {
+            let m = self.st.len();
+            self.resize(2 * m);
+        }
+
+        let i = self.probe(&key);
+        if self.st[i].is_none() {
+            self.n += 1;
+        }
+        self.st[i] = Some((key, val));
+    }
+
+    /// remove a key from the table, returning its value
+    pub fn delete(&mut self, key: &K) -> Option<V> {
+        let m = self.st.len();
+        let mut i = self.probe(key);
+        let (_, removed) = self.st[i].take()?;
+        self.n -= 1;
+
+        // rehash the rest of the cluster, so no key is cut off from its hash slot
+        i = (i + 1) % m;
+        while let Some((k, v)) = self.st[i].take() {
+            let j = self.probe(&k);
+            self.st[j] = Some((k, v));
+            i = (i + 1) % m;
+        }
+
+        // halve table size if it's 12.5% full or less
+        if m > INIT_CAPACITY && self.n <= m / 8 {
+            self.resize(m / 2);
+        }
+        Some(removed)
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// number of slots in the table
+    pub fn capacity(&self) -> usize {
+        self.st.len()
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> Index<K> for LinearProbingHashST<K, V, S> {
+    type Output = V;
+    fn index(&self, index: K) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+#[test]
+fn test_linear_probing_hash_st() {
+    let mut m = LinearProbingHashST::new();
+    for (i, w) in "SEARCHEXAMPLE".chars().enumerate() {
+        m.put(w, i);
+    }
+    assert_eq!(m.size(), 10);
+    assert_eq!(m.get('E'), Some(&12));
+    assert_eq!(m.get('Z'), None);
+
+    assert_eq!(m.delete(&'E'), Some(12));
+    assert_eq!(m.delete(&'Z'), None);
+    assert_eq!(m.size(), 9);
+    assert_eq!(m.get('E'), None);
+
+    if let Some(v) = m.get_mut('S') {
+        *v = 100;
+    }
+    assert_eq!(m['S'], 100);
+    assert_eq!(m['P'], 10);
+}
+
+#[test]
+fn quicktest_linear_probing_hash_st() {
+    use std::collections::HashMap;
+
+    fn prop(ops: Vec<(bool, u8, u32)>) -> bool {
+        let mut m = LinearProbingHashST::new();
+        let mut model = HashMap::new();
+        for &(put, k, v) in ops.iter() {
+            if put {
+                m.put(k, v);
+                model.insert(k, v);
+            } else {
+                m.delete(&k);
+                model.remove(&k);
+            }
+        }
+        m.size() == model.len() && (0..=255u8).all(|k| m.get(k) == model.get(&k))
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u32)>) -> bool);
+}
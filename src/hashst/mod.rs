@@ -0,0 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+
+pub mod cuckoo;
+pub mod linear_probing;
+pub mod robin_hood;
+pub mod separate_chaining;
+
+// reexports
+pub use self::cuckoo::{CuckooHashST, CuckooStats};
+pub use self::linear_probing::LinearProbingHashST;
+pub use self::robin_hood::RobinHoodHashST;
+pub use self::separate_chaining::HashST;
+
+/// Hasher used by the tables in this module unless another `BuildHasher` is given.
+pub type DefaultHashBuilder = BuildHasherDefault<DefaultHasher>;
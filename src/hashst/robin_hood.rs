@@ -0,0 +1,248 @@
+use super::DefaultHashBuilder;
+#[cfg(test)]
+use quickcheck::quickcheck;
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::ops::Index;
+
+const INIT_CAPACITY: usize = 16;
+
+struct Bucket<K, V> {
+    hash: u64,
+    key: K,
+    val: V,
+}
+
+/// Symbol table implementation with Robin Hood hashing
+///
+/// Linear probing where an inserted key displaces any key closer to its home
+/// slot, which keeps probe lengths even at a load factor of 90%. Deletion
+/// shifts the following keys back instead of leaving tombstones.
+pub struct RobinHoodHashST<K, V, S = DefaultHashBuilder> {
+    // the size is a power of two
+    st: Vec<Option<Bucket<K, V>>>,
+    n: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher + Default> Default for RobinHoodHashST<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V> RobinHoodHashST<K, V> {
+    pub fn new() -> RobinHoodHashST<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// create a table holding `capacity` keys without resizing
+    pub fn with_capacity(capacity: usize) -> RobinHoodHashST<K, V> {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> RobinHoodHashST<K, V, S> {
+    /// create a table using `hash_builder` to hash keys
+    pub fn with_hasher(hash_builder: S) -> RobinHoodHashST<K, V, S> {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> RobinHoodHashST<K, V, S> {
+        let m = INIT_CAPACITY.max((capacity * 10 / 9 + 1).next_power_of_two());
+        let st = (0..m).map(|_| None).collect();
+        RobinHoodHashST { st, n: 0, hash_builder }
+    }
+
+    #[inline]
+    fn mask(&self) -> usize {
+        self.st.len() - 1
+    }
+
+    // distance of slot `i` from the home slot of `hash`
+    #[inline]
+    fn probe_distance(&self, hash: u64, i: usize) -> usize {
+        i.wrapping_sub(hash as usize) & self.mask()
+    }
+
+    fn resize(&mut self, m: usize) {
+        let st = (0..m).map(|_| None).collect();
+        let old = mem::replace(&mut self.st, st);
+        for bucket in old.into_iter().flatten() {
+            self.insert_new(bucket);
+        }
+    }
+
+    // insert a bucket whose key is known to be absent
+    fn insert_new(&mut self, bucket: Bucket<K, V>) {
+        let mask = self.mask();
+        let mut bucket = bucket;
+        let mut i = bucket.hash as usize & mask;
+        let mut dist = 0;
+        loop {
+            match self.st[i] {
+                None => {
+                    self.st[i] = Some(bucket);
+                    return;
+                }
+                Some(ref mut b) => {
+                    let d = i.wrapping_sub(b.hash as usize) & mask;
+                    if d < dist {
+                        // take from the rich
+                        mem::swap(b, &mut bucket);
+                        dist = d;
+                    }
+                }
+            }
+            i = (i + 1) & mask;
+            dist += 1;
+        }
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        let hash = self.hash_builder.hash_one(key);
+        let mut i = hash as usize & self.mask();
+        let mut dist = 0;
+        while let Some(ref b) = self.st[i] {
+            // the key would have displaced this one
+            if self.probe_distance(b.hash, i) < dist {
+                return None;
+            }
+            if b.hash == hash && b.key == *key {
+                return Some(i);
+            }
+            i = (i + 1) & self.mask();
+            dist += 1;
+        }
+        None
+    }
+
+    pub fn get<T: Borrow<K>>(&self, key: T) -> Option<&V> {
+        self.find(key.borrow())
+            .and_then(|i| self.st[i].as_ref())
+            .map(|b| &b.val)
+    }
+
+    pub fn get_mut<T: Borrow<K>>(&mut self, key: T) -> Option<&mut V> {
+        match self.find(key.borrow()) {
+            Some(i) => self.st[i].as_mut().map(|b| &mut b.val),
+            None => None,
+        }
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        if let Some(i) = self.find(&key) {
+            self.st[i].as_mut().unwrap().val = val;
+            return;
+        }
+        // keep the load factor under 90%
+        if (self.n + 1) * 10 > self.st.len() * 9 {
+            let m = self.st.len();
+            self.resize(2 * m);
+        }
+        let hash = self.hash_builder.hash_one(&key);
+        self.insert_new(Bucket { hash, key, val });
+        self.n += 1;
+    }
+
+    /// remove a key from the table, returning its value
+    pub fn delete(&mut self, key: &K) -> Option<V> {
+        let mut i = self.find(key)?;
+        let removed = self.st[i].take().map(|b| b.val);
+        self.n -= 1;
+
+        // backward shift the following keys until one is at its home slot
+        let mask = self.mask();
+        let mut j = (i + 1) & mask;
+        while self.st[j].as_ref().is_some_and(|b| self.probe_distance(b.hash, j) > 0) {
+            self.st[i] = self.st[j].take();
+            i = j;
+            j = (j + 1) & mask;
+        }
+
+        let m = self.st.len();
+        if m > INIT_CAPACITY && self.n <= m / 8 {
+            self.resize(m / 2);
+        }
+        removed
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// number of slots in the table
+    pub fn capacity(&self) -> usize {
+        self.st.len()
+    }
+
+    /// longest distance of a key from its home slot
+    pub fn max_probe_distance(&self) -> usize {
+        self.st
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| b.as_ref().map(|b| self.probe_distance(b.hash, i)))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> Index<K> for RobinHoodHashST<K, V, S> {
+    type Output = V;
+    fn index(&self, index: K) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+#[test]
+fn test_robin_hood_hash_st() {
+    let mut m = RobinHoodHashST::new();
+    for i in 0..1000 {
+        m.put(i, i.to_string());
+    }
+    m.put(7, "seven".to_string());
+    assert_eq!(m.size(), 1000);
+    assert!(m.capacity() * 9 >= 1000 * 10);
+    assert_eq!(m[7], "seven");
+    assert_eq!(m.get(999), Some(&"999".to_string()));
+    assert_eq!(m.get(1000), None);
+    assert!(m.max_probe_distance() < 64);
+
+    for i in (0..1000).filter(|i| i % 10 != 0) {
+        m.delete(&i);
+    }
+    assert_eq!(m.delete(&1), None);
+    assert_eq!(m.size(), 100);
+    assert!(m.capacity() <= 512);
+    assert!((0..1000).all(|i| m.get(i).is_some() == (i % 10 == 0)));
+
+    m.get_mut(10).unwrap().push('!');
+    assert_eq!(m[10], "10!");
+}
+
+#[test]
+fn quicktest_robin_hood_hash_st() {
+    use std::collections::HashMap;
+
+    fn prop(ops: Vec<(bool, u8, u32)>) -> bool {
+        let mut m = RobinHoodHashST::new();
+        let mut model = HashMap::new();
+        for &(put, k, v) in ops.iter() {
+            if put {
+                m.put(k, v);
+                model.insert(k, v);
+            } else {
+                m.delete(&k);
+                model.remove(&k);
+            }
+        }
+        m.size() == model.len() && (0..=255u8).all(|k| m.get(k) == model.get(&k))
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u32)>) -> bool);
+}
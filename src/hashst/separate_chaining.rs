@@ -0,0 +1,671 @@
+use super::DefaultHashBuilder;
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+#[cfg(test)]
+use std::hash::{BuildHasherDefault, Hasher};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Index, IndexMut};
+use std::slice;
+use std::vec;
+
+struct Node<K, V> {
+    key: K,
+    val: V,
+    next: Option<Box<Node<K, V>>>,
+}
+
+fn delete<K: PartialEq, V>(x: Option<Box<Node<K, V>>>, key: &K, removed: &mut Option<V>) -> Option<Box<Node<K, V>>> {
+    if let Some(mut x) = x {
+        let next = x.next.take();
+        if x.key == *key {
+            *removed = Some(x.val);
+            next // this will drop x
+        } else {
+            x.next = delete(next, key, removed);
+            Some(x)
+        }
+    } else {
+        None
+    }
+}
+
+// detach the node with the key from a chain
+fn unlink<K: PartialEq, V>(chain: &mut Option<Box<Node<K, V>>>, key: &K) -> Option<Box<Node<K, V>>> {
+    let mut x = chain;
+    while x.as_ref().is_some_and(|n| n.key != *key) {
+        x = &mut x.as_mut().unwrap().next;
+    }
+    let mut node = x.take()?;
+    *x = node.next.take();
+    Some(node)
+}
+
+const INIT_CAPACITY: usize = 16;
+// average chain length that triggers doubling the table
+const MAX_LOAD: usize = 2;
+
+// separate chaining
+pub struct HashST<K, V, S = DefaultHashBuilder> {
+    st: Vec<Option<Box<Node<K, V>>>>,
+    n: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher + Default> Default for HashST<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V> HashST<K, V> {
+    pub fn new() -> HashST<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// create a table holding `capacity` keys without resizing
+    pub fn with_capacity(capacity: usize) -> HashST<K, V> {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> HashST<K, V, S> {
+    /// create a table using `hash_builder` to hash keys
+    pub fn with_hasher(hash_builder: S) -> HashST<K, V, S> {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> HashST<K, V, S> {
+        let m = INIT_CAPACITY.max(capacity / MAX_LOAD + 1);
+        let st = (0..m).map(|_| None).collect();
+        HashST { st, n: 0, hash_builder }
+    }
+
+    fn hash(&self, key: &K) -> usize {
+        (self.hash_builder.hash_one(key) % self.st.len() as u64) as usize
+    }
+
+    // rehash all keys into `m` chains
+    fn resize(&mut self, m: usize) {
+        let st = (0..m).map(|_| None).collect();
+        let old = mem::replace(&mut self.st, st);
+        for mut chain in old {
+            while let Some(mut x) = chain {
+                chain = x.next.take();
+                let i = self.hash(&x.key);
+                x.next = self.st[i].take();
+                self.st[i] = Some(x);
+            }
+        }
+    }
+
+    pub fn get<T: Borrow<K>>(&self, key: T) -> Option<&V> {
+        let key = key.borrow();
+        let i = self.hash(key);
+        let mut x = self.st[i].as_ref();
+        while x.is_some() {
+            if *key == x.unwrap().key {
+                return Some(&x.unwrap().val);
+            }
+            x = x.unwrap().next.as_ref();
+        }
+        None
+    }
+
+    pub fn get_mut<T: Borrow<K>>(&mut self, key: T) -> Option<&mut V> {
+        let key = key.borrow();
+        let i = self.hash(key);
+        let mut x = self.st[i].as_mut();
+        while x.is_some() {
+            if x.as_ref().map_or(false, |n| n.key == *key) {
+                return Some(&mut x.unwrap().val);
+            }
+            x = x.unwrap().next.as_mut();
+        }
+        None
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        let i = self.hash(&key);
+        {
+            let mut x = self.st[i].as_mut();
+            while x.is_some() {
+                if x.as_ref().map_or(false, |x| x.key == key) {
+                    x.unwrap().val = val;
+                    return;
+                } else {
+                    x = x.unwrap().next.as_mut();
+                }
+            }
+        }
+        let old = self.st[i].take();
+        self.st[i] = Some(Box::new(Node { key, val, next: old }));
+        self.n += 1;
+
+        let m = self.st.len();
+        if self.n >= MAX_LOAD * m {
+            self.resize(2 * m);
+        }
+    }
+
+    /// remove a key from the table, returning its value
+    pub fn delete(&mut self, key: &K) -> Option<V> {
+        let i = self.hash(key);
+        let mut removed = None;
+        self.st[i] = delete(self.st[i].take(), key, &mut removed);
+        if removed.is_some() {
+            self.n -= 1;
+        }
+
+        let m = self.st.len();
+        if m > INIT_CAPACITY && self.n <= m / 8 {
+            self.resize(m / 2);
+        }
+        removed
+    }
+
+    /// get the entry of a key for in-place manipulation
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let i = self.hash(&key);
+        match unlink(&mut self.st[i], &key) {
+            Some(mut node) => {
+                // move the node to the front of its chain, where the entry can borrow it
+                node.next = self.st[i].take();
+                Entry::Occupied(OccupiedEntry {
+                    node: self.st[i].insert(node),
+                })
+            }
+            None => Entry::Vacant(VacantEntry { table: self, key, i }),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// number of chains in the table
+    pub fn capacity(&self) -> usize {
+        self.st.len()
+    }
+}
+
+impl<K, V, S> HashST<K, V, S> {
+    /// iterate over key-value pairs in arbitrary order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            chains: self.st.iter(),
+            cur: None,
+            remaining: self.n,
+        }
+    }
+
+    /// iterate over key-value pairs, with mutable values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            chains: self.st.iter_mut(),
+            cur: None,
+            remaining: self.n,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// remove all key-value pairs, keeping the allocated chains
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let remaining = mem::replace(&mut self.n, 0);
+        Drain {
+            chains: self.st.iter_mut(),
+            cur: None,
+            remaining,
+        }
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> Index<K> for HashST<K, V, S> {
+    type Output = V;
+    fn index(&self, index: K) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> IndexMut<K> for HashST<K, V, S> {
+    fn index_mut(&mut self, index: K) -> &mut V {
+        self.get_mut(index).expect("key not exists")
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher + Default> FromIterator<(K, V)> for HashST<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut st = HashST::default();
+        st.extend(iter);
+        st
+    }
+}
+
+impl<K: Hash + PartialEq, V, S: BuildHasher> Extend<(K, V)> for HashST<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+/// A view into a single entry of a `HashST`, which may be vacant or occupied
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    node: &'a mut Node<K, V>,
+}
+
+pub struct VacantEntry<'a, K, V, S> {
+    table: &'a mut HashST<K, V, S>,
+    key: K,
+    // chain the key hashes to
+    i: usize,
+}
+
+impl<'a, K: Hash + PartialEq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref e) => e.key(),
+            Entry::Vacant(ref e) => e.key(),
+        }
+    }
+
+    /// insert `default` if the entry is vacant, and return the value
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// insert the result of `default` if the entry is vacant, and return the value
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// modify the value in place if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(e) => {
+                f(&mut e.node.val);
+                Entry::Occupied(e)
+            }
+            vacant => vacant,
+        }
+    }
+}
+
+impl<'a, K: Hash + PartialEq, V: Default, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.node.key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.node.val
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.node.val
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.node.val
+    }
+
+    /// replace the value, returning the old one
+    pub fn insert(&mut self, val: V) -> V {
+        mem::replace(&mut self.node.val, val)
+    }
+}
+
+impl<'a, K: Hash + PartialEq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn insert(self, val: V) -> &'a mut V {
+        let table = self.table;
+        let mut i = self.i;
+        // grow first, so the new node stays in its chain
+        let m = table.st.len();
+        if table.n + 1 >= MAX_LOAD * m {
+            table.resize(2 * m);
+            i = table.hash(&self.key);
+        }
+        let old = table.st[i].take();
+        table.n += 1;
+        let node = table.st[i].insert(Box::new(Node {
+            key: self.key,
+            val,
+            next: old,
+        }));
+        &mut node.val
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    chains: slice::Iter<'a, Option<Box<Node<K, V>>>>,
+    cur: Option<&'a Node<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(node) = self.cur {
+                self.cur = node.next.as_deref();
+                self.remaining -= 1;
+                return Some((&node.key, &node.val));
+            }
+            self.cur = self.chains.next()?.as_deref();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    chains: slice::IterMut<'a, Option<Box<Node<K, V>>>>,
+    cur: Option<&'a mut Node<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(node) = self.cur.take() {
+                let Node { key, val, next } = node;
+                self.cur = next.as_deref_mut();
+                self.remaining -= 1;
+                return Some((key, val));
+            }
+            self.cur = self.chains.next()?.as_deref_mut();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {}
+
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+// unlink the head of a chain
+fn pop_node<K, V>(cur: &mut Option<Box<Node<K, V>>>) -> Option<(K, V)> {
+    cur.take().map(|node| {
+        let Node { key, val, next } = *node;
+        *cur = next;
+        (key, val)
+    })
+}
+
+pub struct IntoIter<K, V> {
+    chains: vec::IntoIter<Option<Box<Node<K, V>>>>,
+    cur: Option<Box<Node<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.cur.is_none() {
+            self.cur = self.chains.next()?;
+        }
+        self.remaining -= 1;
+        pop_node(&mut self.cur)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+/// Draining iterator; the table is empty once it is dropped
+pub struct Drain<'a, K, V> {
+    chains: slice::IterMut<'a, Option<Box<Node<K, V>>>>,
+    cur: Option<Box<Node<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Drain<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while self.cur.is_none() {
+            self.cur = self.chains.next()?.take();
+        }
+        self.remaining -= 1;
+        pop_node(&mut self.cur)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Drain<'a, K, V> {}
+
+impl<'a, K, V> Drop for Drain<'a, K, V> {
+    fn drop(&mut self) {
+        for chain in &mut self.chains {
+            chain.take();
+        }
+    }
+}
+
+impl<K, V, S> IntoIterator for HashST<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            chains: self.st.into_iter(),
+            cur: None,
+            remaining: self.n,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashST<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashST<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[test]
+fn test_separate_chaining_hash_st() {
+    let mut m = HashST::new();
+    assert_eq!(m.size(), 0);
+    m.put("Name", "Feather");
+    m.put("Age", "25");
+    m.put("Address", "Beijing");
+
+    assert_eq!(m.size(), 3);
+    assert_eq!(m.get("Age"), Some(&"25"));
+    assert_eq!(m.get("Gender"), None);
+
+    m.delete(&"Age");
+    assert_eq!(m.size(), 2);
+    assert_eq!(m.get("Age"), None);
+
+    m.get_mut("Address").map(|v| *v = "Shanghai");
+    assert_eq!(m.get("Address"), Some(&"Shanghai"));
+
+    assert_eq!(m["Address"], "Shanghai");
+}
+
+#[test]
+fn test_hash_st_resize_and_hasher() {
+    // FNV-1a, deterministic across runs
+    #[derive(Default)]
+    struct Fnv(u64);
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            if self.0 == 0 {
+                self.0 = 0xcbf2_9ce4_8422_2325;
+            }
+            for &b in bytes {
+                self.0 = (self.0 ^ b as u64).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    let mut m: HashST<u32, u32, BuildHasherDefault<Fnv>> = HashST::default();
+    for i in 0..10_000 {
+        m.put(i, i * i);
+    }
+    m.put(42, 0);
+    assert_eq!(m.size(), 10_000);
+    assert!(m.capacity() > 10_000 / MAX_LOAD);
+    assert!((0..10_000).filter(|&i| i != 42).all(|i| m.get(i) == Some(&(i * i))));
+    assert_eq!(m.get(42), Some(&0));
+
+    for i in 0..9_990 {
+        m.delete(&i);
+    }
+    m.delete(&0);
+    assert_eq!(m.size(), 10);
+    assert!(m.capacity() < 100);
+    assert_eq!(m[9_999], 9_999 * 9_999);
+
+    let m: HashST<&str, i32> = HashST::with_capacity(1000);
+    assert!(m.is_empty());
+    assert!(m.capacity() >= 1000 / MAX_LOAD);
+}
+
+#[test]
+fn test_hash_st_entry_resize() {
+    let mut t: HashST<usize, usize> = (0..MAX_LOAD * INIT_CAPACITY - 1).map(|i| (i, i)).collect();
+    assert_eq!(t.capacity(), INIT_CAPACITY);
+    // only inserting through a vacant entry may grow the table
+    *t.entry(7).or_insert(0) += 1;
+    assert!(matches!(t.entry(8), Entry::Occupied(_)));
+    assert_eq!(t.capacity(), INIT_CAPACITY);
+    assert!(matches!(t.entry(100), Entry::Vacant(_)));
+    assert_eq!(t.capacity(), INIT_CAPACITY);
+    t.entry(100).or_insert(100);
+    assert_eq!(t.capacity(), 2 * INIT_CAPACITY);
+    assert_eq!(t.size(), MAX_LOAD * INIT_CAPACITY);
+    assert!((0..MAX_LOAD * INIT_CAPACITY - 1).all(|i| t[i] == i + (i == 7) as usize));
+    assert_eq!(t[100], 100);
+}
+
+#[test]
+fn test_hash_st_entry_and_iter() {
+    let text = "it was the best of times it was the worst of times";
+    let mut freq: HashST<&str, usize> = text.split(' ').map(|w| (w, 0)).collect();
+    for w in text.split(' ') {
+        *freq.entry(w).or_insert(0) += 1;
+    }
+    freq.entry("age").and_modify(|c| *c += 1).or_insert_with(|| 42);
+    freq.entry("it").and_modify(|c| *c *= 10).or_default();
+    assert_eq!(freq.size(), 8);
+    assert_eq!(freq["it"], 20);
+    assert_eq!(freq["age"], 42);
+
+    freq["times"] = 0;
+    for (_, c) in freq.iter_mut() {
+        *c += 1;
+    }
+    assert_eq!(freq.values().sum::<usize>(), 21 + 3 + 3 + 2 + 3 + 1 + 2 + 43);
+    let mut keys = freq.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, vec!["age", "best", "it", "of", "the", "times", "was", "worst"]);
+
+    assert_eq!(freq.delete(&"age"), Some(43));
+    assert_eq!(freq.delete(&"age"), None);
+    assert_eq!(freq.iter().len(), 7);
+
+    let drained = freq.drain().take(3).count();
+    assert_eq!(drained, 3);
+    assert!(freq.is_empty() && freq.iter().next().is_none());
+
+    freq.extend(vec![("a", 1), ("b", 2), ("a", 3)]);
+    let mut pairs = freq.into_iter().collect::<Vec<_>>();
+    pairs.sort();
+    assert_eq!(pairs, vec![("a", 3), ("b", 2)]);
+}
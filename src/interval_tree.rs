@@ -0,0 +1,308 @@
+use crate::rbtree::{delete, delete_root, Color, RedBlackNode};
+use std::cmp::Ordering;
+use std::mem;
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+/// Closed interval `[lo, hi]`, ordered by `lo`, then by `hi`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<K> {
+    pub lo: K,
+    pub hi: K,
+}
+
+impl<K: PartialOrd> Interval<K> {
+    pub fn new(lo: K, hi: K) -> Interval<K> {
+        assert!(lo <= hi, "interval must not end before it starts");
+        Interval { lo, hi }
+    }
+
+    /// does the interval contain the point?
+    pub fn contains(&self, x: &K) -> bool {
+        self.lo <= *x && *x <= self.hi
+    }
+
+    /// do the two intervals share a point?
+    pub fn intersects(&self, that: &Interval<K>) -> bool {
+        overlaps(self, &that.lo, &that.hi)
+    }
+}
+
+#[inline]
+fn overlaps<K: PartialOrd>(interval: &Interval<K>, lo: &K, hi: &K) -> bool {
+    interval.lo <= *hi && *lo <= interval.hi
+}
+
+type NodeCell<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    interval: Interval<K>,
+    val: V,
+    // largest endpoint in the subtree
+    max: K,
+    left: NodeCell<K, V>,
+    right: NodeCell<K, V>,
+    color: Color,
+}
+
+impl<K: PartialOrd + Clone, V> Node<K, V> {
+    fn new(interval: Interval<K>, val: V) -> Node<K, V> {
+        Node {
+            max: interval.hi.clone(),
+            interval,
+            val,
+            left: None,
+            right: None,
+            color: Color::Red,
+        }
+    }
+}
+
+impl<K: PartialOrd + Clone, V> RedBlackNode for Node<K, V> {
+    type Key = Interval<K>;
+
+    #[inline]
+    fn key(&self) -> &Interval<K> {
+        &self.interval
+    }
+
+    #[inline]
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    #[inline]
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    #[inline]
+    fn left(&self) -> &NodeCell<K, V> {
+        &self.left
+    }
+
+    #[inline]
+    fn right(&self) -> &NodeCell<K, V> {
+        &self.right
+    }
+
+    #[inline]
+    fn left_mut(&mut self) -> &mut NodeCell<K, V> {
+        &mut self.left
+    }
+
+    #[inline]
+    fn right_mut(&mut self) -> &mut NodeCell<K, V> {
+        &mut self.right
+    }
+
+    fn swap_entry(&mut self, other: &mut Self) {
+        mem::swap(&mut self.interval, &mut other.interval);
+        mem::swap(&mut self.val, &mut other.val);
+    }
+
+    // the largest endpoint in the subtree
+    fn pull(&mut self) {
+        self.max = self.interval.hi.clone();
+        for child in [&self.left, &self.right].iter().filter_map(|c| c.as_ref()) {
+            if child.max > self.max {
+                self.max = child.max.clone();
+            }
+        }
+    }
+}
+
+// put helper, the replaced value goes to old
+fn put<K: PartialOrd + Clone, V>(
+    x: NodeCell<K, V>,
+    interval: Interval<K>,
+    val: V,
+    old: &mut Option<V>,
+) -> Box<Node<K, V>> {
+    let mut h = match x {
+        None => return Box::new(Node::new(interval, val)),
+        Some(h) => h,
+    };
+    match interval.partial_cmp(&h.interval).unwrap() {
+        Ordering::Less => h.left = Some(put(h.left.take(), interval, val, old)),
+        Ordering::Greater => h.right = Some(put(h.right.take(), interval, val, old)),
+        Ordering::Equal => *old = Some(mem::replace(&mut h.val, val)),
+    }
+    h.balance();
+    h
+}
+
+// collect the intervals of the subtree overlapping [lo, hi], in order
+fn search<'a, K: PartialOrd, V>(x: Option<&'a Node<K, V>>, lo: &K, hi: &K, out: &mut Vec<(&'a Interval<K>, &'a V)>) {
+    let x = match x {
+        Some(x) if x.max >= *lo => x,
+        // nothing in the subtree ends at or after lo
+        _ => return,
+    };
+    search(x.left.as_deref(), lo, hi, out);
+    if overlaps(&x.interval, lo, hi) {
+        out.push((&x.interval, &x.val));
+    }
+    // everything to the right starts at or after x
+    if x.interval.lo <= *hi {
+        search(x.right.as_deref(), lo, hi, out);
+    }
+}
+
+/// Interval symbol table, a left-leaning red-black tree of intervals where
+/// every node also keeps the largest endpoint of its subtree.
+///
+/// The endpoint lets a search skip subtrees ending before the query, so an
+/// overlap check takes O(log n), and reporting k overlaps O(min(n, (k + 1) log n)).
+pub struct IntervalTree<K, V> {
+    root: NodeCell<K, V>,
+    n: usize,
+}
+
+impl<K: PartialOrd + Clone, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone, V> IntervalTree<K, V> {
+    pub fn new() -> IntervalTree<K, V> {
+        IntervalTree { root: None, n: 0 }
+    }
+
+    /// number of intervals in the tree
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn get(&self, interval: &Interval<K>) -> Option<&V> {
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            match interval.partial_cmp(&node.interval).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.val),
+            }
+        }
+        None
+    }
+
+    pub fn contains(&self, interval: &Interval<K>) -> bool {
+        self.get(interval).is_some()
+    }
+
+    /// insert an interval with its payload, returns the payload it had before
+    pub fn insert(&mut self, interval: Interval<K>, val: V) -> Option<V> {
+        let mut old = None;
+        let mut root = put(self.root.take(), interval, val, &mut old);
+        root.set_color(Color::Black);
+        self.root = Some(root);
+        if old.is_none() {
+            self.n += 1;
+        }
+        old
+    }
+
+    /// remove an interval, returns its payload if it was in the tree
+    pub fn remove(&mut self, interval: &Interval<K>) -> Option<V> {
+        if !self.contains(interval) {
+            return None;
+        }
+        let deleted = delete_root(&mut self.root, |root| delete(root, interval));
+        self.n -= 1;
+        Some(deleted.val)
+    }
+
+    /// all intervals containing the point, in order
+    pub fn stab(&self, x: &K) -> Vec<(&Interval<K>, &V)> {
+        let mut out = Vec::new();
+        search(self.root.as_deref(), x, x, &mut out);
+        out
+    }
+
+    /// all intervals overlapping the interval, in order
+    pub fn overlapping(&self, interval: &Interval<K>) -> Vec<(&Interval<K>, &V)> {
+        let mut out = Vec::new();
+        search(self.root.as_deref(), &interval.lo, &interval.hi, &mut out);
+        out
+    }
+
+    /// some interval overlapping the interval, if there is any
+    pub fn any_overlap(&self, interval: &Interval<K>) -> Option<(&Interval<K>, &V)> {
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if node.interval.intersects(interval) {
+                return Some((&node.interval, &node.val));
+            }
+            // if nothing on the left ends late enough, nothing there overlaps,
+            // otherwise an overlap there exists if one exists at all
+            x = match node.left.as_deref() {
+                Some(left) if left.max >= interval.lo => Some(left),
+                _ => node.right.as_deref(),
+            };
+        }
+        None
+    }
+}
+
+#[test]
+fn test_interval_tree() {
+    let mut t = IntervalTree::new();
+    for &(lo, hi) in [(17, 19), (5, 8), (21, 24), (4, 8), (15, 18), (7, 10), (16, 22)].iter() {
+        t.insert(Interval::new(lo, hi), format!("{}-{}", lo, hi));
+    }
+    assert_eq!(t.size(), 7);
+    assert_eq!(t.insert(Interval::new(5, 8), "again".into()), Some("5-8".into()));
+
+    let names = |found: Vec<(&Interval<i32>, &String)>| found.into_iter().map(|(_, v)| v.clone()).collect::<Vec<_>>();
+    assert_eq!(names(t.stab(&8)), ["4-8", "again", "7-10"]);
+    assert_eq!(names(t.stab(&18)), ["15-18", "16-22", "17-19"]);
+    assert!(t.stab(&12).is_empty());
+    assert_eq!(names(t.overlapping(&Interval::new(9, 16))), ["7-10", "15-18", "16-22"]);
+
+    assert_eq!(t.any_overlap(&Interval::new(11, 14)), None);
+    assert!(t.any_overlap(&Interval::new(23, 30)).is_some());
+
+    assert_eq!(t.remove(&Interval::new(7, 10)), Some("7-10".into()));
+    assert_eq!(t.remove(&Interval::new(7, 10)), None);
+    assert_eq!(t.any_overlap(&Interval::new(9, 14)), None);
+    assert_eq!(t.size(), 6);
+}
+
+#[test]
+fn quicktest_interval_tree() {
+    fn prop(ops: Vec<(bool, u8, u8)>, queries: Vec<(u8, u8)>) -> bool {
+        let mut t = IntervalTree::new();
+        let mut model = Vec::new();
+        for &(insert, a, b) in ops.iter() {
+            let interval = Interval::new(a.min(b), a.max(b));
+            if insert {
+                if t.insert(interval, ()).is_none() {
+                    model.push(interval);
+                }
+            } else if t.remove(&interval).is_some() {
+                model.retain(|i| *i != interval);
+            }
+        }
+        model.sort();
+        t.size() == model.len()
+            && queries.iter().all(|&(a, b)| {
+                let q = Interval::new(a.min(b), a.max(b));
+                let expected = model.iter().filter(|i| i.intersects(&q)).collect::<Vec<_>>();
+                let stabbed = model.iter().filter(|i| i.contains(&a)).collect::<Vec<_>>();
+                t.overlapping(&q)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .eq(expected.iter().copied())
+                    && t.stab(&a).into_iter().map(|(i, _)| i).eq(stabbed.iter().copied())
+                    && t.any_overlap(&q).is_some() != expected.is_empty()
+            })
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u8)>, Vec<(u8, u8)>) -> bool);
+}
@@ -2,6 +2,10 @@
 #![cfg_attr(feature = "dev", plugin(clippy))]
 #![cfg_attr(not(feature = "dev"), allow(unknown_lints))]
 
+#[cfg(test)]
+#[macro_use]
+mod testing;
+
 pub mod bag;
 pub mod deque;
 pub mod graph;
@@ -18,7 +22,17 @@ pub mod rope;
 
 pub mod skip_list;
 
+pub mod sketch;
+
+pub mod avl_tree;
+pub mod btree;
 pub mod rbtree;
+pub mod treap;
+
+pub mod interval_tree;
+pub mod persistent_rbtree;
+
+pub mod consistent_hash;
 
 pub mod kdtree;
 pub mod primitive;
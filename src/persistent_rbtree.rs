@@ -0,0 +1,661 @@
+use self::Color::*;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Black,
+}
+
+type Link<K, V> = Option<Arc<Node<K, V>>>;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    key: K,
+    val: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    color: Color,
+    // number of nodes in the subtree
+    n: usize,
+}
+
+#[inline]
+fn is_red<K, V>(x: &Link<K, V>) -> bool {
+    x.as_ref().is_some_and(|n| n.color == Red)
+}
+
+#[inline]
+fn size<K, V>(x: &Link<K, V>) -> usize {
+    x.as_ref().map_or(0, |n| n.n)
+}
+
+// Nodes are only ever changed through an owned copy: `Arc::unwrap_or_clone`
+// moves a node out when this is its only version, and copies it otherwise.
+impl<K: Clone, V: Clone> Node<K, V> {
+    fn new(key: K, val: V) -> Node<K, V> {
+        Node {
+            key,
+            val,
+            left: None,
+            right: None,
+            color: Red,
+            n: 1,
+        }
+    }
+
+    #[inline]
+    fn update_size(&mut self) {
+        self.n = 1 + size(&self.left) + size(&self.right);
+    }
+
+    /// Left rotation. Orient a (temporarily) right-leaning red link to lean left.
+    fn rotate_left(&mut self) {
+        let mut x = Arc::unwrap_or_clone(self.right.take().unwrap());
+        self.right = x.left.take();
+        x.color = self.color;
+        self.color = Red;
+        let mut old_self = mem::replace(self, x);
+        old_self.update_size();
+        self.left = Some(Arc::new(old_self));
+        self.update_size();
+    }
+
+    /// Right rotation. Orient a left-leaning red link to (temporarily) lean right.
+    fn rotate_right(&mut self) {
+        let mut x = Arc::unwrap_or_clone(self.left.take().unwrap());
+        self.left = x.right.take();
+        x.color = self.color;
+        self.color = Red;
+        let mut old_self = mem::replace(self, x);
+        old_self.update_size();
+        self.right = Some(Arc::new(old_self));
+        self.update_size();
+    }
+
+    /// Flip the colors of a node and its two children.
+    fn flip_colors(&mut self) {
+        self.color = if self.color == Red { Black } else { Red };
+        for child in [&mut self.left, &mut self.right].iter_mut() {
+            if let Some(child) = child.as_mut() {
+                let child = Arc::make_mut(child);
+                child.color = if child.color == Red { Black } else { Red };
+            }
+        }
+    }
+
+    fn move_red_left(&mut self) {
+        self.flip_colors();
+        if is_red(&self.right.as_ref().unwrap().left) {
+            Arc::make_mut(self.right.as_mut().unwrap()).rotate_right();
+            self.rotate_left();
+            self.flip_colors();
+        }
+    }
+
+    fn move_red_right(&mut self) {
+        self.flip_colors();
+        if is_red(&self.left.as_ref().unwrap().left) {
+            self.rotate_right();
+            self.flip_colors();
+        }
+    }
+
+    /// Restore the red-black invariants on the way up.
+    fn balance(&mut self) {
+        if is_red(&self.right) && !is_red(&self.left) {
+            self.rotate_left();
+        }
+        if is_red(&self.left) && is_red(&self.left.as_ref().unwrap().left) {
+            self.rotate_right();
+        }
+        if is_red(&self.left) && is_red(&self.right) {
+            self.flip_colors();
+        }
+        self.update_size();
+    }
+}
+
+fn put<K: PartialOrd + Clone, V: Clone>(x: Link<K, V>, key: K, val: V) -> Arc<Node<K, V>> {
+    let mut h = match x {
+        None => return Arc::new(Node::new(key, val)),
+        Some(h) => Arc::unwrap_or_clone(h),
+    };
+    match key.partial_cmp(&h.key).unwrap() {
+        Ordering::Less => h.left = Some(put(h.left.take(), key, val)),
+        Ordering::Greater => h.right = Some(put(h.right.take(), key, val)),
+        Ordering::Equal => h.val = val,
+    }
+    h.balance();
+    Arc::new(h)
+}
+
+fn delete_min<K: Clone, V: Clone>(h: Arc<Node<K, V>>) -> Link<K, V> {
+    let mut h = Arc::unwrap_or_clone(h);
+    h.left.as_ref()?;
+    if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
+        h.move_red_left();
+    }
+    h.left = delete_min(h.left.take().unwrap());
+    h.balance();
+    Some(Arc::new(h))
+}
+
+// delete helper, the key must be in the subtree
+fn delete<K, Q, V>(h: Arc<Node<K, V>>, key: &Q) -> Link<K, V>
+where
+    K: Borrow<Q> + Clone,
+    Q: PartialOrd + ?Sized,
+    V: Clone,
+{
+    let mut h = Arc::unwrap_or_clone(h);
+    if key < h.key.borrow() {
+        if !is_red(&h.left) && !is_red(&h.left.as_ref().unwrap().left) {
+            h.move_red_left();
+        }
+        h.left = delete(h.left.take().unwrap(), key);
+    } else {
+        if is_red(&h.left) {
+            h.rotate_right();
+        }
+        if key == h.key.borrow() && h.right.is_none() {
+            return None;
+        }
+        if !is_red(&h.right) && !is_red(&h.right.as_ref().unwrap().left) {
+            h.move_red_right();
+        }
+        if key == h.key.borrow() {
+            // replace with the successor
+            let right = h.right.take().unwrap();
+            let min = find_min(&right);
+            h.key = min.key.clone();
+            h.val = min.val.clone();
+            h.right = delete_min(right);
+        } else {
+            h.right = delete(h.right.take().unwrap(), key);
+        }
+    }
+    h.balance();
+    Some(Arc::new(h))
+}
+
+fn find_min<K, V>(mut x: &Node<K, V>) -> &Node<K, V> {
+    while let Some(left) = x.left.as_deref() {
+        x = left;
+    }
+    x
+}
+
+fn find_max<K, V>(mut x: &Node<K, V>) -> &Node<K, V> {
+    while let Some(right) = x.right.as_deref() {
+        x = right;
+    }
+    x
+}
+
+// number of keys in the subtree for which pred holds, given it holds for a
+// prefix of the keys in order
+fn count_prefix<K, V, F: Fn(&K) -> bool>(mut x: Option<&Node<K, V>>, pred: F) -> usize {
+    let mut n = 0;
+    while let Some(node) = x {
+        if pred(&node.key) {
+            n += 1 + size(&node.left);
+            x = node.right.as_deref();
+        } else {
+            x = node.left.as_deref();
+        }
+    }
+    n
+}
+
+/// Persistent ordered symbol table, a left-leaning red-black tree whose
+/// versions share their unchanged subtrees.
+///
+/// `insert` and `remove` leave the map alone and return a new version, copying
+/// only the O(log n) nodes on the changed path. Cloning a map is O(1), and
+/// `put` and `delete` update a version in place, copying only shared nodes.
+pub struct PersistentRedBlackBST<K, V> {
+    root: Link<K, V>,
+}
+
+impl<K, V> Clone for PersistentRedBlackBST<K, V> {
+    fn clone(&self) -> Self {
+        PersistentRedBlackBST {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for PersistentRedBlackBST<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> PersistentRedBlackBST<K, V> {
+    pub fn new() -> PersistentRedBlackBST<K, V> {
+        PersistentRedBlackBST { root: None }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// number of key-value pairs in the table
+    pub fn size(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// smallest key
+    pub fn min(&self) -> Option<&K> {
+        self.root.as_deref().map(|x| &find_min(x).key)
+    }
+
+    /// largest key
+    pub fn max(&self) -> Option<&K> {
+        self.root.as_deref().map(|x| &find_max(x).key)
+    }
+
+    /// key of rank k
+    pub fn select(&self, k: usize) -> Option<&K> {
+        let mut x = self.root.as_deref();
+        let mut k = k;
+        while let Some(node) = x {
+            let t = size(&node.left);
+            match k.cmp(&t) {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    x = node.right.as_deref();
+                    k -= t + 1;
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        None
+    }
+
+    /// do both versions share the same tree?
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// iterator over the entries, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            len: self.size(),
+        };
+        iter.push_left(self.root.as_deref());
+        iter.push_right(self.root.as_deref());
+        iter
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: PartialOrd, V> PersistentRedBlackBST<K, V> {
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.val),
+            }
+        }
+        None
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// largest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref();
+        let mut best = None;
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    best = Some(&node.key);
+                    x = node.right.as_deref();
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        best
+    }
+
+    /// smallest key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref();
+        let mut best = None;
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => {
+                    best = Some(&node.key);
+                    x = node.left.as_deref();
+                }
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        best
+    }
+
+    /// number of keys less than key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        count_prefix(self.root.as_deref(), |k| k.borrow() < key)
+    }
+
+    /// iterator over the entries with keys in the range, in key order
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let after_start = |key: &K| match range.start_bound() {
+            Bound::Included(lo) => key.borrow() >= lo,
+            Bound::Excluded(lo) => key.borrow() > lo,
+            Bound::Unbounded => true,
+        };
+        let before_end = |key: &K| match range.end_bound() {
+            Bound::Included(hi) => key.borrow() <= hi,
+            Bound::Excluded(hi) => key.borrow() < hi,
+            Bound::Unbounded => true,
+        };
+
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            len: count_prefix(self.root.as_deref(), before_end)
+                .saturating_sub(count_prefix(self.root.as_deref(), |k| !after_start(k))),
+        };
+        // keep the nodes inside the range along the search paths to either bound
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if after_start(&node.key) {
+                iter.front.push(node);
+                x = node.left.as_deref();
+            } else {
+                x = node.right.as_deref();
+            }
+        }
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if before_end(&node.key) {
+                iter.back.push(node);
+                x = node.right.as_deref();
+            } else {
+                x = node.left.as_deref();
+            }
+        }
+        iter
+    }
+}
+
+impl<K: PartialOrd + Clone, V: Clone> PersistentRedBlackBST<K, V> {
+    /// new version with the key-value pair inserted
+    pub fn insert(&self, key: K, val: V) -> Self {
+        let mut t = self.clone();
+        t.put(key, val);
+        t
+    }
+
+    /// new version without the key
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut t = self.clone();
+        t.delete(key);
+        t
+    }
+
+    /// insert into this version, other versions are unaffected
+    pub fn put(&mut self, key: K, val: V) {
+        let mut root = Arc::unwrap_or_clone(put(self.root.take(), key, val));
+        root.color = Black;
+        self.root = Some(Arc::new(root));
+    }
+
+    /// delete from this version, other versions are unaffected
+    pub fn delete<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        if !self.contains(key) {
+            return;
+        }
+        let mut root = Arc::unwrap_or_clone(self.root.take().unwrap());
+        // if both children of root are black, set root to red
+        if !is_red(&root.left) && !is_red(&root.right) {
+            root.color = Red;
+        }
+        self.root = delete(Arc::new(root), key).map(|root| {
+            let mut root = Arc::unwrap_or_clone(root);
+            root.color = Black;
+            Arc::new(root)
+        });
+    }
+}
+
+impl<K: PartialOrd + Clone, V: Clone> FromIterator<(K, V)> for PersistentRedBlackBST<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut t = PersistentRedBlackBST::new();
+        for (key, val) in iter {
+            t.put(key, val);
+        }
+        t
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for PersistentRedBlackBST<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || (self.size() == other.size() && self.iter().eq(other.iter()))
+    }
+}
+
+/// Iterator over the entries of a `PersistentRedBlackBST` in key order, from either end.
+pub struct Iter<'a, K, V> {
+    // nodes not visited yet, whose smaller (front) or larger (back) keys are
+    front: Vec<&'a Node<K, V>>,
+    back: Vec<&'a Node<K, V>>,
+    // entries left between the two ends
+    len: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.front.push(node);
+            x = node.left.as_deref();
+        }
+    }
+
+    fn push_right(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.back.push(node);
+            x = node.right.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.push_left(node.right.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.push_right(node.left.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.val))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K, V> IntoIterator for &'a PersistentRedBlackBST<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_persistent_red_black_tree() {
+    let empty = PersistentRedBlackBST::new();
+    let v1 = empty.insert("b", 1).insert("d", 2).insert("f", 3);
+    let v2 = v1.insert("c", 4).remove("d");
+    let v3 = v2.insert("b", 5);
+
+    assert!(empty.is_empty());
+    assert_eq!(v1.iter().collect::<Vec<_>>(), [(&"b", &1), (&"d", &2), (&"f", &3)]);
+    assert_eq!(v2.iter().collect::<Vec<_>>(), [(&"b", &1), (&"c", &4), (&"f", &3)]);
+    assert_eq!(v3.get("b"), Some(&5));
+    assert_eq!(v2.get("b"), Some(&1));
+
+    assert_eq!(v2.floor("e"), Some(&"c"));
+    assert_eq!(v2.ceiling("d"), Some(&"f"));
+    assert_eq!(v2.floor("a"), None);
+    assert_eq!(v2.rank("f"), 2);
+    assert_eq!(v2.select(1), Some(&"c"));
+    assert_eq!((v1.min(), v1.max()), (Some(&"b"), Some(&"f")));
+    assert_eq!(v1.range("c"..).rev().map(|(k, _)| *k).collect::<Vec<_>>(), ["f", "d"]);
+
+    // a clone is the same tree until it changes
+    let mut snapshot = v3.clone();
+    assert!(snapshot.ptr_eq(&v3));
+    snapshot.delete("c");
+    assert!(!snapshot.ptr_eq(&v3) && snapshot != v3);
+    assert_eq!(v3.size(), 3);
+
+    // unchanged subtrees are shared
+    let big = (0..1000).map(|i| (i, i)).collect::<PersistentRedBlackBST<_, _>>();
+    let changed = big.insert(1000, 1000);
+    let (a, b) = (big.root.as_ref().unwrap(), changed.root.as_ref().unwrap());
+    assert!(Arc::ptr_eq(a.left.as_ref().unwrap(), b.left.as_ref().unwrap()));
+}
+
+#[cfg(test)]
+impl crate::testing::Table for PersistentRedBlackBST<u8, u16> {
+    fn insert(&mut self, key: u8, val: u16) -> Option<u16> {
+        let old = self.get(&key).cloned();
+        self.put(key, val);
+        old
+    }
+
+    fn remove(&mut self, key: &u8) -> Option<u16> {
+        let old = self.get(key).cloned();
+        self.delete(key);
+        old
+    }
+
+    fn entries(&self) -> Vec<(u8, u16)> {
+        self.iter().map(|(&k, &v)| (k, v)).collect()
+    }
+}
+
+#[cfg(test)]
+impl crate::testing::OrderedTable for PersistentRedBlackBST<u8, u16> {
+    fn rank(&self, key: &u8) -> usize {
+        PersistentRedBlackBST::rank(self, key)
+    }
+
+    fn select(&self, k: usize) -> Option<u8> {
+        PersistentRedBlackBST::select(self, k).cloned()
+    }
+
+    fn floor(&self, key: &u8) -> Option<u8> {
+        PersistentRedBlackBST::floor(self, key).cloned()
+    }
+
+    fn ceiling(&self, key: &u8) -> Option<u8> {
+        PersistentRedBlackBST::ceiling(self, key).cloned()
+    }
+}
+
+#[test]
+fn quicktest_persistent_red_black_tree() {
+    use crate::testing::{matches, queries_match, replay, Model};
+
+    // every version must still match the model it had when it was made
+    fn prop(ops: Vec<(bool, u8, u16)>, probe: u8) -> bool {
+        let mut versions = vec![(PersistentRedBlackBST::new(), Model::new())];
+        for op in ops.chunks(1) {
+            let (mut t, mut model) = versions.last().cloned().unwrap();
+            if replay(&mut t, &mut model, op).is_none() {
+                return false;
+            }
+            versions.push((t, model));
+        }
+        versions.iter().all(|(t, model)| {
+            matches(t, model) && queries_match(t, model, probe) && t.range(10..100).eq(model.range(10..100))
+        })
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u16)>, u8) -> bool);
+}
@@ -10,7 +10,7 @@ pub use super::graph::Graph;
 
 pub use super::graph::Digraph;
 
-pub use super::priority_queue::{IndexMinPQ, MaxPQ, MinPQ};
+pub use super::priority_queue::{IndexMaxPQ, IndexMinPQ, MaxPQ, MinPQ};
 
 pub use super::tries::TernarySearchTrie;
 
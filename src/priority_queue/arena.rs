@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::mem;
+use std::ops::{Index, IndexMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Position of a node: the arena segment it was allocated in, and its slot there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Ix {
+    segment: u64,
+    slot: usize,
+}
+
+/// no node, in place of a null link
+pub(super) const NIL: Ix = Ix {
+    segment: u64::MAX,
+    slot: usize::MAX,
+};
+
+static NEXT_SEGMENT: AtomicU64 = AtomicU64::new(0);
+
+/// Reference to an element of a `BinomialHeap`, `PairingHeap` or `FibonacciHeap`, returned by `insert`.
+///
+/// A handle stays valid while its element is in the heap it was inserted into, or in a heap
+/// that heap was melded into. Once the element is removed the handle is stale and the
+/// methods taking it report `HeapError::InvalidHandle`, even if the slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    segment: u64,
+    slot: usize,
+    generation: u64,
+}
+
+/// Errors reported by the `try_*` methods of the meldable heaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeapError {
+    /// the handle does not refer to an element of the heap
+    InvalidHandle,
+    /// the new key is farther from the top than the current one
+    KeyIncreased,
+}
+
+impl fmt::Display for HeapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HeapError::InvalidHandle => write!(f, "handle does not refer to an element of the heap"),
+            HeapError::KeyIncreased => write!(f, "decrease_key() with a larger key"),
+        }
+    }
+}
+
+impl error::Error for HeapError {}
+
+pub(super) fn unwrap_or_panic<R>(result: Result<R, HeapError>) -> R {
+    match result {
+        Ok(r) => r,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+// segment ids are distinct counter values, a multiplicative mix is enough to spread them
+#[derive(Default)]
+struct SegmentHasher(u64);
+
+impl Hasher for SegmentHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 << 8 | b as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = i.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+struct Slot<N> {
+    // bumped on every removal, so stale handles never match a reused slot
+    generation: u64,
+    node: Option<N>,
+}
+
+struct Segment<N> {
+    slots: Vec<Slot<N>>,
+    free: Vec<usize>,
+    // number of live nodes
+    live: usize,
+}
+
+impl<N> Segment<N> {
+    fn new() -> Self {
+        Segment {
+            slots: Vec::new(),
+            free: Vec::new(),
+            live: 0,
+        }
+    }
+}
+
+/// Slab of heap nodes in segments with free lists.
+///
+/// Every arena starts with a segment of its own. Melding hands whole segments over
+/// to the other arena, so nodes never move and their `Ix` and handles stay valid.
+/// A segment is dropped with its last node unless it is the arena's own, so an
+/// arena of n nodes has at most n + 1 segments.
+pub(super) struct Arena<N> {
+    // segment that new slots are pushed to
+    home: u64,
+    segments: HashMap<u64, Segment<N>, BuildHasherDefault<SegmentHasher>>,
+    // the segments with free slots, and dropped segments
+    spare: Vec<u64>,
+}
+
+impl<N> Arena<N> {
+    pub fn new() -> Self {
+        let home = NEXT_SEGMENT.fetch_add(1, Ordering::Relaxed);
+        let mut segments = HashMap::default();
+        segments.insert(home, Segment::new());
+        Arena {
+            home,
+            segments,
+            spare: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn segment(&mut self, id: u64) -> &mut Segment<N> {
+        self.segments.get_mut(&id).expect("segment of another arena")
+    }
+
+    pub fn alloc(&mut self, node: N) -> Ix {
+        while let Some(&id) = self.spare.last() {
+            let segment = match self.segments.get_mut(&id) {
+                Some(segment) => segment,
+                None => {
+                    self.spare.pop();
+                    continue;
+                }
+            };
+            let slot = segment.free.pop().unwrap();
+            segment.slots[slot].node = Some(node);
+            segment.live += 1;
+            if segment.free.is_empty() {
+                self.spare.pop();
+            }
+            return Ix { segment: id, slot };
+        }
+        let home = self.home;
+        let segment = self.segment(home);
+        segment.slots.push(Slot {
+            generation: 0,
+            node: Some(node),
+        });
+        segment.live += 1;
+        Ix {
+            segment: home,
+            slot: segment.slots.len() - 1,
+        }
+    }
+
+    pub fn remove(&mut self, x: Ix) -> N {
+        let home = self.home;
+        let segment = self.segment(x.segment);
+        let slot = &mut segment.slots[x.slot];
+        slot.generation += 1;
+        let node = slot.node.take().expect("free arena slot");
+        segment.live -= 1;
+        if segment.live == 0 && x.segment != home {
+            // handles into a dropped segment are invalid like other stale handles
+            self.segments.remove(&x.segment);
+        } else {
+            segment.free.push(x.slot);
+            if segment.free.len() == 1 {
+                self.spare.push(x.segment);
+            }
+        }
+        node
+    }
+
+    pub fn handle(&self, x: Ix) -> Handle {
+        Handle {
+            segment: x.segment,
+            slot: x.slot,
+            generation: self.segments[&x.segment].slots[x.slot].generation,
+        }
+    }
+
+    /// the live node `h` refers to
+    pub fn resolve(&self, h: Handle) -> Result<Ix, HeapError> {
+        match self.segments.get(&h.segment).and_then(|s| s.slots.get(h.slot)) {
+            Some(slot) if slot.generation == h.generation && slot.node.is_some() => Ok(Ix {
+                segment: h.segment,
+                slot: h.slot,
+            }),
+            _ => Err(HeapError::InvalidHandle),
+        }
+    }
+
+    /// take over the segments of `other`. The segment tables are merged smaller into
+    /// larger, so a segment changes tables at most log2(n) times and this is O(log n)
+    /// amortized for arenas of n nodes.
+    pub fn absorb(&mut self, mut other: Arena<N>) {
+        let other_home = other.home;
+        if self.segments.len() < other.segments.len() {
+            mem::swap(&mut self.segments, &mut other.segments);
+            mem::swap(&mut self.spare, &mut other.spare);
+        }
+        let home = self.home;
+        self.segments
+            .extend(other.segments.into_iter().filter(|(id, s)| s.live > 0 || *id == home));
+        self.spare.extend(other.spare);
+        if self.segments.get(&other_home).is_some_and(|s| s.live == 0) && other_home != home {
+            self.segments.remove(&other_home);
+        }
+    }
+}
+
+impl<N> Index<Ix> for Arena<N> {
+    type Output = N;
+
+    #[inline]
+    fn index(&self, x: Ix) -> &N {
+        self.segments[&x.segment].slots[x.slot]
+            .node
+            .as_ref()
+            .expect("free arena slot")
+    }
+}
+
+impl<N> IndexMut<Ix> for Arena<N> {
+    #[inline]
+    fn index_mut(&mut self, x: Ix) -> &mut N {
+        self.segment(x.segment).slots[x.slot]
+            .node
+            .as_mut()
+            .expect("free arena slot")
+    }
+}
+
+#[test]
+fn test_arena_absorb() {
+    let mut arena = Arena::new();
+    let mut xs = vec![arena.alloc(0)];
+    for i in 1..100 {
+        let mut other = Arena::new();
+        xs.push(other.alloc(i));
+        arena.absorb(other);
+    }
+    let handles = xs.iter().map(|&x| arena.handle(x)).collect::<Vec<_>>();
+    assert_eq!(arena.segments.len(), 100);
+    assert!(xs.iter().enumerate().all(|(i, &x)| arena[x] == i));
+
+    // emptied segments are dropped with their stale handles, new nodes reuse a segment
+    for &x in &xs[..50] {
+        arena.remove(x);
+    }
+    assert_eq!(arena.segments.len(), 51);
+    assert_eq!(arena.resolve(handles[1]), Err(HeapError::InvalidHandle));
+    assert_eq!(arena.resolve(handles[50]), Ok(xs[50]));
+    let x = arena.alloc(100);
+    assert_eq!(arena.resolve(handles[0]), Err(HeapError::InvalidHandle));
+    assert_eq!(arena[x], 100);
+
+    // an empty arena brings no segment along
+    arena.absorb(Arena::new());
+    assert_eq!(arena.segments.len(), 51);
+}
+
+/// Model check of a meldable heap: two heaps are melded, then random elements of
+/// both are deleted or have their keys decreased through their handles.
+#[cfg(test)]
+macro_rules! meldable_heap_prop {
+    ($heap:ident) => {{
+        fn prop(xs: Vec<i32>, ys: Vec<i32>, ops: Vec<(bool, u8, u16)>) -> bool {
+            let mut pq = $heap::<i64>::new();
+            let mut other = $heap::<i64>::new();
+            let mut handles = xs.iter().map(|&x| pq.insert(x as i64)).collect::<Vec<_>>();
+            handles.extend(ys.iter().map(|&y| other.insert(y as i64)));
+            // popping sentinels gives both heaps some structure before the meld
+            pq.insert(i64::MIN);
+            pq.pop();
+            other.insert(i64::MIN);
+            other.pop();
+            pq.meld(other);
+
+            let mut model = xs.iter().chain(ys.iter()).map(|&x| Some(x as i64)).collect::<Vec<_>>();
+            for &(delete, i, delta) in &ops {
+                if handles.is_empty() {
+                    break;
+                }
+                let i = i as usize % handles.len();
+                let h = handles[i];
+                if pq.key_of(h) != model[i].as_ref() {
+                    return false;
+                }
+                match model[i] {
+                    None => {
+                        if pq.try_delete(h) != Err($crate::priority_queue::HeapError::InvalidHandle) {
+                            return false;
+                        }
+                    }
+                    Some(key) if delete => {
+                        if pq.delete(h) != key {
+                            return false;
+                        }
+                        model[i] = None;
+                    }
+                    Some(key) => {
+                        let key = key - delta as i64;
+                        pq.decrease_key(h, key);
+                        if pq.try_decrease_key(h, key + 1) != Err($crate::priority_queue::HeapError::KeyIncreased) {
+                            return false;
+                        }
+                        model[i] = Some(key);
+                    }
+                }
+            }
+
+            let mut remaining = model.into_iter().flatten().collect::<Vec<_>>();
+            remaining.sort();
+            if pq.size() != remaining.len() {
+                return false;
+            }
+            let mut keys = vec![];
+            while let Some(k) = pq.pop() {
+                keys.push(k);
+            }
+            keys == remaining && handles.iter().all(|&h| pq.key_of(h).is_none())
+        }
+
+        quickcheck::quickcheck(prop as fn(Vec<i32>, Vec<i32>, Vec<(bool, u8, u16)>) -> bool);
+    }};
+}
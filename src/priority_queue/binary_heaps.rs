@@ -1,233 +1,277 @@
-const INITIAL_SIZE: usize = 1;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::vec;
+
+use super::comparator::{Comparator, MaxOrder, MinOrder};
+
+/// Generic priority queue implementation with a binary heap, ordered by a `Comparator`
+pub struct BinaryHeapPQ<Key, C> {
+    pq: Vec<Key>,
+    cmp: C,
+}
 
 /// Generic max priority queue implementation with a binary heap
-pub struct MaxPQ<Key> {
-    pq: Vec<Option<Key>>,
-    n: usize,
-}
+pub type MaxPQ<Key> = BinaryHeapPQ<Key, MaxOrder>;
 
-impl<Key: PartialOrd> MaxPQ<Key> {
-    fn with_capacity(capacity: usize) -> MaxPQ<Key> {
-        let mut pq = Vec::with_capacity(capacity + 1);
-        for _ in 0..capacity + 1 {
-            pq.push(None);
-        }
-        MaxPQ { pq, n: 0 }
-    }
+/// Generic min priority queue implementation with a binary heap
+pub type MinPQ<Key> = BinaryHeapPQ<Key, MinOrder>;
 
-    fn resize(&mut self, capacity: usize) {
-        assert!(capacity > self.n);
-        let mut temp = Vec::with_capacity(capacity);
-        for i in 0..capacity {
-            if i >= 1 && i <= self.n {
-                temp.push(self.pq[i].take());
-            } else {
-                temp.push(None);
-            }
-        }
-        self.pq = temp;
+impl<Key, C: Comparator<Key>> BinaryHeapPQ<Key, C> {
+    /// should the key at `i` be below the key at `j`?
+    #[inline]
+    fn greater(&self, i: usize, j: usize) -> bool {
+        self.cmp.compare(&self.pq[i], &self.pq[j]) == Ordering::Greater
     }
 
     fn swim(&mut self, k: usize) {
         let mut k = k;
-        while k > 1 && self.pq[k / 2] < self.pq[k] {
-            self.pq.swap(k, k / 2);
-            k /= 2;
+        while k > 0 && self.greater((k - 1) / 2, k) {
+            self.pq.swap(k, (k - 1) / 2);
+            k = (k - 1) / 2;
         }
     }
 
-    fn sink(&mut self, k: usize) {
+    /// sink the key at `k` within the first `n` entries
+    fn sink(&mut self, k: usize, n: usize) {
         let mut k = k;
-        while 2 * k <= self.n {
-            let mut j = 2 * k;
-            if j < self.n && self.pq[j] < self.pq[j + 1] {
+        while 2 * k + 1 < n {
+            let mut j = 2 * k + 1;
+            if j + 1 < n && self.greater(j, j + 1) {
                 j += 1;
             }
-            if self.pq[k] >= self.pq[j] {
+            if !self.greater(k, j) {
                 break;
             }
             self.pq.swap(k, j);
             k = j;
         }
     }
+
+    /// bottom-up heap construction
+    fn rebuild(&mut self) {
+        let n = self.pq.len();
+        for k in (0..n / 2).rev() {
+            self.sink(k, n);
+        }
+    }
 }
 
-impl<Key: PartialOrd> Default for MaxPQ<Key> {
+impl<Key, C: Comparator<Key> + Default> Default for BinaryHeapPQ<Key, C> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<Key: PartialOrd> MaxPQ<Key> {
+impl<Key, C: Comparator<Key> + Default> BinaryHeapPQ<Key, C> {
     /// create an empty priority queue
     pub fn new() -> Self {
-        MaxPQ::with_capacity(INITIAL_SIZE)
+        BinaryHeapPQ::with_comparator(C::default())
     }
 
-    /// create a priority queue with given keys
+    /// create a priority queue with given keys, in linear time
     pub fn from_vec(a: Vec<Key>) -> Self {
-        let mut pq = Self::new();
-        for i in a {
-            pq.insert(i);
-        }
+        Self::from_vec_with_comparator(a, C::default())
+    }
+}
+
+impl<Key, C: Comparator<Key>> BinaryHeapPQ<Key, C> {
+    /// create an empty priority queue ordered by `cmp`
+    pub fn with_comparator(cmp: C) -> Self {
+        BinaryHeapPQ { pq: Vec::new(), cmp }
+    }
+
+    /// create a priority queue with given keys, ordered by `cmp`, in linear time
+    pub fn from_vec_with_comparator(a: Vec<Key>, cmp: C) -> Self {
+        let mut pq = BinaryHeapPQ { pq: a, cmp };
+        pq.rebuild();
         pq
     }
 
     /// insert a key into the priority queue
     pub fn insert(&mut self, x: Key) {
-        let len = self.pq.len();
-        if self.n == len - 1 {
-            self.resize(2 * len);
+        self.pq.push(x);
+        let n = self.pq.len();
+        self.swim(n - 1);
+    }
+
+    /// return and remove the top key
+    pub fn pop(&mut self) -> Option<Key> {
+        if self.pq.is_empty() {
+            return None;
         }
-        self.n += 1;
-        let n = self.n;
-        self.pq[n] = Some(x);
-        self.swim(n);
+        let top = self.pq.swap_remove(0);
+        let n = self.pq.len();
+        self.sink(0, n);
+        Some(top)
     }
 
-    /// return and remove the largest key
-    pub fn del_max(&mut self) -> Option<Key> {
-        let max = self.pq[1].take();
-        self.pq.swap(1, self.n);
-        self.n -= 1;
-        self.sink(1);
-        let len = self.pq.len();
-        if self.n > 0 && self.n == (len - 1) / 4 {
-            self.resize(len / 2);
+    /// return the top key
+    pub fn peek(&self) -> Option<&Key> {
+        self.pq.first()
+    }
+
+    /// mutable access to the top key, the heap is restored when the guard is dropped
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, Key, C>> {
+        if self.pq.is_empty() {
+            None
+        } else {
+            Some(PeekMut { heap: self })
         }
-        max
+    }
+
+    /// the comparator ordering this queue
+    pub fn comparator(&self) -> &C {
+        &self.cmp
     }
 
     /// is the priority queue empty?
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.n == 0
-    }
-
-    /// return the largest key
-    pub fn max(&self) -> Option<&Key> {
-        self.pq[1].as_ref()
+        self.pq.is_empty()
     }
 
     /// number of entries in the priority queue
     #[inline]
     pub fn size(&self) -> usize {
-        self.n
+        self.pq.len()
     }
-}
 
-/// Generic min priority queue implementation with a binary heap
-pub struct MinPQ<Key> {
-    pq: Vec<Option<Key>>,
-    n: usize,
-}
+    /// iterate over all keys in arbitrary order
+    pub fn iter(&self) -> slice::Iter<'_, Key> {
+        self.pq.iter()
+    }
 
-impl<Key: PartialOrd> MinPQ<Key> {
-    fn with_capacity(capacity: usize) -> MinPQ<Key> {
-        let mut pq = Vec::with_capacity(capacity + 1);
-        for _ in 0..capacity + 1 {
-            pq.push(None);
-        }
-        MinPQ { pq, n: 0 }
+    /// remove all keys, yielding them in arbitrary order
+    pub fn drain(&mut self) -> vec::Drain<'_, Key> {
+        self.pq.drain(..)
     }
 
-    fn resize(&mut self, capacity: usize) {
-        assert!(capacity > self.n);
-        let mut temp = Vec::with_capacity(capacity);
-        for i in 0..capacity {
-            if i >= 1 && i <= self.n {
-                temp.push(self.pq[i].take());
-            } else {
-                temp.push(None);
-            }
-        }
-        self.pq = temp;
+    /// keep only the keys for which `f` returns true
+    pub fn retain<F: FnMut(&Key) -> bool>(&mut self, f: F) {
+        self.pq.retain(f);
+        self.rebuild();
     }
 
-    fn swim(&mut self, k: usize) {
-        let mut k = k;
-        while k > 1 && self.pq[k / 2] > self.pq[k] {
-            self.pq.swap(k, k / 2);
-            k /= 2;
+    /// all keys sorted in place by heapsort, in ascending order of the comparator's
+    /// priority: the top key comes last, the reverse of the order `pop` returns them.
+    ///
+    /// Like `std::collections::BinaryHeap::into_sorted_vec`, a `MaxPQ` gives its keys
+    /// in ascending order and a `MinPQ` in descending order.
+    pub fn into_sorted_vec(mut self) -> Vec<Key> {
+        let mut end = self.pq.len();
+        while end > 1 {
+            end -= 1;
+            self.pq.swap(0, end);
+            self.sink(0, end);
         }
+        self.pq
     }
 
-    fn sink(&mut self, k: usize) {
-        let mut k = k;
-        while 2 * k <= self.n {
-            let mut j = 2 * k;
-            if j < self.n && self.pq[j] > self.pq[j + 1] {
-                j += 1;
-            }
-            if self.pq[k] <= self.pq[j] {
-                break;
-            }
-            self.pq.swap(k, j);
-            k = j;
-        }
+    /// all keys in heap order
+    pub fn into_vec(self) -> Vec<Key> {
+        self.pq
     }
 }
 
-impl<Key: PartialOrd> Default for MinPQ<Key> {
-    fn default() -> Self {
-        Self::new()
+impl<Key: PartialOrd> BinaryHeapPQ<Key, MaxOrder> {
+    /// return and remove the largest key
+    pub fn del_max(&mut self) -> Option<Key> {
+        self.pop()
+    }
+
+    /// return the largest key
+    pub fn max(&self) -> Option<&Key> {
+        self.peek()
     }
 }
 
-impl<Key: PartialOrd> MinPQ<Key> {
-    /// create an empty priority queue
-    pub fn new() -> Self {
-        MinPQ::with_capacity(INITIAL_SIZE)
+impl<Key: PartialOrd> BinaryHeapPQ<Key, MinOrder> {
+    /// return and remove the smallest key
+    pub fn del_min(&mut self) -> Option<Key> {
+        self.pop()
     }
 
-    /// create a priority queue with given keys
-    pub fn from_vec(a: Vec<Key>) -> Self {
-        let mut pq = Self::new();
-        for i in a {
-            pq.insert(i);
-        }
-        pq
+    /// return the smallest key
+    pub fn min(&self) -> Option<&Key> {
+        self.peek()
     }
+}
 
-    /// insert a key into the priority queue
-    pub fn insert(&mut self, x: Key) {
-        let len = self.pq.len();
-        if self.n == len - 1 {
-            self.resize(2 * len);
-        }
-        self.n += 1;
-        let n = self.n;
-        self.pq[n] = Some(x);
-        self.swim(n);
+impl<Key, C: Comparator<Key> + Default> FromIterator<Key> for BinaryHeapPQ<Key, C> {
+    fn from_iter<I: IntoIterator<Item = Key>>(iter: I) -> Self {
+        BinaryHeapPQ::from_vec(iter.into_iter().collect())
     }
+}
 
-    /// return and remove the smallest key
-    pub fn del_min(&mut self) -> Option<Key> {
-        let min = self.pq[1].take();
-        self.pq.swap(1, self.n);
-        self.n -= 1;
-        self.sink(1);
-        let len = self.pq.len();
-        if self.n > 0 && self.n == (len - 1) / 4 {
-            self.resize(len / 2);
+impl<Key, C: Comparator<Key>> Extend<Key> for BinaryHeapPQ<Key, C> {
+    fn extend<I: IntoIterator<Item = Key>>(&mut self, iter: I) {
+        let old_len = self.pq.len();
+        self.pq.extend(iter);
+        // rebuilding is linear, swimming each new key is O(k log n)
+        if self.pq.len() - old_len > old_len {
+            self.rebuild();
+        } else {
+            for k in old_len..self.pq.len() {
+                self.swim(k);
+            }
         }
-        min
     }
+}
 
-    /// is the priority queue empty?
-    #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.n == 0
+impl<Key, C> IntoIterator for BinaryHeapPQ<Key, C> {
+    type Item = Key;
+    type IntoIter = vec::IntoIter<Key>;
+
+    /// all keys in arbitrary order
+    fn into_iter(self) -> vec::IntoIter<Key> {
+        self.pq.into_iter()
     }
+}
 
-    /// return the smallest key
-    pub fn min(&self) -> Option<&Key> {
-        self.pq[1].as_ref()
+impl<'a, Key, C: Comparator<Key>> IntoIterator for &'a BinaryHeapPQ<Key, C> {
+    type Item = &'a Key;
+    type IntoIter = slice::Iter<'a, Key>;
+
+    fn into_iter(self) -> slice::Iter<'a, Key> {
+        self.iter()
     }
+}
 
-    /// number of entries in the priority queue
-    pub fn size(&self) -> usize {
-        self.n
+/// Mutable reference to the top key of a `BinaryHeapPQ`, created by `peek_mut`.
+///
+/// The key is sunk to its place when the guard is dropped.
+pub struct PeekMut<'a, Key, C: Comparator<Key>> {
+    heap: &'a mut BinaryHeapPQ<Key, C>,
+}
+
+impl<'a, Key, C: Comparator<Key>> PeekMut<'a, Key, C> {
+    /// remove the peeked key from the heap
+    pub fn pop(this: PeekMut<'a, Key, C>) -> Key {
+        // Drop sinks the new top
+        this.heap.pq.swap_remove(0)
+    }
+}
+
+impl<'a, Key, C: Comparator<Key>> Deref for PeekMut<'a, Key, C> {
+    type Target = Key;
+
+    fn deref(&self) -> &Key {
+        &self.heap.pq[0]
+    }
+}
+
+impl<'a, Key, C: Comparator<Key>> DerefMut for PeekMut<'a, Key, C> {
+    fn deref_mut(&mut self) -> &mut Key {
+        &mut self.heap.pq[0]
+    }
+}
+
+impl<'a, Key, C: Comparator<Key>> Drop for PeekMut<'a, Key, C> {
+    fn drop(&mut self) {
+        let n = self.heap.pq.len();
+        self.heap.sink(0, n);
     }
 }
 
@@ -295,3 +339,58 @@ fn test_empty_binary_heap() {
     assert_eq!(pq.size(), 0);
     assert_eq!(pq.is_empty(), true);
 }
+
+#[test]
+fn test_binary_heap_with_comparator() {
+    use super::comparator::ByKey;
+    use std::cmp::Reverse;
+
+    // (deadline, priority): earliest deadline first, then highest priority
+    let tasks = vec![(3, 1), (1, 5), (3, 9), (2, 2), (1, 7)];
+    let mut pq = BinaryHeapPQ::from_vec_with_comparator(tasks, ByKey(|t: &(u32, u32)| (t.0, Reverse(t.1))));
+    let mut order = vec![];
+    while let Some(t) = pq.pop() {
+        order.push(t);
+    }
+    assert_eq!(order, vec![(1, 7), (1, 5), (2, 2), (3, 9), (3, 1)]);
+
+    let mut pq = BinaryHeapPQ::with_comparator(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+    pq.insert("three");
+    pq.insert("a");
+    pq.insert("to");
+    assert_eq!(pq.peek(), Some(&"a"));
+    assert_eq!(pq.size(), 3);
+}
+
+#[test]
+fn test_binary_heap_collection_api() {
+    let mut pq: MinPQ<i32> = vec![5, 1, 8, 3, 9, 2].into_iter().collect();
+    assert_eq!(pq.min(), Some(&1));
+    assert_eq!(pq.iter().count(), 6);
+
+    pq.extend(vec![7, 0, 4]);
+    assert_eq!(pq.min(), Some(&0));
+
+    if let Some(mut top) = pq.peek_mut() {
+        *top = 6;
+    }
+    assert_eq!(pq.min(), Some(&1));
+    assert_eq!(PeekMut::pop(pq.peek_mut().unwrap()), 1);
+    assert_eq!(pq.min(), Some(&2));
+
+    pq.retain(|&k| k % 2 == 0);
+    assert_eq!(pq.size(), 4);
+
+    let mut drained = pq.drain().collect::<Vec<_>>();
+    drained.sort();
+    assert_eq!(drained, vec![2, 4, 6, 8]);
+    assert!(pq.is_empty());
+
+    let pq: MaxPQ<char> = "HEAPSORT".chars().collect();
+    assert_eq!(pq.into_sorted_vec(), "AEHOPRST".chars().collect::<Vec<_>>());
+    let pq: MinPQ<char> = "HEAPSORT".chars().collect();
+    assert_eq!(pq.into_sorted_vec(), "TSRPOHEA".chars().collect::<Vec<_>>());
+    let xs = vec![5, 1, 8, 3, 8, 2];
+    let sorted = std::collections::BinaryHeap::from(xs.clone()).into_sorted_vec();
+    assert_eq!(xs.into_iter().collect::<MaxPQ<_>>().into_sorted_vec(), sorted);
+}
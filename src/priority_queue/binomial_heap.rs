@@ -0,0 +1,317 @@
+use super::arena::{unwrap_or_panic, Arena, Handle, HeapError, Ix, NIL};
+use super::comparator::{Comparator, MinOrder};
+use std::cmp::Ordering;
+
+// Keys live in items separate from tree nodes, so `decrease_key` can swap
+// items up the tree while handles keep pointing at the same item.
+struct Item<K> {
+    key: K,
+    node: Ix,
+}
+
+struct Node {
+    item: Ix,
+    parent: Ix,
+    // leftmost child, children are in decreasing order of degree
+    child: Ix,
+    sibling: Ix,
+    degree: usize,
+}
+
+/// Binomial heap, a forest of binomial trees with distinct degrees.
+///
+/// `insert`, `peek`, `pop`, `decrease_key` and `delete` are O(log n). `meld` unions
+/// the root lists and hands the arena segments of one heap to the other without
+/// moving nodes, O(log n) amortized.
+pub struct BinomialHeap<K, C = MinOrder> {
+    items: Arena<Item<K>>,
+    nodes: Arena<Node>,
+    // root list in increasing order of degree
+    head: Ix,
+    n: usize,
+    cmp: C,
+}
+
+impl<K, C: Comparator<K> + Default> Default for BinomialHeap<K, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, C: Comparator<K> + Default> BinomialHeap<K, C> {
+    pub fn new() -> Self {
+        BinomialHeap::with_comparator(C::default())
+    }
+}
+
+impl<K, C: Comparator<K>> BinomialHeap<K, C> {
+    pub fn with_comparator(cmp: C) -> Self {
+        BinomialHeap {
+            items: Arena::new(),
+            nodes: Arena::new(),
+            head: NIL,
+            n: 0,
+            cmp,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    #[inline]
+    fn key(&self, x: Ix) -> &K {
+        &self.items[self.nodes[x].item].key
+    }
+
+    #[inline]
+    fn less(&self, x: Ix, y: Ix) -> bool {
+        self.cmp.compare(self.key(x), self.key(y)) == Ordering::Less
+    }
+
+    /// insert a key, returning a handle for `decrease_key` and `delete`
+    pub fn insert(&mut self, key: K) -> Handle {
+        let item = self.items.alloc(Item { key, node: NIL });
+        let x = self.nodes.alloc(Node {
+            item,
+            parent: NIL,
+            child: NIL,
+            sibling: NIL,
+            degree: 0,
+        });
+        self.items[item].node = x;
+        self.head = self.union(self.head, x);
+        self.n += 1;
+        self.items.handle(item)
+    }
+
+    fn min_root(&self) -> Ix {
+        let mut min = self.head;
+        let mut x = self.head;
+        while x != NIL {
+            if self.less(x, min) {
+                min = x;
+            }
+            x = self.nodes[x].sibling;
+        }
+        min
+    }
+
+    /// return the top key
+    pub fn peek(&self) -> Option<&K> {
+        match self.min_root() {
+            NIL => None,
+            min => Some(self.key(min)),
+        }
+    }
+
+    /// return and remove the top key
+    pub fn pop(&mut self) -> Option<K> {
+        match self.min_root() {
+            NIL => None,
+            min => Some(self.remove_root(min)),
+        }
+    }
+
+    /// move all keys of `other` into this heap, keeping this heap's comparator.
+    /// Handles into `other` stay valid for this heap.
+    pub fn meld(&mut self, other: BinomialHeap<K, C>) {
+        self.items.absorb(other.items);
+        self.nodes.absorb(other.nodes);
+        self.head = self.union(self.head, other.head);
+        self.n += other.n;
+    }
+
+    /// does `h` refer to an element of this heap
+    pub fn contains(&self, h: Handle) -> bool {
+        self.items.resolve(h).is_ok()
+    }
+
+    /// the key of an element, `None` if it is not in the heap
+    pub fn key_of(&self, h: Handle) -> Option<&K> {
+        self.items.resolve(h).ok().map(|i| &self.items[i].key)
+    }
+
+    pub fn decrease_key(&mut self, h: Handle, key: K) {
+        unwrap_or_panic(self.try_decrease_key(h, key))
+    }
+
+    /// replace the key of an element with one not farther from the top
+    pub fn try_decrease_key(&mut self, h: Handle, key: K) -> Result<(), HeapError> {
+        let i = self.items.resolve(h)?;
+        if self.cmp.compare(&key, &self.items[i].key) == Ordering::Greater {
+            return Err(HeapError::KeyIncreased);
+        }
+        self.items[i].key = key;
+        let x = self.items[i].node;
+        self.bubble_up(x, false);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, h: Handle) -> K {
+        unwrap_or_panic(self.try_delete(h))
+    }
+
+    /// remove an element, returning its key
+    pub fn try_delete(&mut self, h: Handle) -> Result<K, HeapError> {
+        let i = self.items.resolve(h)?;
+        let x = self.items[i].node;
+        let root = self.bubble_up(x, true);
+        Ok(self.remove_root(root))
+    }
+
+    /// swap the item of `x` towards the root while it is smaller than its parent's,
+    /// or all the way up when `force` is set. Returns the node it ends up in.
+    fn bubble_up(&mut self, x: Ix, force: bool) -> Ix {
+        let mut x = x;
+        while self.nodes[x].parent != NIL && (force || self.less(x, self.nodes[x].parent)) {
+            let y = self.nodes[x].parent;
+            let (a, b) = (self.nodes[x].item, self.nodes[y].item);
+            self.nodes[x].item = b;
+            self.nodes[y].item = a;
+            self.items[a].node = y;
+            self.items[b].node = x;
+            x = y;
+        }
+        x
+    }
+
+    /// remove the tree rooted at `root` from the root list and return its key
+    fn remove_root(&mut self, root: Ix) -> K {
+        if self.head == root {
+            self.head = self.nodes[root].sibling;
+        } else {
+            let mut prev = self.head;
+            while self.nodes[prev].sibling != root {
+                prev = self.nodes[prev].sibling;
+            }
+            self.nodes[prev].sibling = self.nodes[root].sibling;
+        }
+
+        // children in reverse order form a root list
+        let mut children = NIL;
+        let mut x = self.nodes[root].child;
+        while x != NIL {
+            let next = self.nodes[x].sibling;
+            self.nodes[x].sibling = children;
+            self.nodes[x].parent = NIL;
+            children = x;
+            x = next;
+        }
+        self.head = self.union(self.head, children);
+        self.n -= 1;
+
+        let node = self.nodes.remove(root);
+        self.items.remove(node.item).key
+    }
+
+    /// make `y` the leftmost child of `z`, both roots of the same degree
+    fn link(&mut self, y: Ix, z: Ix) {
+        self.nodes[y].parent = z;
+        self.nodes[y].sibling = self.nodes[z].child;
+        self.nodes[z].child = y;
+        self.nodes[z].degree += 1;
+    }
+
+    /// merge two root lists by degree
+    fn merge(&mut self, a: Ix, b: Ix) -> Ix {
+        let mut head = NIL;
+        let mut tail = NIL;
+        let (mut a, mut b) = (a, b);
+        while a != NIL || b != NIL {
+            let next;
+            if b == NIL || (a != NIL && self.nodes[a].degree <= self.nodes[b].degree) {
+                next = a;
+                a = self.nodes[a].sibling;
+            } else {
+                next = b;
+                b = self.nodes[b].sibling;
+            }
+            if tail == NIL {
+                head = next;
+            } else {
+                self.nodes[tail].sibling = next;
+            }
+            tail = next;
+        }
+        head
+    }
+
+    fn union(&mut self, a: Ix, b: Ix) -> Ix {
+        let mut head = self.merge(a, b);
+        if head == NIL {
+            return head;
+        }
+        let mut prev = NIL;
+        let mut x = head;
+        let mut next = self.nodes[x].sibling;
+        while next != NIL {
+            let after = self.nodes[next].sibling;
+            if self.nodes[x].degree != self.nodes[next].degree
+                || (after != NIL && self.nodes[after].degree == self.nodes[x].degree)
+            {
+                prev = x;
+                x = next;
+            } else if !self.less(next, x) {
+                self.nodes[x].sibling = after;
+                self.link(next, x);
+            } else {
+                if prev == NIL {
+                    head = next;
+                } else {
+                    self.nodes[prev].sibling = next;
+                }
+                self.link(x, next);
+                x = next;
+            }
+            next = self.nodes[x].sibling;
+        }
+        head
+    }
+}
+
+#[test]
+fn test_binomial_heap() {
+    let mut pq = BinomialHeap::<i32>::new();
+    let mut handles = vec![];
+    for k in 0..20 {
+        handles.push(pq.insert((k * 7) % 20));
+    }
+    assert_eq!(pq.peek(), Some(&0));
+
+    // the key 19 was inserted at position 17
+    pq.decrease_key(handles[17], -5);
+    assert_eq!(pq.delete(handles[3]), 1);
+    assert_eq!(pq.key_of(handles[4]), Some(&8));
+    assert_eq!(pq.key_of(handles[3]), None);
+    assert_eq!(pq.try_delete(handles[3]), Err(HeapError::InvalidHandle));
+    assert_eq!(pq.try_decrease_key(handles[4], 9), Err(HeapError::KeyIncreased));
+
+    let mut other = BinomialHeap::new();
+    other.insert(100);
+    let h = other.insert(-10);
+    pq.meld(other);
+    assert_eq!(pq.size(), 21);
+    assert_eq!(pq.key_of(h), Some(&-10));
+
+    let mut keys = vec![];
+    while let Some(k) = pq.pop() {
+        keys.push(k);
+    }
+    let mut expected = (0..19).filter(|&k| k != 1).collect::<Vec<_>>();
+    expected.insert(0, -5);
+    expected.insert(0, -10);
+    expected.push(100);
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn quicktest_binomial_heap() {
+    meldable_heap_prop!(BinomialHeap);
+}
@@ -0,0 +1,64 @@
+use std::cmp::Ordering;
+
+/// Ordering used by the heaps in this module.
+///
+/// The key comparing `Less` than every other key is at the top of the heap, so
+/// `MinOrder` gives a min-heap and `MaxOrder` a max-heap. Any closure
+/// `Fn(&T, &T) -> Ordering` is a comparator as well.
+pub trait Comparator<T: ?Sized> {
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+/// Natural order, smallest key first. Incomparable keys are treated as equal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinOrder;
+
+impl<T: PartialOrd + ?Sized> Comparator<T> for MinOrder {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.partial_cmp(b).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Reversed natural order, largest key first. Incomparable keys are treated as equal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaxOrder;
+
+impl<T: PartialOrd + ?Sized> Comparator<T> for MaxOrder {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        b.partial_cmp(a).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Orders keys by an `Ord` projection, smallest projection first.
+///
+/// `ByKey(|t: &Task| (t.deadline, Reverse(t.priority)))` serves the earliest deadline
+/// first and breaks ties by the highest priority, without a newtype around `Task`.
+#[derive(Clone, Copy, Debug)]
+pub struct ByKey<F>(pub F);
+
+impl<T: ?Sized, K: Ord, F: Fn(&T) -> K> Comparator<T> for ByKey<F> {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        (self.0)(a).cmp(&(self.0)(b))
+    }
+}
+
+/// Reverses another comparator, turning a min-heap order into a max-heap order.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Reversed<C>(pub C);
+
+impl<T: ?Sized, C: Comparator<T>> Comparator<T> for Reversed<C> {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.compare(b, a)
+    }
+}
+
+impl<T: ?Sized, F: Fn(&T, &T) -> Ordering> Comparator<T> for F {
+    #[inline]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
@@ -0,0 +1,158 @@
+use super::comparator::{Comparator, MinOrder};
+use std::cmp::Ordering;
+
+const DEFAULT_ARITY: usize = 4;
+
+/// Generic priority queue implementation with a d-ary heap, ordered by a `Comparator`
+///
+/// A wider heap is shallower, so `insert` does fewer comparisons and `pop` more,
+/// with better cache locality than a binary heap. The indexed variant is
+/// `IndexPQ::with_arity`.
+pub struct DaryHeap<Key, C = MinOrder> {
+    d: usize,
+    pq: Vec<Key>,
+    cmp: C,
+}
+
+impl<Key, C: Comparator<Key> + Default> Default for DaryHeap<Key, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, C: Comparator<Key> + Default> DaryHeap<Key, C> {
+    /// create an empty 4-ary heap
+    pub fn new() -> Self {
+        DaryHeap::with_arity(DEFAULT_ARITY)
+    }
+
+    /// create an empty `d`-ary heap
+    pub fn with_arity(d: usize) -> Self {
+        DaryHeap::with_arity_and_comparator(d, C::default())
+    }
+
+    /// create a `d`-ary heap with given keys, in linear time
+    pub fn from_vec(d: usize, a: Vec<Key>) -> Self {
+        Self::from_vec_with_comparator(d, a, C::default())
+    }
+}
+
+impl<Key, C: Comparator<Key>> DaryHeap<Key, C> {
+    pub fn with_arity_and_comparator(d: usize, cmp: C) -> Self {
+        assert!(d >= 2, "arity must be at least 2");
+        DaryHeap { d, pq: Vec::new(), cmp }
+    }
+
+    /// create a `d`-ary heap with given keys, ordered by `cmp`, in linear time
+    pub fn from_vec_with_comparator(d: usize, a: Vec<Key>, cmp: C) -> Self {
+        let mut heap = DaryHeap::with_arity_and_comparator(d, cmp);
+        heap.pq = a;
+        if heap.pq.len() > 1 {
+            for k in (0..=(heap.pq.len() - 2) / d).rev() {
+                heap.sink(k);
+            }
+        }
+        heap
+    }
+
+    #[inline]
+    fn greater(&self, i: usize, j: usize) -> bool {
+        self.cmp.compare(&self.pq[i], &self.pq[j]) == Ordering::Greater
+    }
+
+    fn swim(&mut self, k: usize) {
+        let mut k = k;
+        while k > 0 && self.greater((k - 1) / self.d, k) {
+            let parent = (k - 1) / self.d;
+            self.pq.swap(k, parent);
+            k = parent;
+        }
+    }
+
+    fn sink(&mut self, k: usize) {
+        let n = self.pq.len();
+        let mut k = k;
+        loop {
+            let first = self.d * k + 1;
+            if first >= n {
+                break;
+            }
+            let mut j = first;
+            for c in first + 1..(first + self.d).min(n) {
+                if self.greater(j, c) {
+                    j = c;
+                }
+            }
+            if !self.greater(k, j) {
+                break;
+            }
+            self.pq.swap(k, j);
+            k = j;
+        }
+    }
+
+    /// arity of the heap
+    pub fn arity(&self) -> usize {
+        self.d
+    }
+
+    /// insert a key into the priority queue
+    pub fn insert(&mut self, x: Key) {
+        self.pq.push(x);
+        let n = self.pq.len();
+        self.swim(n - 1);
+    }
+
+    /// return and remove the top key
+    pub fn pop(&mut self) -> Option<Key> {
+        if self.pq.is_empty() {
+            return None;
+        }
+        let top = self.pq.swap_remove(0);
+        self.sink(0);
+        Some(top)
+    }
+
+    /// return the top key
+    pub fn peek(&self) -> Option<&Key> {
+        self.pq.first()
+    }
+
+    /// is the priority queue empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pq.is_empty()
+    }
+
+    /// number of entries in the priority queue
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.pq.len()
+    }
+}
+
+#[test]
+fn test_dary_heap() {
+    for &d in [2, 3, 4, 8].iter() {
+        let mut pq: DaryHeap<i32> = DaryHeap::with_arity(d);
+        for i in 0..100 {
+            pq.insert((i * 37) % 101);
+        }
+        assert_eq!(pq.size(), 100);
+        assert_eq!(pq.peek(), Some(&0));
+
+        let keys = (0..100).map(|_| pq.pop().unwrap()).collect::<Vec<_>>();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+        assert!(pq.is_empty());
+
+        let mut pq: DaryHeap<i32> = DaryHeap::from_vec(d, (0..50).rev().collect());
+        assert_eq!(pq.pop(), Some(0));
+        assert_eq!(pq.pop(), Some(1));
+
+        let mut pq = DaryHeap::from_vec_with_comparator(d, (0..50).collect(), |a: &i32, b: &i32| b.cmp(a));
+        assert_eq!(pq.pop(), Some(49));
+        assert_eq!(pq.pop(), Some(48));
+    }
+}
@@ -0,0 +1,385 @@
+use super::arena::{unwrap_or_panic, Arena, Handle, HeapError, Ix, NIL};
+use super::comparator::{Comparator, MinOrder};
+use std::cmp::Ordering;
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+struct Node<K> {
+    key: K,
+    parent: Ix,
+    // any child, children form a circular doubly linked list
+    child: Ix,
+    left: Ix,
+    right: Ix,
+    degree: usize,
+    // lost a child since it became a child itself
+    mark: bool,
+}
+
+/// Fibonacci heap, a lazily consolidated forest of heap-ordered trees.
+///
+/// `insert`, `peek` and `decrease_key` are O(1) amortized, `pop` and `delete`
+/// are O(log n) amortized. `meld` splices the root lists and hands the arena
+/// segments of one heap to the other without moving nodes, O(log n) amortized.
+pub struct FibonacciHeap<K, C = MinOrder> {
+    nodes: Arena<Node<K>>,
+    // top of the circular root list
+    min: Ix,
+    n: usize,
+    cmp: C,
+}
+
+impl<K, C: Comparator<K> + Default> Default for FibonacciHeap<K, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, C: Comparator<K> + Default> FibonacciHeap<K, C> {
+    pub fn new() -> Self {
+        FibonacciHeap::with_comparator(C::default())
+    }
+}
+
+impl<K, C: Comparator<K>> FibonacciHeap<K, C> {
+    pub fn with_comparator(cmp: C) -> Self {
+        FibonacciHeap {
+            nodes: Arena::new(),
+            min: NIL,
+            n: 0,
+            cmp,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    #[inline]
+    fn less(&self, x: Ix, y: Ix) -> bool {
+        self.cmp.compare(&self.nodes[x].key, &self.nodes[y].key) == Ordering::Less
+    }
+
+    /// insert a key, returning a handle for `decrease_key` and `delete`
+    pub fn insert(&mut self, key: K) -> Handle {
+        let x = self.nodes.alloc(Node {
+            key,
+            parent: NIL,
+            child: NIL,
+            left: NIL,
+            right: NIL,
+            degree: 0,
+            mark: false,
+        });
+        self.add_root(x);
+        self.n += 1;
+        self.nodes.handle(x)
+    }
+
+    /// return the top key
+    pub fn peek(&self) -> Option<&K> {
+        match self.min {
+            NIL => None,
+            x => Some(&self.nodes[x].key),
+        }
+    }
+
+    /// return and remove the top key
+    pub fn pop(&mut self) -> Option<K> {
+        if self.min == NIL {
+            None
+        } else {
+            Some(self.remove_min())
+        }
+    }
+
+    /// move all keys of `other` into this heap, keeping this heap's comparator.
+    /// Handles into `other` stay valid for this heap.
+    pub fn meld(&mut self, other: FibonacciHeap<K, C>) {
+        self.nodes.absorb(other.nodes);
+        let (ours, theirs) = (self.min, other.min);
+        if ours == NIL {
+            self.min = theirs;
+        } else if theirs != NIL {
+            self.splice(ours, theirs);
+            if self.less(theirs, ours) {
+                self.min = theirs;
+            }
+        }
+        self.n += other.n;
+    }
+
+    /// does `h` refer to an element of this heap
+    pub fn contains(&self, h: Handle) -> bool {
+        self.nodes.resolve(h).is_ok()
+    }
+
+    /// the key of an element, `None` if it is not in the heap
+    pub fn key_of(&self, h: Handle) -> Option<&K> {
+        self.nodes.resolve(h).ok().map(|x| &self.nodes[x].key)
+    }
+
+    pub fn decrease_key(&mut self, h: Handle, key: K) {
+        unwrap_or_panic(self.try_decrease_key(h, key))
+    }
+
+    /// replace the key of an element with one not farther from the top
+    pub fn try_decrease_key(&mut self, h: Handle, key: K) -> Result<(), HeapError> {
+        let x = self.nodes.resolve(h)?;
+        if self.cmp.compare(&key, &self.nodes[x].key) == Ordering::Greater {
+            return Err(HeapError::KeyIncreased);
+        }
+        self.nodes[x].key = key;
+        let y = self.nodes[x].parent;
+        if y != NIL && self.less(x, y) {
+            self.cut(x, y);
+            self.cascading_cut(y);
+        }
+        if self.less(x, self.min) {
+            self.min = x;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, h: Handle) -> K {
+        unwrap_or_panic(self.try_delete(h))
+    }
+
+    /// remove an element, returning its key
+    pub fn try_delete(&mut self, h: Handle) -> Result<K, HeapError> {
+        let x = self.nodes.resolve(h)?;
+        let y = self.nodes[x].parent;
+        if y != NIL {
+            self.cut(x, y);
+            self.cascading_cut(y);
+        }
+        self.min = x;
+        Ok(self.remove_min())
+    }
+
+    fn add_root(&mut self, x: Ix) {
+        let node = &mut self.nodes[x];
+        node.parent = NIL;
+        node.mark = false;
+        node.left = x;
+        node.right = x;
+        if self.min == NIL {
+            self.min = x;
+        } else {
+            self.splice(self.min, x);
+            if self.less(x, self.min) {
+                self.min = x;
+            }
+        }
+    }
+
+    /// move `x` from the children of `y` to the root list
+    fn cut(&mut self, x: Ix, y: Ix) {
+        if self.nodes[x].right == x {
+            self.nodes[y].child = NIL;
+        } else {
+            if self.nodes[y].child == x {
+                self.nodes[y].child = self.nodes[x].right;
+            }
+            self.unlink(x);
+        }
+        self.nodes[y].degree -= 1;
+        self.add_root(x);
+    }
+
+    fn cascading_cut(&mut self, y: Ix) {
+        let mut y = y;
+        loop {
+            let z = self.nodes[y].parent;
+            if z == NIL {
+                break;
+            }
+            if !self.nodes[y].mark {
+                self.nodes[y].mark = true;
+                break;
+            }
+            self.cut(y, z);
+            y = z;
+        }
+    }
+
+    fn remove_min(&mut self) -> K {
+        let z = self.min;
+        // promote all children of z to roots
+        let child = self.nodes[z].child;
+        if child != NIL {
+            let mut x = child;
+            loop {
+                self.nodes[x].parent = NIL;
+                self.nodes[x].mark = false;
+                x = self.nodes[x].right;
+                if x == child {
+                    break;
+                }
+            }
+            self.splice(z, child);
+            self.nodes[z].child = NIL;
+        }
+
+        if self.nodes[z].right == z {
+            self.min = NIL;
+        } else {
+            self.min = self.nodes[z].right;
+            self.unlink(z);
+            self.consolidate();
+        }
+        self.n -= 1;
+        self.nodes.remove(z).key
+    }
+
+    /// link roots of equal degree until all degrees are distinct
+    fn consolidate(&mut self) {
+        let mut roots = Vec::new();
+        let mut x = self.min;
+        loop {
+            roots.push(x);
+            x = self.nodes[x].right;
+            if x == self.min {
+                break;
+            }
+        }
+
+        let mut by_degree: Vec<Ix> = Vec::new();
+        for root in roots {
+            let mut x = root;
+            self.nodes[x].left = x;
+            self.nodes[x].right = x;
+            loop {
+                let d = self.nodes[x].degree;
+                if by_degree.len() <= d {
+                    by_degree.resize(d + 1, NIL);
+                }
+                let mut y = by_degree[d];
+                if y == NIL {
+                    by_degree[d] = x;
+                    break;
+                }
+                if self.less(y, x) {
+                    std::mem::swap(&mut x, &mut y);
+                }
+                // y becomes a child of x
+                self.nodes[y].parent = x;
+                self.nodes[y].mark = false;
+                let child = self.nodes[x].child;
+                if child == NIL {
+                    self.nodes[x].child = y;
+                } else {
+                    self.splice(child, y);
+                }
+                self.nodes[x].degree += 1;
+                by_degree[d] = NIL;
+            }
+        }
+
+        self.min = NIL;
+        for root in by_degree {
+            if root != NIL {
+                self.add_root(root);
+            }
+        }
+    }
+
+    /// join two disjoint circular lists
+    fn splice(&mut self, a: Ix, b: Ix) {
+        let a_right = self.nodes[a].right;
+        let b_left = self.nodes[b].left;
+        self.nodes[a].right = b;
+        self.nodes[b].left = a;
+        self.nodes[b_left].right = a_right;
+        self.nodes[a_right].left = b_left;
+    }
+
+    /// remove `x` from its circular list, leaving it as a single-element list
+    fn unlink(&mut self, x: Ix) {
+        let (left, right) = (self.nodes[x].left, self.nodes[x].right);
+        self.nodes[left].right = right;
+        self.nodes[right].left = left;
+        self.nodes[x].left = x;
+        self.nodes[x].right = x;
+    }
+}
+
+#[test]
+fn test_fibonacci_heap() {
+    let mut pq = FibonacciHeap::<i32>::new();
+    let mut handles = vec![];
+    for k in 0..50 {
+        handles.push(pq.insert((k * 13) % 50));
+    }
+    assert_eq!(pq.pop(), Some(0));
+    assert_eq!(pq.pop(), Some(1));
+
+    // the key 49 was inserted at position 23, 26 at position 2
+    pq.decrease_key(handles[23], -1);
+    assert_eq!(pq.delete(handles[2]), 26);
+    assert_eq!(pq.key_of(handles[3]), Some(&39));
+    // 0 was popped, its handle is stale
+    assert_eq!(pq.key_of(handles[0]), None);
+    assert_eq!(pq.try_decrease_key(handles[0], -100), Err(HeapError::InvalidHandle));
+    assert_eq!(pq.peek(), Some(&-1));
+
+    let mut other = FibonacciHeap::new();
+    other.insert(100);
+    other.insert(-10);
+    pq.meld(other);
+    assert_eq!(pq.size(), 49);
+
+    let mut keys = vec![];
+    while let Some(k) = pq.pop() {
+        keys.push(k);
+    }
+    let mut expected = (2..49).filter(|&k| k != 26).collect::<Vec<_>>();
+    expected.insert(0, -1);
+    expected.insert(0, -10);
+    expected.push(100);
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn quicktest_fibonacci_heap() {
+    fn prop(xs: Vec<i32>) -> bool {
+        let mut pq = FibonacciHeap::<i64>::new();
+        let handles = xs.iter().map(|&x| pq.insert(x as i64)).collect::<Vec<_>>();
+        // popping a sentinel consolidates the trees before decrease_key and delete
+        pq.insert(i64::MIN);
+        pq.pop();
+
+        let mut remaining = vec![];
+        for (i, &h) in handles.iter().enumerate() {
+            let key = *pq.key_of(h).unwrap();
+            if i % 5 == 0 {
+                pq.delete(h);
+            } else if i % 3 == 0 {
+                pq.decrease_key(h, key - 1000);
+                remaining.push(key - 1000);
+            } else {
+                remaining.push(key);
+            }
+        }
+        remaining.sort();
+        let mut keys = vec![];
+        while let Some(k) = pq.pop() {
+            keys.push(k);
+        }
+        keys == remaining
+    }
+
+    quickcheck(prop as fn(Vec<i32>) -> bool);
+}
+
+#[test]
+fn quicktest_fibonacci_heap_meld() {
+    meldable_heap_prop!(FibonacciHeap);
+}
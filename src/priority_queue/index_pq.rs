@@ -1,26 +1,134 @@
-use std::iter;
-use std::usize;
+use super::comparator::{Comparator, MaxOrder, MinOrder};
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
 
-pub struct IndexMinPQ<T: PartialOrd> {
+/// Errors reported by the `try_*` methods of `IndexPQ`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexPQError {
+    /// the index is beyond the current capacity of the queue
+    IndexOutOfRange(usize),
+    /// no key is associated with the index
+    NoSuchIndex(usize),
+    /// a key is already associated with the index
+    DuplicateIndex(usize),
+    /// the queue is empty
+    Underflow,
+}
+
+impl fmt::Display for IndexPQError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexPQError::IndexOutOfRange(i) => write!(f, "index {} out of range", i),
+            IndexPQError::NoSuchIndex(i) => write!(f, "index {} is not in the priority queue", i),
+            IndexPQError::DuplicateIndex(i) => write!(f, "index {} is already in the priority queue", i),
+            IndexPQError::Underflow => write!(f, "priority queue underflow"),
+        }
+    }
+}
+
+impl error::Error for IndexPQError {}
+
+fn unwrap_or_panic<R>(result: Result<R, IndexPQError>) -> R {
+    match result {
+        Ok(r) => r,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Indexed priority queue with a d-ary heap (binary by default), ordered by a `Comparator`.
+///
+/// Each key is associated with an index, which can be used to change or delete
+/// the key later. Inserting an index beyond the capacity grows the queue.
+pub struct IndexPQ<T, C> {
+    // arity of the heap
+    d: usize,
     nmax: usize,
     n: usize,
     pq: Vec<usize>,
     qp: Vec<usize>,
     keys: Vec<Option<T>>,
+    cmp: C,
 }
 
-impl<T: PartialOrd> IndexMinPQ<T> {
-    pub fn with_capacity(nmax: usize) -> IndexMinPQ<T> {
+/// Indexed min priority queue
+pub type IndexMinPQ<T> = IndexPQ<T, MinOrder>;
+
+/// Indexed max priority queue
+pub type IndexMaxPQ<T> = IndexPQ<T, MaxOrder>;
+
+impl<T, C: Comparator<T> + Default> Default for IndexPQ<T, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, C: Comparator<T> + Default> IndexPQ<T, C> {
+    /// create an empty queue, which grows as indices are inserted
+    pub fn new() -> IndexPQ<T, C> {
+        IndexPQ::with_capacity(0)
+    }
+
+    pub fn with_capacity(nmax: usize) -> IndexPQ<T, C> {
+        IndexPQ::with_capacity_and_comparator(nmax, C::default())
+    }
+
+    /// create an empty queue backed by a `d`-ary heap, 4 or 8 often beat binary for
+    /// `decrease_key` heavy workloads such as Dijkstra
+    pub fn with_arity(d: usize) -> IndexPQ<T, C> {
+        IndexPQ::with_arity_and_comparator(d, C::default())
+    }
+}
+
+impl<T, C: Comparator<T>> IndexPQ<T, C> {
+    pub fn with_capacity_and_comparator(nmax: usize, cmp: C) -> IndexPQ<T, C> {
         let mut keys = Vec::new();
         for _ in 0..nmax + 1 {
             keys.push(None);
         }
-        IndexMinPQ {
+        IndexPQ {
+            d: 2,
             nmax,
             n: 0,
-            pq: iter::repeat(0).take(nmax + 1).collect(),
-            qp: iter::repeat(usize::MAX).take(nmax + 1).collect(),
+            pq: vec![0; nmax + 1],
+            qp: vec![usize::MAX; nmax + 1],
             keys,
+            cmp,
+        }
+    }
+
+    pub fn with_arity_and_comparator(d: usize, cmp: C) -> IndexPQ<T, C> {
+        assert!(d >= 2, "arity must be at least 2");
+        let mut pq = IndexPQ::with_capacity_and_comparator(0, cmp);
+        pq.d = d;
+        pq
+    }
+
+    /// arity of the underlying heap
+    pub fn arity(&self) -> usize {
+        self.d
+    }
+
+    /// current index range, indices in `0..capacity()` need no reallocation
+    pub fn capacity(&self) -> usize {
+        self.nmax
+    }
+
+    fn grow(&mut self, i: usize) {
+        let nmax = (i + 1).max(2 * self.nmax);
+        self.pq.resize(nmax + 1, 0);
+        self.qp.resize(nmax + 1, usize::MAX);
+        self.keys.resize_with(nmax + 1, || None);
+        self.nmax = nmax;
+    }
+
+    fn validate_index(&self, i: usize) -> Result<(), IndexPQError> {
+        if i >= self.nmax {
+            Err(IndexPQError::IndexOutOfRange(i))
+        } else if self.qp[i] == usize::MAX {
+            Err(IndexPQError::NoSuchIndex(i))
+        } else {
+            Ok(())
         }
     }
 
@@ -29,8 +137,7 @@ impl<T: PartialOrd> IndexMinPQ<T> {
     }
 
     pub fn contains(&self, i: usize) -> bool {
-        assert!(i < self.nmax, "index out of bounds");
-        self.qp[i] != usize::MAX
+        i < self.nmax && self.qp[i] != usize::MAX
     }
 
     pub fn size(&self) -> usize {
@@ -39,24 +146,40 @@ impl<T: PartialOrd> IndexMinPQ<T> {
 
     // Associates key with index i
     pub fn insert(&mut self, i: usize, key: T) {
-        assert!(i < self.nmax, "index out of bounds");
+        unwrap_or_panic(self.try_insert(i, key))
+    }
+
+    /// Associates key with index i, failing if i is already in the queue
+    pub fn try_insert(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
         if self.contains(i) {
-            panic!("index already in pq");
+            return Err(IndexPQError::DuplicateIndex(i));
+        }
+        if i >= self.nmax {
+            self.grow(i);
         }
         self.n += 1;
         self.qp[i] = self.n;
         self.pq[self.n] = i;
         self.keys[i] = Some(key);
         let n = self.n;
-        self.swim(n)
+        self.swim(n);
+        Ok(())
     }
 
-    pub fn min_index(&self) -> usize {
-        assert!(self.n != 0, "priority queue underflow");
-        self.pq[1]
+    /// index associated with the top key
+    pub fn top_index(&self) -> usize {
+        unwrap_or_panic(self.try_top_index())
     }
 
-    pub fn min_key(&self) -> Option<&T> {
+    pub fn try_top_index(&self) -> Result<usize, IndexPQError> {
+        if self.n == 0 {
+            Err(IndexPQError::Underflow)
+        } else {
+            Ok(self.pq[1])
+        }
+    }
+
+    pub fn top_key(&self) -> Option<&T> {
         if self.n == 0 {
             None
         } else {
@@ -64,25 +187,26 @@ impl<T: PartialOrd> IndexMinPQ<T> {
         }
     }
 
-    pub fn del_min(&mut self) -> Option<usize> {
+    /// remove the top key and return its index
+    pub fn pop(&mut self) -> Option<usize> {
         if self.n == 0 {
             None
         } else {
-            let min = self.pq[1];
+            let top = self.pq[1];
             let n = self.n;
             self.exch(1, n);
             self.n -= 1;
             self.sink(1);
-            self.qp[min] = usize::MAX; // delete
+            self.qp[top] = usize::MAX; // delete
                                        // help with gc
             self.keys[self.pq[self.n + 1]] = None;
             self.pq[self.n + 1] = usize::MAX;
-            Some(min)
+            Some(top)
         }
     }
 
     pub fn key_of(&self, i: usize) -> Option<&T> {
-        if i >= self.nmax || !self.contains(i) {
+        if !self.contains(i) {
             None
         } else {
             self.keys[i].as_ref()
@@ -90,51 +214,69 @@ impl<T: PartialOrd> IndexMinPQ<T> {
     }
 
     pub fn change_key(&mut self, i: usize, key: T) {
-        if i >= self.nmax || !self.contains(i) {
-            panic!("blah....");
-        }
+        unwrap_or_panic(self.try_change_key(i, key))
+    }
+
+    pub fn try_change_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.validate_index(i)?;
         self.keys[i] = Some(key);
         let p = self.qp[i];
         self.swim(p);
         let p = self.qp[i];
         self.sink(p);
+        Ok(())
     }
 
-    pub fn decrease_key(&mut self, i: usize, key: T) {
-        if i >= self.nmax || !self.contains(i) {
-            panic!("decrease_key");
-        }
+    /// change the key of index i to one nearer the top
+    fn raise_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.validate_index(i)?;
         self.keys[i] = Some(key);
         let p = self.qp[i];
         self.swim(p);
+        Ok(())
     }
 
-    pub fn increase_key(&mut self, i: usize, key: T) {
-        if i >= self.nmax || !self.contains(i) {
-            panic!("increase_key");
-        }
+    /// change the key of index i to one farther from the top
+    fn lower_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.validate_index(i)?;
         self.keys[i] = Some(key);
         let p = self.qp[i];
         self.sink(p);
+        Ok(())
     }
 
     pub fn delete(&mut self, i: usize) {
-        if i >= self.nmax || !self.contains(i) {
-            panic!("delete");
-        }
+        unwrap_or_panic(self.try_delete(i).map(|_| ()))
+    }
+
+    /// remove index i from the queue, returning its key
+    pub fn try_delete(&mut self, i: usize) -> Result<T, IndexPQError> {
+        self.validate_index(i)?;
         let index = self.qp[i];
         let n = self.n;
         self.exch(index, n);
         self.n -= 1;
-        self.swim(index);
-        self.sink(index);
-        self.keys[i] = None;
+        if index <= self.n {
+            self.swim(index);
+            self.sink(index);
+        }
         self.qp[i] = usize::MAX;
+        Ok(self.keys[i].take().unwrap())
+    }
+
+    /// iterate over `(index, key)` in priority order, without modifying the queue
+    pub fn iter(&self) -> Iter<'_, T, C> {
+        Iter {
+            queue: self,
+            frontier: if self.n == 0 { vec![] } else { vec![1] },
+        }
     }
 
     #[inline]
     fn greater(&self, i: usize, j: usize) -> bool {
-        self.keys[self.pq[i]] > self.keys[self.pq[j]]
+        let a = self.keys[self.pq[i]].as_ref().unwrap();
+        let b = self.keys[self.pq[j]].as_ref().unwrap();
+        self.cmp.compare(a, b) == Ordering::Greater
     }
 
     fn exch(&mut self, i: usize, j: usize) {
@@ -142,20 +284,39 @@ impl<T: PartialOrd> IndexMinPQ<T> {
         self.qp.swap(self.pq[i], self.pq[j]);
     }
 
+    #[inline]
+    fn parent(&self, k: usize) -> usize {
+        (k - 2) / self.d + 1
+    }
+
+    /// heap positions of the children of `k`
+    #[inline]
+    fn children(&self, k: usize) -> ::std::ops::Range<usize> {
+        let first = self.d * (k - 1) + 2;
+        first.min(self.n + 1)..(first + self.d).min(self.n + 1)
+    }
+
     fn swim(&mut self, k: usize) {
         let mut k = k;
-        while k > 1 && self.greater(k / 2, k) {
-            self.exch(k, k / 2);
-            k /= 2;
+        while k > 1 && self.greater(self.parent(k), k) {
+            let parent = self.parent(k);
+            self.exch(k, parent);
+            k = parent;
         }
     }
 
     fn sink(&mut self, k: usize) {
         let mut k = k;
-        while 2 * k <= self.n {
-            let mut j = 2 * k;
-            if j < self.n && self.greater(j, j + 1) {
-                j += 1;
+        loop {
+            let mut children = self.children(k);
+            let mut j = match children.next() {
+                Some(j) => j,
+                None => break,
+            };
+            for c in children {
+                if self.greater(j, c) {
+                    j = c;
+                }
             }
             if !self.greater(k, j) {
                 break;
@@ -166,6 +327,129 @@ impl<T: PartialOrd> IndexMinPQ<T> {
     }
 }
 
+impl<T: PartialOrd> IndexPQ<T, MinOrder> {
+    pub fn min_index(&self) -> usize {
+        self.top_index()
+    }
+
+    pub fn min_key(&self) -> Option<&T> {
+        self.top_key()
+    }
+
+    pub fn del_min(&mut self) -> Option<usize> {
+        self.pop()
+    }
+
+    pub fn decrease_key(&mut self, i: usize, key: T) {
+        unwrap_or_panic(self.try_decrease_key(i, key))
+    }
+
+    pub fn try_decrease_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.raise_key(i, key)
+    }
+
+    pub fn increase_key(&mut self, i: usize, key: T) {
+        unwrap_or_panic(self.try_increase_key(i, key))
+    }
+
+    pub fn try_increase_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.lower_key(i, key)
+    }
+}
+
+impl<T: PartialOrd> IndexPQ<T, MaxOrder> {
+    pub fn max_index(&self) -> usize {
+        self.top_index()
+    }
+
+    pub fn max_key(&self) -> Option<&T> {
+        self.top_key()
+    }
+
+    pub fn del_max(&mut self) -> Option<usize> {
+        self.pop()
+    }
+
+    pub fn increase_key(&mut self, i: usize, key: T) {
+        unwrap_or_panic(self.try_increase_key(i, key))
+    }
+
+    pub fn try_increase_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.raise_key(i, key)
+    }
+
+    pub fn decrease_key(&mut self, i: usize, key: T) {
+        unwrap_or_panic(self.try_decrease_key(i, key))
+    }
+
+    pub fn try_decrease_key(&mut self, i: usize, key: T) -> Result<(), IndexPQError> {
+        self.lower_key(i, key)
+    }
+}
+
+/// Iterator over `(index, key)` of an `IndexPQ` in priority order.
+///
+/// Keeps a small heap of the candidate heap positions, so taking `k` items costs `O(k log k)`.
+pub struct Iter<'a, T, C> {
+    queue: &'a IndexPQ<T, C>,
+    // binary heap of positions in `queue.pq`, 0-based
+    frontier: Vec<usize>,
+}
+
+impl<'a, T, C: Comparator<T>> Iter<'a, T, C> {
+    fn push(&mut self, p: usize) {
+        self.frontier.push(p);
+        let mut k = self.frontier.len() - 1;
+        while k > 0 && self.queue.greater(self.frontier[(k - 1) / 2], self.frontier[k]) {
+            self.frontier.swap(k, (k - 1) / 2);
+            k = (k - 1) / 2;
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        if self.frontier.is_empty() {
+            return None;
+        }
+        let top = self.frontier.swap_remove(0);
+        let n = self.frontier.len();
+        let mut k = 0;
+        while 2 * k + 1 < n {
+            let mut j = 2 * k + 1;
+            if j + 1 < n && self.queue.greater(self.frontier[j], self.frontier[j + 1]) {
+                j += 1;
+            }
+            if !self.queue.greater(self.frontier[k], self.frontier[j]) {
+                break;
+            }
+            self.frontier.swap(k, j);
+            k = j;
+        }
+        Some(top)
+    }
+}
+
+impl<'a, T, C: Comparator<T>> Iterator for Iter<'a, T, C> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<(usize, &'a T)> {
+        let p = self.pop()?;
+        for child in self.queue.children(p) {
+            self.push(child);
+        }
+        let i = self.queue.pq[p];
+        self.queue.keys[i].as_ref().map(|key| (i, key))
+    }
+}
+
+impl<'a, T, C: Comparator<T>> IntoIterator for &'a IndexPQ<T, C> {
+    type Item = (usize, &'a T);
+    type IntoIter = Iter<'a, T, C>;
+
+    fn into_iter(self) -> Iter<'a, T, C> {
+        self.iter()
+    }
+}
+
 #[test]
 fn test_index_min_pq() {
     let strings = vec!["it", "was", "the", "best", "of", "times", "it", "was", "the", "worst"];
@@ -188,3 +472,94 @@ fn test_index_min_pq() {
         pq.del_min();
     }
 }
+
+#[test]
+fn test_index_max_pq() {
+    let strings = vec!["it", "was", "the", "best", "of", "times", "it", "was", "the", "worst"];
+    let mut pq = IndexMaxPQ::with_capacity(strings.len());
+
+    for (i, s) in strings.iter().enumerate() {
+        pq.insert(i, *s);
+    }
+    assert_eq!(pq.max_key(), Some(&"worst"));
+
+    pq.decrease_key(9, "a");
+    pq.increase_key(3, "zoo");
+    assert_eq!(pq.del_max(), Some(3));
+    assert_eq!(pq.del_max(), Some(1));
+    assert_eq!(pq.del_max(), Some(7));
+    assert_eq!(pq.max_index(), 5);
+    assert_eq!(pq.size(), 7);
+}
+
+#[test]
+fn test_index_pq_with_comparator() {
+    // by distance, then by the larger hop count
+    let mut pq =
+        IndexPQ::with_capacity_and_comparator(4, |a: &(u32, u32), b: &(u32, u32)| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    pq.insert(0, (5, 1));
+    pq.insert(1, (3, 1));
+    pq.insert(2, (3, 4));
+    pq.insert(3, (9, 0));
+    pq.change_key(3, (1, 0));
+
+    let mut order = vec![];
+    while let Some(i) = pq.pop() {
+        order.push(i);
+    }
+    assert_eq!(order, vec![3, 2, 1, 0]);
+}
+
+#[test]
+fn test_index_min_pq_grow_and_iter() {
+    let mut pq = IndexMinPQ::new();
+    pq.insert(10, 5);
+    pq.insert(3, 1);
+    pq.insert(42, 3);
+    pq.insert(0, 4);
+    assert!(pq.capacity() > 42);
+    assert!(pq.contains(42));
+    assert!(!pq.contains(1000));
+
+    let items = pq.iter().map(|(i, k)| (i, *k)).collect::<Vec<_>>();
+    assert_eq!(items, vec![(3, 1), (42, 3), (0, 4), (10, 5)]);
+    assert_eq!(pq.size(), 4);
+    assert_eq!(pq.min_index(), 3);
+}
+
+#[test]
+fn test_index_min_pq_errors() {
+    let mut pq = IndexMinPQ::with_capacity(4);
+    assert_eq!(pq.try_top_index(), Err(IndexPQError::Underflow));
+    assert_eq!(pq.try_insert(1, 10), Ok(()));
+    assert_eq!(pq.try_insert(1, 20), Err(IndexPQError::DuplicateIndex(1)));
+    assert_eq!(pq.try_change_key(2, 5), Err(IndexPQError::NoSuchIndex(2)));
+    assert_eq!(pq.try_decrease_key(9, 5), Err(IndexPQError::IndexOutOfRange(9)));
+    assert_eq!(pq.try_delete(1), Ok(10));
+    assert_eq!(pq.try_delete(1), Err(IndexPQError::NoSuchIndex(1)));
+    assert!(pq.is_empty());
+}
+
+#[test]
+fn test_index_pq_arity() {
+    for &d in [2, 3, 4, 8].iter() {
+        let mut pq = IndexMinPQ::with_arity(d);
+        for i in 0..100 {
+            pq.insert(i, (i * 37) % 101);
+        }
+        for i in (0..100).step_by(7) {
+            pq.decrease_key(i, 0);
+        }
+        let first = pq.iter().take(15).map(|(i, _)| i).collect::<Vec<_>>();
+        assert_eq!(first.len(), 15);
+        assert!(first.iter().all(|i| i % 7 == 0));
+
+        let mut last = 0;
+        while let Some(i) = pq.del_min() {
+            let key = if i % 7 == 0 { 0 } else { (i * 37) % 101 };
+            assert!(key >= last);
+            last = key;
+        }
+        assert_eq!(pq.arity(), d);
+    }
+}
@@ -0,0 +1,234 @@
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+/// Double-ended priority queue implementation with a min-max heap
+///
+/// Nodes on even levels are smaller than all their descendants, nodes on odd
+/// levels are larger, so both the smallest and the largest key are at the top.
+pub struct MinMaxPQ<Key> {
+    pq: Vec<Key>,
+}
+
+#[inline]
+fn is_min_level(i: usize) -> bool {
+    // level of i is floor(log2(i + 1))
+    (i + 1).ilog2() & 1 == 0
+}
+
+impl<Key: PartialOrd> MinMaxPQ<Key> {
+    /// does the key at `i` belong above the key at `j`, on a min level or a max level?
+    #[inline]
+    fn before(&self, i: usize, j: usize, min_level: bool) -> bool {
+        if min_level {
+            self.pq[i] < self.pq[j]
+        } else {
+            self.pq[i] > self.pq[j]
+        }
+    }
+
+    fn swim(&mut self, k: usize) {
+        if k == 0 {
+            return;
+        }
+        let parent = (k - 1) / 2;
+        let min_level = is_min_level(k);
+        if self.before(parent, k, min_level) {
+            self.pq.swap(k, parent);
+            self.swim_levels(parent, !min_level);
+        } else {
+            self.swim_levels(k, min_level);
+        }
+    }
+
+    /// swim along the grandparent chain, staying on min levels or on max levels
+    fn swim_levels(&mut self, k: usize, min_level: bool) {
+        let mut k = k;
+        while k > 2 {
+            let grandparent = ((k - 1) / 2 - 1) / 2;
+            if !self.before(k, grandparent, min_level) {
+                break;
+            }
+            self.pq.swap(k, grandparent);
+            k = grandparent;
+        }
+    }
+
+    fn sink(&mut self, k: usize) {
+        let min_level = is_min_level(k);
+        let n = self.pq.len();
+        let mut k = k;
+        loop {
+            // the first among children and grandchildren
+            let first_child = 2 * k + 1;
+            if first_child >= n {
+                break;
+            }
+            let mut m = first_child;
+            let candidates = [first_child + 1, 4 * k + 3, 4 * k + 4, 4 * k + 5, 4 * k + 6];
+            for &c in candidates.iter().filter(|&&c| c < n) {
+                if self.before(c, m, min_level) {
+                    m = c;
+                }
+            }
+            if !self.before(m, k, min_level) {
+                break;
+            }
+            self.pq.swap(m, k);
+            if m <= first_child + 1 {
+                break;
+            }
+            let parent = (m - 1) / 2;
+            if self.before(parent, m, min_level) {
+                self.pq.swap(m, parent);
+            }
+            k = m;
+        }
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self.pq.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => Some(if self.pq[2] > self.pq[1] { 2 } else { 1 }),
+        }
+    }
+
+    fn remove_at(&mut self, k: usize) -> Key {
+        let key = self.pq.swap_remove(k);
+        if k < self.pq.len() {
+            self.sink(k);
+        }
+        key
+    }
+}
+
+impl<Key: PartialOrd> Default for MinMaxPQ<Key> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key: PartialOrd> MinMaxPQ<Key> {
+    /// create an empty priority queue
+    pub fn new() -> Self {
+        MinMaxPQ { pq: Vec::new() }
+    }
+
+    /// create a priority queue with given keys, in linear time
+    pub fn from_vec(a: Vec<Key>) -> Self {
+        let mut pq = MinMaxPQ { pq: a };
+        for k in (0..pq.pq.len() / 2).rev() {
+            pq.sink(k);
+        }
+        pq
+    }
+
+    /// insert a key into the priority queue
+    pub fn insert(&mut self, x: Key) {
+        self.pq.push(x);
+        let n = self.pq.len();
+        self.swim(n - 1);
+    }
+
+    /// return and remove the smallest key
+    pub fn del_min(&mut self) -> Option<Key> {
+        if self.pq.is_empty() {
+            None
+        } else {
+            Some(self.remove_at(0))
+        }
+    }
+
+    /// return and remove the largest key
+    pub fn del_max(&mut self) -> Option<Key> {
+        self.max_index().map(|k| self.remove_at(k))
+    }
+
+    /// return the smallest key
+    pub fn min(&self) -> Option<&Key> {
+        self.pq.first()
+    }
+
+    /// return the largest key
+    pub fn max(&self) -> Option<&Key> {
+        self.max_index().map(|k| &self.pq[k])
+    }
+
+    /// is the priority queue empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pq.is_empty()
+    }
+
+    /// number of entries in the priority queue
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.pq.len()
+    }
+}
+
+#[test]
+fn test_min_max_priority_queue() {
+    let mut pq: MinMaxPQ<char> = MinMaxPQ::new();
+
+    pq.insert('P');
+    pq.insert('Q');
+    pq.insert('E');
+
+    assert_eq!(pq.size(), 3);
+    assert_eq!(pq.min(), Some(&'E'));
+    assert_eq!(pq.max(), Some(&'Q'));
+    assert_eq!(pq.del_max().unwrap(), 'Q');
+    assert_eq!(pq.del_min().unwrap(), 'E');
+
+    pq.insert('X');
+    pq.insert('A');
+    pq.insert('M');
+    pq.insert('B');
+
+    assert_eq!(pq.del_min().unwrap(), 'A');
+    assert_eq!(pq.del_max().unwrap(), 'X');
+    assert_eq!(pq.del_min().unwrap(), 'B');
+    assert_eq!(pq.size(), 2);
+
+    let mut pq = MinMaxPQ::from_vec("HEAPSORTEXAMPLE".chars().collect());
+    assert_eq!(pq.del_max().unwrap(), 'X');
+    assert_eq!(pq.del_min().unwrap(), 'A');
+    assert_eq!(pq.size(), 13);
+}
+
+#[test]
+fn quicktest_min_max_priority_queue() {
+    fn prop(xs: Vec<isize>, heapify: bool) -> bool {
+        let mut pq = if heapify {
+            MinMaxPQ::from_vec(xs.clone())
+        } else {
+            let mut pq = MinMaxPQ::new();
+            for &x in xs.iter() {
+                pq.insert(x);
+            }
+            pq
+        };
+        let mut sorted = xs;
+        sorted.sort();
+        let (mut lo, mut hi) = (0, sorted.len());
+        // alternate ends
+        while lo < hi {
+            if (lo + hi) % 2 == 0 {
+                if pq.min() != Some(&sorted[lo]) || pq.del_min() != Some(sorted[lo]) {
+                    return false;
+                }
+                lo += 1;
+            } else {
+                if pq.max() != Some(&sorted[hi - 1]) || pq.del_max() != Some(sorted[hi - 1]) {
+                    return false;
+                }
+                hi -= 1;
+            }
+        }
+        pq.is_empty()
+    }
+
+    quickcheck(prop as fn(Vec<isize>, bool) -> bool);
+}
@@ -1,5 +1,26 @@
+#[macro_use]
+mod arena;
 pub mod binary_heaps;
+pub mod binomial_heap;
+pub mod comparator;
+pub mod dary_heap;
+pub mod fibonacci_heap;
 pub mod index_pq;
+pub mod min_max_heap;
+pub mod multiway;
+pub mod pairing_heap;
+pub mod radix_heap;
+pub mod top_k;
 
-pub use self::binary_heaps::{MaxPQ, MinPQ};
-pub use self::index_pq::IndexMinPQ;
+pub use self::arena::{Handle, HeapError};
+pub use self::binary_heaps::{BinaryHeapPQ, MaxPQ, MinPQ};
+pub use self::binomial_heap::BinomialHeap;
+pub use self::comparator::{ByKey, Comparator, MaxOrder, MinOrder, Reversed};
+pub use self::dary_heap::DaryHeap;
+pub use self::fibonacci_heap::FibonacciHeap;
+pub use self::index_pq::{IndexMaxPQ, IndexMinPQ, IndexPQ, IndexPQError};
+pub use self::min_max_heap::MinMaxPQ;
+pub use self::multiway::{kway_merge, kway_merge_by, KWayMerge};
+pub use self::pairing_heap::PairingHeap;
+pub use self::radix_heap::{IndexRadixHeap, RadixHeap};
+pub use self::top_k::TopK;
@@ -0,0 +1,88 @@
+use super::comparator::{Comparator, MinOrder};
+use super::index_pq::IndexPQ;
+use std::cmp::Ordering;
+
+/// Orders `(item, source)` pairs by item, then by source index.
+pub struct SourceOrder<C>(C);
+
+impl<T, C: Comparator<T>> Comparator<(T, usize)> for SourceOrder<C> {
+    #[inline]
+    fn compare(&self, a: &(T, usize), b: &(T, usize)) -> Ordering {
+        self.0.compare(&a.0, &b.0).then(a.1.cmp(&b.1))
+    }
+}
+
+/// Lazy k-way merge of sorted iterators, created by `kway_merge` and `kway_merge_by`.
+///
+/// Holds one pending item per source in an `IndexPQ` keyed by source index, so each
+/// item costs O(log k). Equal items are yielded in source order.
+pub struct KWayMerge<I: Iterator, C> {
+    sources: Vec<I>,
+    pq: IndexPQ<(I::Item, usize), SourceOrder<C>>,
+}
+
+/// Merge iterators that are each sorted in ascending order into one sorted iterator.
+pub fn kway_merge<S, I>(sources: S) -> KWayMerge<I::IntoIter, MinOrder>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: PartialOrd,
+{
+    kway_merge_by(sources, MinOrder)
+}
+
+/// Merge iterators that are each sorted by `cmp` into one iterator sorted by `cmp`.
+pub fn kway_merge_by<S, I, C>(sources: S, cmp: C) -> KWayMerge<I::IntoIter, C>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    C: Comparator<I::Item>,
+{
+    let mut sources = sources.into_iter().map(|s| s.into_iter()).collect::<Vec<_>>();
+    let mut pq = IndexPQ::with_capacity_and_comparator(sources.len(), SourceOrder(cmp));
+    for (i, source) in sources.iter_mut().enumerate() {
+        if let Some(item) = source.next() {
+            pq.insert(i, (item, i));
+        }
+    }
+    KWayMerge { sources, pq }
+}
+
+impl<I: Iterator, C: Comparator<I::Item>> Iterator for KWayMerge<I, C> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let i = self.pq.try_top_index().ok()?;
+        let (item, _) = self.pq.try_delete(i).unwrap();
+        if let Some(next) = self.sources[i].next() {
+            self.pq.insert(i, (next, i));
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.pq.size();
+        self.sources.iter().fold((pending, Some(pending)), |(lo, hi), s| {
+            let (slo, shi) = s.size_hint();
+            (lo.saturating_add(slo), hi.and_then(|h| shi.and_then(|sh| h.checked_add(sh))))
+        })
+    }
+}
+
+#[test]
+fn test_kway_merge() {
+    let a = vec!["A", "B", "C", "F", "G", "I", "I", "Z"];
+    let b = vec!["B", "D", "H", "P", "Q", "Q"];
+    let c = vec!["A", "B", "E", "F", "J", "N"];
+    let merged = kway_merge(vec![a, b, c]).collect::<Vec<_>>();
+    assert_eq!(merged.concat(), "AABBBCDEFFGHIIJNPQQZ");
+
+    // stable: equal items come out in source order
+    let xs = vec![(1, 'a'), (3, 'a')];
+    let ys = vec![(1, 'b'), (2, 'b'), (3, 'b')];
+    let merged = kway_merge_by(vec![xs, ys], |x: &(i32, char), y: &(i32, char)| x.0.cmp(&y.0)).collect::<Vec<_>>();
+    assert_eq!(merged, vec![(1, 'a'), (1, 'b'), (2, 'b'), (3, 'a'), (3, 'b')]);
+
+    let empty: Vec<Vec<i32>> = vec![vec![], vec![]];
+    assert_eq!(kway_merge(empty).size_hint(), (0, Some(0)));
+}
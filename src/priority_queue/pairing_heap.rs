@@ -0,0 +1,245 @@
+use super::arena::{unwrap_or_panic, Arena, Handle, HeapError, Ix, NIL};
+use super::comparator::{Comparator, MinOrder};
+use std::cmp::Ordering;
+
+struct Node<K> {
+    key: K,
+    child: Ix,
+    sibling: Ix,
+    // parent for the first child, previous sibling otherwise
+    prev: Ix,
+}
+
+/// Pairing heap, a self-adjusting heap-ordered multiway tree.
+///
+/// `insert` and `peek` are O(1), `pop` and `delete` are O(log n) amortized,
+/// `decrease_key` is o(log n) amortized. `meld` links the two roots and hands the
+/// arena segments of one heap to the other without moving nodes, O(log n) amortized.
+pub struct PairingHeap<K, C = MinOrder> {
+    nodes: Arena<Node<K>>,
+    root: Ix,
+    n: usize,
+    cmp: C,
+}
+
+impl<K, C: Comparator<K> + Default> Default for PairingHeap<K, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, C: Comparator<K> + Default> PairingHeap<K, C> {
+    pub fn new() -> Self {
+        PairingHeap::with_comparator(C::default())
+    }
+}
+
+impl<K, C: Comparator<K>> PairingHeap<K, C> {
+    pub fn with_comparator(cmp: C) -> Self {
+        PairingHeap {
+            nodes: Arena::new(),
+            root: NIL,
+            n: 0,
+            cmp,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// insert a key, returning a handle for `decrease_key` and `delete`
+    pub fn insert(&mut self, key: K) -> Handle {
+        let x = self.nodes.alloc(Node {
+            key,
+            child: NIL,
+            sibling: NIL,
+            prev: NIL,
+        });
+        self.root = self.link(self.root, x);
+        self.n += 1;
+        self.nodes.handle(x)
+    }
+
+    /// return the top key
+    pub fn peek(&self) -> Option<&K> {
+        match self.root {
+            NIL => None,
+            x => Some(&self.nodes[x].key),
+        }
+    }
+
+    /// return and remove the top key
+    pub fn pop(&mut self) -> Option<K> {
+        if self.root == NIL {
+            return None;
+        }
+        let x = self.nodes.remove(self.root);
+        self.root = self.combine_siblings(x.child);
+        self.n -= 1;
+        Some(x.key)
+    }
+
+    /// move all keys of `other` into this heap, keeping this heap's comparator.
+    /// Handles into `other` stay valid for this heap.
+    pub fn meld(&mut self, other: PairingHeap<K, C>) {
+        self.nodes.absorb(other.nodes);
+        self.root = self.link(self.root, other.root);
+        self.n += other.n;
+    }
+
+    /// does `h` refer to an element of this heap
+    pub fn contains(&self, h: Handle) -> bool {
+        self.nodes.resolve(h).is_ok()
+    }
+
+    /// the key of an element, `None` if it is not in the heap
+    pub fn key_of(&self, h: Handle) -> Option<&K> {
+        self.nodes.resolve(h).ok().map(|x| &self.nodes[x].key)
+    }
+
+    pub fn decrease_key(&mut self, h: Handle, key: K) {
+        unwrap_or_panic(self.try_decrease_key(h, key))
+    }
+
+    /// replace the key of an element with one not farther from the top
+    pub fn try_decrease_key(&mut self, h: Handle, key: K) -> Result<(), HeapError> {
+        let x = self.nodes.resolve(h)?;
+        if self.cmp.compare(&key, &self.nodes[x].key) == Ordering::Greater {
+            return Err(HeapError::KeyIncreased);
+        }
+        self.nodes[x].key = key;
+        if x != self.root {
+            self.detach(x);
+            self.root = self.link(self.root, x);
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, h: Handle) -> K {
+        unwrap_or_panic(self.try_delete(h))
+    }
+
+    /// remove an element, returning its key
+    pub fn try_delete(&mut self, h: Handle) -> Result<K, HeapError> {
+        let x = self.nodes.resolve(h)?;
+        if x != self.root {
+            self.detach(x);
+            let rest = self.combine_siblings(self.nodes[x].child);
+            self.root = self.link(self.root, rest);
+        } else {
+            self.root = self.combine_siblings(self.nodes[x].child);
+        }
+        self.n -= 1;
+        Ok(self.nodes.remove(x).key)
+    }
+
+    /// unlink the subtree rooted at `x` from its parent or siblings
+    fn detach(&mut self, x: Ix) {
+        let prev = self.nodes[x].prev;
+        let sibling = self.nodes[x].sibling;
+        if self.nodes[prev].child == x {
+            self.nodes[prev].child = sibling;
+        } else {
+            self.nodes[prev].sibling = sibling;
+        }
+        if sibling != NIL {
+            self.nodes[sibling].prev = prev;
+        }
+        self.nodes[x].sibling = NIL;
+        self.nodes[x].prev = NIL;
+    }
+
+    /// link two detached trees, the larger root becomes the first child of the smaller
+    fn link(&mut self, a: Ix, b: Ix) -> Ix {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        let (parent, child) = if self.cmp.compare(&self.nodes[b].key, &self.nodes[a].key) == Ordering::Less {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        let first = self.nodes[parent].child;
+        self.nodes[child].sibling = first;
+        if first != NIL {
+            self.nodes[first].prev = child;
+        }
+        self.nodes[child].prev = parent;
+        self.nodes[parent].child = child;
+        parent
+    }
+
+    /// two-pass pairing of a sibling list into a single tree
+    fn combine_siblings(&mut self, first: Ix) -> Ix {
+        let mut trees = Vec::new();
+        let mut x = first;
+        while x != NIL {
+            let next = self.nodes[x].sibling;
+            self.nodes[x].sibling = NIL;
+            self.nodes[x].prev = NIL;
+            trees.push(x);
+            x = next;
+        }
+        let mut paired = Vec::with_capacity(trees.len() / 2 + 1);
+        for pair in trees.chunks(2) {
+            if pair.len() == 2 {
+                paired.push(self.link(pair[0], pair[1]));
+            } else {
+                paired.push(pair[0]);
+            }
+        }
+        let mut root = NIL;
+        while let Some(t) = paired.pop() {
+            root = self.link(t, root);
+        }
+        root
+    }
+}
+
+#[test]
+fn test_pairing_heap() {
+    let mut pq = PairingHeap::<i32>::new();
+    let mut handles = vec![];
+    for &k in &[5, 3, 9, 1, 7, 8, 2] {
+        handles.push(pq.insert(k));
+    }
+    assert_eq!(pq.peek(), Some(&1));
+
+    pq.decrease_key(handles[2], 0);
+    assert_eq!(pq.delete(handles[4]), 7);
+    assert_eq!(pq.key_of(handles[0]), Some(&5));
+    assert!(!pq.contains(handles[4]));
+    assert_eq!(pq.try_delete(handles[4]), Err(HeapError::InvalidHandle));
+    // the freed slot is reused, the stale handle must not see the new element
+    let h = pq.insert(6);
+    assert_eq!(pq.key_of(handles[4]), None);
+    assert_eq!(pq.delete(h), 6);
+
+    let mut other = PairingHeap::new();
+    other.insert(4);
+    other.insert(-1);
+    pq.meld(other);
+    assert_eq!(pq.size(), 8);
+
+    let mut keys = vec![];
+    while let Some(k) = pq.pop() {
+        keys.push(k);
+    }
+    assert_eq!(keys, vec![-1, 0, 1, 2, 3, 4, 5, 8]);
+    assert!(pq.is_empty());
+}
+
+#[test]
+fn quicktest_pairing_heap() {
+    meldable_heap_prop!(PairingHeap);
+}
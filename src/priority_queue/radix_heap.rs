@@ -0,0 +1,358 @@
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+// bucket 0 holds keys equal to the last removed key, bucket b > 0 holds keys
+// whose highest bit differing from it is bit b - 1
+const BUCKETS: usize = 65;
+
+#[inline]
+fn bucket_of(key: u64, last: u64) -> usize {
+    if key == last {
+        0
+    } else {
+        64 - (key ^ last).leading_zeros() as usize
+    }
+}
+
+/// Monotone priority queue for integer keys, such as Dijkstra distances.
+///
+/// Keys inserted must not be smaller than the last key removed. Each key moves
+/// to a lower bucket at most 64 times, so `del_min` is O(log C) amortized where
+/// C is the largest key, and `insert` is O(1).
+pub struct RadixHeap<V> {
+    last: u64,
+    buckets: Vec<Vec<(u64, V)>>,
+    n: usize,
+}
+
+impl<V> Default for RadixHeap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> RadixHeap<V> {
+    pub fn new() -> RadixHeap<V> {
+        RadixHeap {
+            last: 0,
+            buckets: (0..BUCKETS).map(|_| Vec::new()).collect(),
+            n: 0,
+        }
+    }
+
+    /// is the priority queue empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// number of entries in the priority queue
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// the last removed key, no smaller key can be inserted
+    pub fn last_key(&self) -> u64 {
+        self.last
+    }
+
+    /// insert a key with its value into the priority queue
+    pub fn insert(&mut self, key: u64, value: V) {
+        assert!(key >= self.last, "key is smaller than the last removed key");
+        self.buckets[bucket_of(key, self.last)].push((key, value));
+        self.n += 1;
+    }
+
+    /// return the smallest key
+    pub fn min_key(&self) -> Option<u64> {
+        self.buckets
+            .iter()
+            .find(|b| !b.is_empty())
+            .map(|b| b.iter().map(|&(k, _)| k).min().unwrap())
+    }
+
+    /// return and remove an entry with the smallest key
+    pub fn del_min(&mut self) -> Option<(u64, V)> {
+        if self.n == 0 {
+            return None;
+        }
+        if self.buckets[0].is_empty() {
+            let b = (1..BUCKETS).find(|&b| !self.buckets[b].is_empty()).unwrap();
+            let entries = std::mem::take(&mut self.buckets[b]);
+            self.last = entries.iter().map(|&(k, _)| k).min().unwrap();
+            for (k, v) in entries {
+                self.buckets[bucket_of(k, self.last)].push((k, v));
+            }
+        }
+        self.n -= 1;
+        self.buckets[0].pop()
+    }
+}
+
+/// Indexed monotone priority queue for integer keys, mirroring `IndexMinPQ`.
+///
+/// Keys inserted or decreased must not be smaller than the last key removed.
+/// The smallest key outside bucket 0 is kept up to date, so `min_index` and
+/// `min_key` are O(1).
+pub struct IndexRadixHeap {
+    last: u64,
+    buckets: Vec<Vec<usize>>,
+    keys: Vec<Option<u64>>,
+    // (bucket, slot) of each index in the queue
+    pos: Vec<(usize, usize)>,
+    // index with the smallest key in buckets 1.., the first non-empty one
+    rest_min: Option<usize>,
+    n: usize,
+}
+
+impl Default for IndexRadixHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexRadixHeap {
+    /// create an empty queue, which grows as indices are inserted
+    pub fn new() -> IndexRadixHeap {
+        IndexRadixHeap::with_capacity(0)
+    }
+
+    pub fn with_capacity(nmax: usize) -> IndexRadixHeap {
+        IndexRadixHeap {
+            last: 0,
+            buckets: (0..BUCKETS).map(|_| Vec::new()).collect(),
+            keys: vec![None; nmax],
+            pos: vec![(0, 0); nmax],
+            rest_min: None,
+            n: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        i < self.keys.len() && self.keys[i].is_some()
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// the last removed key, no smaller key can be inserted
+    pub fn last_key(&self) -> u64 {
+        self.last
+    }
+
+    pub fn key_of(&self, i: usize) -> Option<u64> {
+        self.keys.get(i).cloned().unwrap_or(None)
+    }
+
+    fn place(&mut self, i: usize, key: u64) {
+        assert!(key >= self.last, "key is smaller than the last removed key");
+        let b = bucket_of(key, self.last);
+        self.keys[i] = Some(key);
+        self.pos[i] = (b, self.buckets[b].len());
+        self.buckets[b].push(i);
+        if b > 0 && self.rest_min.is_none_or(|m| key < self.keys[m].unwrap()) {
+            self.rest_min = Some(i);
+        }
+    }
+
+    fn unplace(&mut self, i: usize) {
+        let (b, slot) = self.pos[i];
+        self.buckets[b].swap_remove(slot);
+        if let Some(&moved) = self.buckets[b].get(slot) {
+            self.pos[moved] = (b, slot);
+        }
+    }
+
+    // the smallest key outside bucket 0 is in the first non-empty bucket
+    fn find_rest_min(&self) -> Option<usize> {
+        let b = (1..BUCKETS).find(|&b| !self.buckets[b].is_empty())?;
+        self.buckets[b].iter().copied().min_by_key(|&i| self.keys[i])
+    }
+
+    // Associates key with index i
+    pub fn insert(&mut self, i: usize, key: u64) {
+        if self.contains(i) {
+            panic!("index already in pq");
+        }
+        if i >= self.keys.len() {
+            let nmax = (i + 1).max(2 * self.keys.len());
+            self.keys.resize(nmax, None);
+            self.pos.resize(nmax, (0, 0));
+        }
+        self.place(i, key);
+        self.n += 1;
+    }
+
+    pub fn decrease_key(&mut self, i: usize, key: u64) {
+        if !self.contains(i) {
+            panic!("decrease_key");
+        }
+        assert!(self.keys[i].unwrap() >= key, "decrease_key() with a larger key");
+        self.unplace(i);
+        self.place(i, key);
+        // a smallest key stays smallest unless it moved to bucket 0
+        if self.rest_min == Some(i) && self.pos[i].0 == 0 {
+            self.rest_min = self.find_rest_min();
+        }
+    }
+
+    pub fn delete(&mut self, i: usize) {
+        if !self.contains(i) {
+            panic!("delete");
+        }
+        self.unplace(i);
+        self.keys[i] = None;
+        self.n -= 1;
+        if self.rest_min == Some(i) {
+            self.rest_min = self.find_rest_min();
+        }
+    }
+
+    /// move the smallest keys into bucket 0, once it is empty
+    fn settle(&mut self) {
+        let m = match self.rest_min {
+            Some(m) if self.buckets[0].is_empty() => m,
+            _ => return,
+        };
+        let b = self.pos[m].0;
+        let indices = std::mem::take(&mut self.buckets[b]);
+        self.last = self.keys[m].unwrap();
+        self.rest_min = None;
+        for i in indices {
+            let key = self.keys[i].unwrap();
+            self.place(i, key);
+        }
+        // all of bucket b may have moved to bucket 0, the others keep their buckets
+        if self.rest_min.is_none() {
+            self.rest_min = self.find_rest_min();
+        }
+    }
+
+    pub fn min_index(&self) -> usize {
+        assert!(self.n != 0, "priority queue underflow");
+        self.buckets[0].last().copied().or(self.rest_min).unwrap()
+    }
+
+    pub fn min_key(&self) -> Option<u64> {
+        self.buckets[0]
+            .last()
+            .copied()
+            .or(self.rest_min)
+            .and_then(|i| self.keys[i])
+    }
+
+    pub fn del_min(&mut self) -> Option<usize> {
+        self.settle();
+        let i = self.buckets[0].pop()?;
+        self.keys[i] = None;
+        self.n -= 1;
+        Some(i)
+    }
+}
+
+#[test]
+fn test_radix_heap() {
+    let mut pq = RadixHeap::new();
+    for &k in [7, 3, 3, 100, 42, 1 << 40].iter() {
+        pq.insert(k, k * 2);
+    }
+    assert_eq!(pq.min_key(), Some(3));
+    assert_eq!(pq.del_min(), Some((3, 6)));
+    assert_eq!(pq.del_min(), Some((3, 6)));
+    pq.insert(5, 10);
+    assert_eq!(pq.del_min(), Some((5, 10)));
+    assert_eq!(pq.del_min(), Some((7, 14)));
+    assert_eq!(pq.del_min(), Some((42, 84)));
+    assert_eq!(pq.del_min(), Some((100, 200)));
+    assert_eq!(pq.del_min(), Some((1 << 40, 1 << 41)));
+    assert!(pq.is_empty());
+    assert_eq!(pq.del_min(), None);
+}
+
+#[test]
+fn quicktest_index_radix_heap() {
+    fn prop(xs: Vec<u32>) -> bool {
+        let mut pq = IndexRadixHeap::new();
+        for (i, &x) in xs.iter().enumerate() {
+            pq.insert(i, x as u64 + 1000);
+        }
+        let mut keys = xs.iter().map(|&x| Some(x as u64 + 1000)).collect::<Vec<_>>();
+        for i in (0..xs.len()).step_by(3) {
+            pq.decrease_key(i, xs[i] as u64 / 2);
+            keys[i] = Some(xs[i] as u64 / 2);
+        }
+        for i in (1..xs.len()).step_by(4) {
+            pq.delete(i);
+            keys[i] = None;
+        }
+        let mut expected = keys.into_iter().flatten().collect::<Vec<_>>();
+        expected.sort();
+        let mut got = vec![];
+        while let Some(key) = pq.min_key() {
+            let i = pq.del_min().unwrap();
+            if pq.contains(i) {
+                return false;
+            }
+            got.push(key);
+        }
+        got == expected
+    }
+
+    quickcheck(prop as fn(Vec<u32>) -> bool);
+}
+
+#[test]
+fn quicktest_index_radix_heap_interleaved() {
+    fn prop(ops: Vec<(u8, u8, u16)>) -> bool {
+        let mut pq = IndexRadixHeap::new();
+        let mut keys: Vec<Option<u64>> = vec![None; 16];
+        let mut last = 0;
+        for &(op, i, delta) in &ops {
+            let i = i as usize % 16;
+            // keys may be below the current minimum, as long as they are not below the last removed one
+            let key = last + delta as u64 % 64;
+            match (op % 4, keys[i]) {
+                (0, None) => {
+                    pq.insert(i, key);
+                    keys[i] = Some(key);
+                }
+                (1, Some(old)) if key <= old => {
+                    pq.decrease_key(i, key);
+                    keys[i] = Some(key);
+                }
+                (2, Some(_)) => {
+                    pq.delete(i);
+                    keys[i] = None;
+                }
+                (3, _) => match pq.del_min() {
+                    Some(j) => {
+                        if keys[j] != keys.iter().flatten().min().cloned() {
+                            return false;
+                        }
+                        last = keys[j].unwrap();
+                        keys[j] = None;
+                    }
+                    None => {
+                        if keys.iter().any(Option::is_some) {
+                            return false;
+                        }
+                    }
+                },
+                _ => {}
+            }
+            let min = keys.iter().flatten().min().cloned();
+            if pq.min_key() != min || pq.last_key() != last || (min.is_some() && keys[pq.min_index()] != min) {
+                return false;
+            }
+        }
+        true
+    }
+
+    quickcheck(prop as fn(Vec<(u8, u8, u16)>) -> bool);
+}
@@ -0,0 +1,96 @@
+use super::binary_heaps::BinaryHeapPQ;
+use super::comparator::{Comparator, MaxOrder, Reversed};
+use std::cmp::Ordering;
+use std::slice;
+
+/// Bounded collector keeping the `k` largest items of a stream in O(k) memory.
+///
+/// With a comparator, keeps the `k` items ordered first by it. Among equal items
+/// at the boundary, the ones pushed earlier are kept.
+pub struct TopK<T, C = MaxOrder> {
+    k: usize,
+    // the worst kept item is on top
+    heap: BinaryHeapPQ<T, Reversed<C>>,
+}
+
+impl<T: PartialOrd> TopK<T> {
+    pub fn new(k: usize) -> TopK<T> {
+        TopK::with_comparator(k, MaxOrder)
+    }
+}
+
+impl<T, C: Comparator<T>> TopK<T, C> {
+    pub fn with_comparator(k: usize, cmp: C) -> TopK<T, C> {
+        TopK {
+            k,
+            heap: BinaryHeapPQ::with_comparator(Reversed(cmp)),
+        }
+    }
+
+    /// offer an item, returns whether it is kept for now
+    pub fn push(&mut self, x: T) -> bool {
+        if self.heap.size() < self.k {
+            self.heap.insert(x);
+            return true;
+        }
+        let better = match self.heap.peek() {
+            Some(worst) => self.heap.comparator().0.compare(&x, worst) == Ordering::Less,
+            None => false,
+        };
+        if better {
+            *self.heap.peek_mut().unwrap() = x;
+        }
+        better
+    }
+
+    /// the worst item kept, once `k` items have been collected an item must beat it to get in
+    pub fn threshold(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    /// iterate over the kept items in arbitrary order
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.heap.iter()
+    }
+
+    /// the kept items, best first
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // the heap keeps the worst item on top, which `into_sorted_vec` puts last
+        self.heap.into_sorted_vec()
+    }
+}
+
+impl<T, C: Comparator<T>> Extend<T> for TopK<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+#[test]
+fn test_top_k() {
+    let mut top = TopK::new(3);
+    top.extend((0..1000).map(|i| (i * 7919) % 1000));
+    assert_eq!(top.size(), 3);
+    assert_eq!(top.threshold(), Some(&997));
+    assert!(!top.push(5));
+    assert_eq!(top.into_sorted_vec(), vec![999, 998, 997]);
+
+    // the 2 shortest words, ties kept in arrival order
+    let mut top = TopK::with_comparator(2, |a: &&str, b: &&str| a.len().cmp(&b.len()));
+    top.extend(vec!["three", "to", "be", "or", "a"]);
+    assert_eq!(top.into_sorted_vec(), vec!["a", "to"]);
+}
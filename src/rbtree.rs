@@ -1,8 +1,14 @@
 use self::Color::*;
+use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::fmt;
-use std::iter;
+use std::iter::{self, FromIterator};
 use std::mem;
+use std::ops::{Bound, Index, RangeBounds};
+
+#[cfg(test)]
+use quickcheck::quickcheck;
 
 fn max<T: PartialOrd + Copy>(a: T, b: T) -> T {
     if a >= b {
@@ -28,12 +34,20 @@ pub enum Color {
 
 pub type NodeCell<K, V> = Option<Box<Node<K, V>>>;
 
+#[derive(Clone)]
 pub struct Node<K, V> {
     pub key: K,
     pub val: V,
     pub left: NodeCell<K, V>,
     pub right: NodeCell<K, V>,
     pub color: Color,
+    // number of nodes in the subtree
+    n: usize,
+}
+
+#[inline]
+fn size<K, V>(x: &NodeCell<K, V>) -> usize {
+    x.as_ref().map_or(0, |n| n.n)
 }
 
 impl<K, V> Node<K, V> {
@@ -45,58 +59,19 @@ impl<K, V> Node<K, V> {
             left: None,
             right: None,
             color,
+            n: 1,
         }
     }
 
-    #[inline]
-    fn is_red(&self) -> bool {
-        self.color == Red
-    }
-
     fn depth(&self) -> usize {
         let lsz = self.left.as_ref().map_or(0, |n| n.depth());
         let rsz = self.right.as_ref().map_or(0, |n| n.depth());
         max(lsz, rsz) + 1
     }
 
-    fn size(&self) -> usize {
-        1 + self.left.as_ref().map_or(0, |n| n.size()) + self.right.as_ref().map_or(0, |n| n.size())
-    }
-
-    /// Left rotation. Orient a (temporarily) right-leaning red link to lean left.
-    fn rotate_left(&mut self) {
-        assert!(is_red(&self.right));
-        let mut x = self.right.take();
-        self.right = x.as_mut().unwrap().left.take();
-        x.as_mut().unwrap().color = self.color;
-        self.color = Red;
-        let old_self = mem::replace(self, *x.unwrap());
-        self.left = Some(Box::new(old_self));
-    }
-
-    /// Right rotation. Orient a left-leaning red link to (temporarily) lean right
-    fn rotate_right(&mut self) {
-        assert!(is_red(&self.left));
-        let mut x = self.left.take();
-        self.left = x.as_mut().unwrap().right.take();
-        x.as_mut().unwrap().color = self.color;
-        self.color = Red;
-        let old_self = mem::replace(self, *x.unwrap());
-        self.right = Some(Box::new(old_self));
-    }
-
-    /// Color flip. Recolor to split a (temporary) 4-node.
-    fn flip_color(&mut self) {
-        assert!(!self.is_red());
-        assert!(is_red(&self.left));
-        assert!(is_red(&self.right));
-        self.color = Red;
-        if let Some(n) = self.left.as_mut() {
-            n.color = Black;
-        }
-        if let Some(n) = self.right.as_mut() {
-            n.color = Black;
-        }
+    #[inline]
+    fn update_size(&mut self) {
+        self.n = 1 + size(&self.left) + size(&self.right);
     }
 }
 
@@ -134,15 +109,162 @@ impl<K: fmt::Debug, V: fmt::Debug> Node<K, V> {
     }
 }
 
-fn is_red<K, V>(x: &NodeCell<K, V>) -> bool {
-    if x.as_ref().is_none() {
-        false
-    } else {
-        x.as_ref().unwrap().color == Red
+// The links, color and entry the rebalancing steps of a left-leaning red-black tree
+// rewire, and the bookkeeping to redo afterwards. Trees that augment their nodes,
+// like the interval tree, share the rotations and deletions through it.
+pub(crate) trait RedBlackNode: Sized {
+    type Key;
+
+    fn key(&self) -> &Self::Key;
+    fn color(&self) -> Color;
+    fn set_color(&mut self, color: Color);
+    fn left(&self) -> &Option<Box<Self>>;
+    fn right(&self) -> &Option<Box<Self>>;
+    fn left_mut(&mut self) -> &mut Option<Box<Self>>;
+    fn right_mut(&mut self) -> &mut Option<Box<Self>>;
+    // exchange key and value with another node, keeping the links
+    fn swap_entry(&mut self, other: &mut Self);
+
+    // recompute whatever the node keeps about its subtree
+    fn pull(&mut self) {}
+
+    #[inline]
+    fn is_red(&self) -> bool {
+        self.color() == Red
+    }
+
+    /// Left rotation. Orient a (temporarily) right-leaning red link to lean left.
+    fn rotate_left(&mut self) {
+        assert!(is_red(self.right()));
+        let mut x = self.right_mut().take().unwrap();
+        *self.right_mut() = x.left_mut().take();
+        x.set_color(self.color());
+        self.set_color(Red);
+        let mut old_self = mem::replace(self, *x);
+        old_self.pull();
+        *self.left_mut() = Some(Box::new(old_self));
+        self.pull();
+    }
+
+    /// Right rotation. Orient a left-leaning red link to (temporarily) lean right
+    fn rotate_right(&mut self) {
+        assert!(is_red(self.left()));
+        let mut x = self.left_mut().take().unwrap();
+        *self.left_mut() = x.right_mut().take();
+        x.set_color(self.color());
+        self.set_color(Red);
+        let mut old_self = mem::replace(self, *x);
+        old_self.pull();
+        *self.right_mut() = Some(Box::new(old_self));
+        self.pull();
+    }
+
+    /// Flip the colors of a node and its two children. Split a (temporary) 4-node
+    /// on the way up, or join one on the way down a deletion.
+    fn flip_colors(&mut self) {
+        fn flip<N: RedBlackNode>(x: &mut N) {
+            x.set_color(if x.is_red() { Black } else { Red });
+        }
+        flip(self);
+        if let Some(left) = self.left_mut() {
+            flip(left.as_mut());
+        }
+        if let Some(right) = self.right_mut() {
+            flip(right.as_mut());
+        }
+    }
+
+    /// Assuming this node is red and both its left child and left-left
+    /// grandchild are black, make the left child or one of its children red.
+    fn move_red_left(&mut self) {
+        self.flip_colors();
+        if is_red(self.right().as_ref().unwrap().left()) {
+            self.right_mut().as_mut().unwrap().rotate_right();
+            self.rotate_left();
+            self.flip_colors();
+        }
+    }
+
+    /// Assuming this node is red and both its right child and right-left
+    /// grandchild are black, make the right child or one of its children red.
+    fn move_red_right(&mut self) {
+        self.flip_colors();
+        if is_red(self.left().as_ref().unwrap().left()) {
+            self.rotate_right();
+            self.flip_colors();
+        }
+    }
+
+    /// Restore the red-black invariants on the way up an insertion or deletion.
+    fn balance(&mut self) {
+        if is_red(self.right()) && !is_red(self.left()) {
+            self.rotate_left();
+        }
+        if is_red(self.left()) && is_red(self.left().as_ref().unwrap().left()) {
+            self.rotate_right();
+        }
+        if is_red(self.left()) && is_red(self.right()) {
+            self.flip_colors();
+        }
+        self.pull();
+    }
+}
+
+impl<K, V> RedBlackNode for Node<K, V> {
+    type Key = K;
+
+    #[inline]
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    #[inline]
+    fn color(&self) -> Color {
+        self.color
     }
+
+    #[inline]
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    #[inline]
+    fn left(&self) -> &NodeCell<K, V> {
+        &self.left
+    }
+
+    #[inline]
+    fn right(&self) -> &NodeCell<K, V> {
+        &self.right
+    }
+
+    #[inline]
+    fn left_mut(&mut self) -> &mut NodeCell<K, V> {
+        &mut self.left
+    }
+
+    #[inline]
+    fn right_mut(&mut self) -> &mut NodeCell<K, V> {
+        &mut self.right
+    }
+
+    fn swap_entry(&mut self, other: &mut Self) {
+        mem::swap(&mut self.key, &mut other.key);
+        mem::swap(&mut self.val, &mut other.val);
+    }
+
+    #[inline]
+    fn pull(&mut self) {
+        self.update_size();
+    }
+}
+
+fn is_red<N: RedBlackNode>(x: &Option<Box<N>>) -> bool {
+    x.as_ref().is_some_and(|n| n.is_red())
 }
 
-fn put<K: PartialOrd, V>(mut x: NodeCell<K, V>, key: K, val: V) -> NodeCell<K, V> {
+// put helper, the replaced value goes to old
+fn put<K: PartialOrd, V>(mut x: NodeCell<K, V>, key: K, val: V, old: &mut Option<V>) -> NodeCell<K, V> {
     if x.is_none() {
         return Some(Box::new(Node::new(key, val, Red)));
     }
@@ -150,62 +272,244 @@ fn put<K: PartialOrd, V>(mut x: NodeCell<K, V>, key: K, val: V) -> NodeCell<K, V
     match cmp {
         Ordering::Less => {
             let left = x.as_mut().unwrap().left.take();
-            x.as_mut().unwrap().left = put(left, key, val)
+            x.as_mut().unwrap().left = put(left, key, val, old)
         }
         Ordering::Greater => {
             let right = x.as_mut().unwrap().right.take();
-            x.as_mut().unwrap().right = put(right, key, val)
+            x.as_mut().unwrap().right = put(right, key, val, old)
         }
-        Ordering::Equal => x.as_mut().unwrap().val = val,
+        Ordering::Equal => *old = Some(mem::replace(&mut x.as_mut().unwrap().val, val)),
     }
+    x.as_mut().unwrap().balance();
+    x
+}
 
-    if is_red(&x.as_ref().unwrap().right) && !is_red(&x.as_ref().unwrap().left) {
-        x.as_mut().unwrap().rotate_left();
+// make the root of a detached subtree black, returns its black height
+fn blacken<K, V>(x: &mut NodeCell<K, V>, black: usize) -> usize {
+    match x {
+        Some(h) if h.is_red() => {
+            h.color = Black;
+            black + 1
+        }
+        _ => black,
+    }
+}
+
+// join a and b around mid, every key of a less than mid's and every key of b greater.
+// a and b have black roots and black heights ha and hb, the result has a black root,
+// returned with its black height. O(|ha - hb| + 1).
+fn join<K, V>(
+    a: NodeCell<K, V>,
+    ha: usize,
+    mut mid: Box<Node<K, V>>,
+    b: NodeCell<K, V>,
+    hb: usize,
+) -> (Box<Node<K, V>>, usize) {
+    // hang a red mid at the first black node of height hb down the right spine of a,
+    // or of height ha down the left spine of b, then balance as after an insertion
+    fn join_right<K, V>(
+        h: NodeCell<K, V>,
+        hh: usize,
+        mid: Box<Node<K, V>>,
+        b: NodeCell<K, V>,
+        hb: usize,
+    ) -> Box<Node<K, V>> {
+        if hh == hb {
+            return red(h, mid, b);
+        }
+        // right links are black, one level down the spine is one black link less
+        let mut h = h.unwrap();
+        h.right = Some(join_right(h.right.take(), hh - 1, mid, b, hb));
+        h.balance();
+        h
     }
-    if is_red(&x.as_ref().unwrap().left) && is_red(&x.as_ref().unwrap().left.as_ref().unwrap().left) {
-        x.as_mut().unwrap().rotate_right();
+
+    fn join_left<K, V>(
+        a: NodeCell<K, V>,
+        ha: usize,
+        mid: Box<Node<K, V>>,
+        h: NodeCell<K, V>,
+        hh: usize,
+    ) -> Box<Node<K, V>> {
+        if hh == ha {
+            return red(a, mid, h);
+        }
+        let mut h = h.unwrap();
+        if is_red(&h.left) {
+            // step over a red left link, it does not count
+            let r = h.left.as_mut().unwrap();
+            r.left = Some(join_left(a, ha, mid, r.left.take(), hh - 1));
+            r.balance();
+        } else {
+            h.left = Some(join_left(a, ha, mid, h.left.take(), hh - 1));
+        }
+        h.balance();
+        h
     }
-    if is_red(&x.as_ref().unwrap().left) && is_red(&x.as_ref().unwrap().right) {
-        x.as_mut().unwrap().flip_color();
+
+    fn red<K, V>(left: NodeCell<K, V>, mut mid: Box<Node<K, V>>, right: NodeCell<K, V>) -> Box<Node<K, V>> {
+        mid.left = left;
+        mid.right = right;
+        mid.color = Red;
+        mid.update_size();
+        mid
     }
-    x
+
+    let mut root = if ha > hb {
+        join_right(a, ha, mid, b, hb)
+    } else if ha < hb {
+        join_left(a, ha, mid, b, hb)
+    } else {
+        mid.left = a;
+        mid.right = b;
+        mid.update_size();
+        mid.color = Red;
+        mid
+    };
+    let black = ha.max(hb) + if root.is_red() { 1 } else { 0 };
+    root.color = Black;
+    (root, black)
 }
 
-fn delete<K: PartialOrd, V>(mut x: NodeCell<K, V>, key: &K) -> NodeCell<K, V> {
-    x.as_ref()?;
+// split the tree x of black height black into the keys less than key and the rest,
+// both with black roots and returned with their black heights. O(log n).
+#[allow(clippy::type_complexity)]
+fn split<K, Q, V>(x: NodeCell<K, V>, black: usize, key: &Q) -> ((NodeCell<K, V>, usize), (NodeCell<K, V>, usize))
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    let mut h = match x {
+        None => return ((None, 0), (None, 0)),
+        Some(h) => h,
+    };
+    let (mut left, mut right) = (h.left.take(), h.right.take());
+    let below = if h.is_red() { black } else { black - 1 };
+    let hl = blacken(&mut left, below);
+    let hr = blacken(&mut right, below);
+    if h.key.borrow() < key {
+        let (mid, high) = split(right, hr, key);
+        let (low, hlow) = join(left, hl, h, mid.0, mid.1);
+        ((Some(low), hlow), high)
+    } else {
+        let (low, mid) = split(left, hl, key);
+        let (high, hhigh) = join(mid.0, mid.1, h, right, hr);
+        (low, (Some(high), hhigh))
+    }
+}
 
-    match key.partial_cmp(&x.as_ref().unwrap().key).unwrap() {
-        Ordering::Less => {
-            let left = x.as_mut().unwrap().left.take();
-            x.as_mut().unwrap().left = delete(left, key);
-            x
+// number of black nodes on the path from x down to a leaf
+fn black_height<K, V>(mut x: Option<&Node<K, V>>) -> usize {
+    let mut black = 0;
+    while let Some(node) = x {
+        if !node.is_red() {
+            black += 1;
         }
-        Ordering::Greater => {
-            let right = x.as_mut().unwrap().right.take();
-            x.as_mut().unwrap().right = delete(right, key);
-            x
-        }
-        Ordering::Equal => {
-            if x.as_ref().unwrap().right.is_none() {
-                return x.as_mut().unwrap().left.take();
-            }
-            if x.as_ref().unwrap().left.is_none() {
-                return x.as_mut().unwrap().right.take();
-            }
+        x = node.left.as_deref();
+    }
+    black
+}
+
+// a tree of the next n entries of a sorted iterator, built in O(n) as a 2-3 tree
+// of height h whose 3-nodes lean left. Such a tree holds 2^h - 1 to 3^h - 1 keys.
+fn build<K, V, I: Iterator<Item = (K, V)>>(entries: &mut I, n: usize, h: u32) -> NodeCell<K, V> {
+    if n == 0 {
+        return None;
+    }
+    // subtrees of height h - 1 hold at most 3^(h-1) - 1 keys
+    let most = 3usize.pow(h - 1) - 1;
+    let mut top = if n <= 2 * most + 1 {
+        let left = build(entries, (n - 1) / 2, h - 1);
+        node(left, entries.next().unwrap(), Black)
+    } else {
+        // spread the keys of the 3-node's three subtrees evenly
+        let (k, r) = ((n - 2) / 3, (n - 2) % 3);
+        let left = build(entries, k + (r > 0) as usize, h - 1);
+        let mut red = node(left, entries.next().unwrap(), Red);
+        red.right = build(entries, k + (r > 1) as usize, h - 1);
+        red.update_size();
+        node(Some(red), entries.next().unwrap(), Black)
+    };
+    top.right = build(entries, n - size(&top.left) - 1, h - 1);
+    top.update_size();
+    return Some(top);
 
-            // Save top
-            let mut t = x;
+    fn node<K, V>(left: NodeCell<K, V>, (key, val): (K, V), color: Color) -> Box<Node<K, V>> {
+        let mut x = Box::new(Node::new(key, val, color));
+        x.left = left;
+        x
+    }
+}
 
-            // split right into right without min, and the min
-            let (right, right_min) = delete_min(t.as_mut().unwrap().right.take());
-            x = right_min;
-            x.as_mut().unwrap().right = right;
-            x.as_mut().unwrap().left = t.as_mut().unwrap().left.take();
-            x
+// a tree of the n entries of a sorted iterator
+fn from_sorted<K, V, I: Iterator<Item = (K, V)>>(n: usize, mut entries: I) -> NodeCell<K, V> {
+    // the largest h with 2^h - 1 <= n, and n <= 3^h - 1 holds then too
+    let h = usize::BITS - 1 - (n + 1).leading_zeros();
+    build(&mut entries, n, h)
+}
+
+// delete helper, the key must be in the subtree
+// returns: top, deleted
+pub(crate) fn delete<N, Q>(mut h: Box<N>, key: &Q) -> (Option<Box<N>>, Box<N>)
+where
+    N: RedBlackNode,
+    N::Key: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    let deleted;
+    if key < h.key().borrow() {
+        if !is_red(h.left()) && !is_red(h.left().as_ref().unwrap().left()) {
+            h.move_red_left();
+        }
+        let (left, d) = delete(h.left_mut().take().unwrap(), key);
+        *h.left_mut() = left;
+        deleted = d;
+    } else {
+        if is_red(h.left()) {
+            h.rotate_right();
+        }
+        if key == h.key().borrow() && h.right().is_none() {
+            return (None, h);
+        }
+        if !is_red(h.right()) && !is_red(h.right().as_ref().unwrap().left()) {
+            h.move_red_right();
+        }
+        if key == h.key().borrow() {
+            // replace with the successor, and hand back the old key and value
+            let (right, mut min) = delete_min(h.right_mut().take().unwrap());
+            h.swap_entry(&mut min);
+            *h.right_mut() = right;
+            deleted = min;
+        } else {
+            let (right, d) = delete(h.right_mut().take().unwrap(), key);
+            *h.right_mut() = right;
+            deleted = d;
         }
     }
+    h.balance();
+    (Some(h), deleted)
 }
 
+// run a deletion helper on a root, which must not be empty
+pub(crate) fn delete_root<N, F>(root: &mut Option<Box<N>>, delete: F) -> Box<N>
+where
+    N: RedBlackNode,
+    F: FnOnce(Box<N>) -> (Option<Box<N>>, Box<N>),
+{
+    let mut h = root.take().unwrap();
+    // if both children of root are black, set root to red
+    if !is_red(h.left()) && !is_red(h.right()) {
+        h.set_color(Red);
+    }
+    let (h, deleted) = delete(h);
+    *root = h;
+    if let Some(h) = root.as_mut() {
+        h.set_color(Black);
+    }
+    deleted
+}
+
+#[derive(Clone)]
 pub struct RedBlackBST<K, V> {
     pub root: NodeCell<K, V>,
 }
@@ -230,34 +534,104 @@ impl<K: PartialOrd, V> RedBlackBST<K, V> {
         RedBlackBST { root: None }
     }
 
-    pub fn contains(&self, key: &K) -> bool {
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
         self.get(key).is_some()
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
-        let mut x = self.root.as_ref();
-        while x.is_some() {
-            match key.partial_cmp(&x.unwrap().key).unwrap() {
-                Ordering::Less => {
-                    x = x.unwrap().left.as_ref();
-                }
-                Ordering::Greater => {
-                    x = x.unwrap().right.as_ref();
-                }
-                Ordering::Equal => return Some(&x.unwrap().val),
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    /// the stored key and its value
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some((&node.key, &node.val)),
+            }
+        }
+        None
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.find_mut(key).map(|node| &mut node.val)
+    }
+
+    fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref_mut();
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref_mut(),
+                Ordering::Greater => x = node.right.as_deref_mut(),
+                Ordering::Equal => return Some(node),
             }
         }
         None
     }
 
     pub fn put(&mut self, key: K, val: V) {
-        self.root = put(self.root.take(), key, val);
+        self.insert(key, val);
+    }
+
+    /// insert a key-value pair, returns the value the key had before
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        let mut old = None;
+        self.root = put(self.root.take(), key, val, &mut old);
         // FIXME: too bad
         self.root.as_mut().unwrap().color = Black;
+        old
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove(key);
     }
 
-    pub fn delete(&mut self, key: &K) {
-        self.root = delete(self.root.take(), key);
+    /// remove a key, returns its value if it was in the table
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// remove a key, returns the stored key and its value
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        if !self.contains(key) {
+            return None;
+        }
+        let node = delete_root(&mut self.root, |root| delete(root, key));
+        Some((node.key, node.val))
     }
 
     pub fn is_empty(&self) -> bool {
@@ -266,18 +640,18 @@ impl<K: PartialOrd, V> RedBlackBST<K, V> {
 
     /// number of key-value pairs in the table
     pub fn size(&self) -> usize {
-        if self.is_empty() {
-            0
-        } else {
-            self.root.as_ref().unwrap().size()
-        }
+        size(&self.root)
     }
 }
 
-fn floor<'a, K: PartialOrd, V>(x: Option<&'a Node<K, V>>, key: &K) -> Option<&'a Node<K, V>> {
+fn floor<'a, K, Q, V>(x: Option<&'a Node<K, V>>, key: &Q) -> Option<&'a Node<K, V>>
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
     x?;
 
-    match key.partial_cmp(&x.unwrap().key).unwrap() {
+    match key.partial_cmp(x.unwrap().key.borrow()).unwrap() {
         Ordering::Equal => {
             return Some(x.unwrap());
         }
@@ -295,10 +669,14 @@ fn floor<'a, K: PartialOrd, V>(x: Option<&'a Node<K, V>>, key: &K) -> Option<&'a
     }
 }
 
-fn ceiling<'a, K: PartialOrd, V>(x: Option<&'a Node<K, V>>, key: &K) -> Option<&'a Node<K, V>> {
+fn ceiling<'a, K, Q, V>(x: Option<&'a Node<K, V>>, key: &Q) -> Option<&'a Node<K, V>>
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
     x?;
 
-    match key.partial_cmp(&x.unwrap().key).unwrap() {
+    match key.partial_cmp(x.unwrap().key.borrow()).unwrap() {
         Ordering::Equal => {
             return Some(x.unwrap());
         }
@@ -318,34 +696,35 @@ fn ceiling<'a, K: PartialOrd, V>(x: Option<&'a Node<K, V>>, key: &K) -> Option<&
 
 // delete_min helper
 // returns: top, deleted
-fn delete_min<K: PartialOrd, V>(mut x: NodeCell<K, V>) -> (NodeCell<K, V>, NodeCell<K, V>) {
-    if x.is_none() {
-        return (None, None);
+pub(crate) fn delete_min<N: RedBlackNode>(mut h: Box<N>) -> (Option<Box<N>>, Box<N>) {
+    if h.left().is_none() {
+        return (None, h);
     }
-    match x.as_mut().unwrap().left.take() {
-        None => (x.as_mut().unwrap().right.take(), x),
-        left @ Some(_) => {
-            let (t, deleted) = delete_min(left);
-            x.as_mut().unwrap().left = t;
-            (x, deleted)
-        }
+    if !is_red(h.left()) && !is_red(h.left().as_ref().unwrap().left()) {
+        h.move_red_left();
     }
+    let (left, deleted) = delete_min(h.left_mut().take().unwrap());
+    *h.left_mut() = left;
+    h.balance();
+    (Some(h), deleted)
 }
 
 // delete_max helper
 // returns: top, deleted
-fn delete_max<K: PartialOrd, V>(mut x: NodeCell<K, V>) -> (NodeCell<K, V>, NodeCell<K, V>) {
-    if x.is_none() {
-        return (None, None);
+fn delete_max<N: RedBlackNode>(mut h: Box<N>) -> (Option<Box<N>>, Box<N>) {
+    if is_red(h.left()) {
+        h.rotate_right();
     }
-    match x.as_mut().unwrap().right.take() {
-        None => (x.as_mut().unwrap().left.take(), x),
-        right @ Some(_) => {
-            let (t, deleted) = delete_max(right);
-            x.as_mut().unwrap().right = t;
-            (x, deleted)
-        }
+    if h.right().is_none() {
+        return (None, h);
+    }
+    if !is_red(h.right()) && !is_red(h.right().as_ref().unwrap().left()) {
+        h.move_red_right();
     }
+    let (right, deleted) = delete_max(h.right_mut().take().unwrap());
+    *h.right_mut() = right;
+    h.balance();
+    (Some(h), deleted)
 }
 
 fn find_max<K: PartialOrd, V>(x: Option<&Node<K, V>>) -> Option<&Node<K, V>> {
@@ -376,7 +755,11 @@ impl<K: PartialOrd, V> RedBlackBST<K, V> {
     }
 
     /// largest key less than or equal to key
-    pub fn floor(&self, key: &K) -> Option<&K> {
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
         let x = floor(self.root.as_deref(), key);
         if let Some(x) = x {
             Some(&x.key)
@@ -386,7 +769,11 @@ impl<K: PartialOrd, V> RedBlackBST<K, V> {
     }
 
     /// smallest key greater than or equal to key
-    pub fn ceiling(&self, key: &K) -> Option<&K> {
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
         let x = ceiling(self.root.as_deref(), key);
         if let Some(x) = x {
             Some(&x.key)
@@ -396,19 +783,24 @@ impl<K: PartialOrd, V> RedBlackBST<K, V> {
     }
 
     /// number of keys less than key
-    pub fn rank(&self, key: &K) -> usize {
-        fn rank_helper<K: PartialOrd, V>(x: Option<&Node<K, V>>, key: &K) -> usize {
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        fn rank_helper<K, Q, V>(x: Option<&Node<K, V>>, key: &Q) -> usize
+        where
+            K: Borrow<Q>,
+            Q: PartialOrd + ?Sized,
+        {
             if x.is_none() {
                 return 0;
             }
 
-            match key.partial_cmp(&x.unwrap().key).unwrap() {
+            match key.partial_cmp(x.unwrap().key.borrow()).unwrap() {
                 Ordering::Less => rank_helper(x.unwrap().left.as_deref(), key),
-                Ordering::Greater => {
-                    1 + x.as_ref().unwrap().left.as_ref().map_or(0, |n| n.size())
-                        + rank_helper(x.unwrap().right.as_deref(), key)
-                }
-                Ordering::Equal => x.as_ref().unwrap().left.as_ref().map_or(0, |n| n.size()),
+                Ordering::Greater => 1 + size(&x.unwrap().left) + rank_helper(x.unwrap().right.as_deref(), key),
+                Ordering::Equal => size(&x.unwrap().left),
             }
         }
 
@@ -417,33 +809,747 @@ impl<K: PartialOrd, V> RedBlackBST<K, V> {
 
     /// key of rank k
     pub fn select(&self, k: usize) -> Option<&K> {
-        self.keys().find(|&key| self.rank(key) == k)
+        let mut x = self.root.as_deref();
+        let mut k = k;
+        while let Some(node) = x {
+            let t = size(&node.left);
+            match k.cmp(&t) {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    x = node.right.as_deref();
+                    k -= t + 1;
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        None
+    }
+
+    /// number of keys in [lo, hi]
+    pub fn range_count<Q>(&self, lo: &Q, hi: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        if lo > hi {
+            0
+        } else if self.contains(hi) {
+            self.rank(hi) - self.rank(lo) + 1
+        } else {
+            self.rank(hi) - self.rank(lo)
+        }
     }
 
     /// delete smallest key
     pub fn delete_min(&mut self) {
-        self.root = delete_min(self.root.take()).0;
+        self.pop_first();
     }
 
     /// delete largest key
     pub fn delete_max(&mut self) {
-        self.root = delete_max(self.root.take()).0;
+        self.pop_last();
+    }
+
+    /// entry with the smallest key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        find_min(self.root.as_deref()).map(|n| (&n.key, &n.val))
+    }
+
+    /// entry with the largest key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        find_max(self.root.as_deref()).map(|n| (&n.key, &n.val))
+    }
+
+    /// remove and return the entry with the smallest key
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        let node = delete_root(&mut self.root, delete_min);
+        Some((node.key, node.val))
+    }
+
+    /// remove and return the entry with the largest key
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        let node = delete_root(&mut self.root, delete_max);
+        Some((node.key, node.val))
+    }
+
+    /// split off the keys greater than or equal to key into a new table
+    pub fn split_off<Q>(&mut self, key: &Q) -> RedBlackBST<K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        // split down the search path, joining the subtrees on either side of it
+        let black = black_height(self.root.as_deref());
+        let ((low, _), (high, _)) = split(self.root.take(), black, key);
+        self.root = low;
+        RedBlackBST { root: high }
+    }
+
+    /// move all entries of other into the table, other's values win on equal keys
+    pub fn append(&mut self, other: &mut RedBlackBST<K, V>) {
+        if self.max().is_some_and(|max| other.min().is_some_and(|min| max < min)) {
+            // all keys of other are greater, join the two trees around other's smallest entry
+            let mid = delete_root(&mut other.root, delete_min);
+            let (ha, hb) = (black_height(self.root.as_deref()), black_height(other.root.as_deref()));
+            let (root, _) = join(self.root.take(), ha, mid, other.root.take(), hb);
+            self.root = Some(root);
+            return;
+        }
+        // insert the smaller table into the larger one
+        if other.size() > self.size() {
+            mem::swap(self, other);
+            for (k, v) in mem::take(other) {
+                self.entry(k).or_insert(v);
+            }
+        } else {
+            self.extend(mem::take(other));
+        }
+    }
+
+    /// keep only the entries for which f returns true
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        // the entries kept are sorted, build a tree of them in one pass
+        let kept = mem::take(self)
+            .into_iter()
+            .filter_map(|(k, mut v)| if f(&k, &mut v) { Some((k, v)) } else { None })
+            .collect::<Vec<_>>();
+        self.root = from_sorted(kept.len(), kept.into_iter());
+    }
+
+    /// view of the entry for key, to insert or update in place
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        if self.contains(&key) {
+            Entry::Occupied(OccupiedEntry {
+                node: self.find_mut(&key).unwrap(),
+            })
+        } else {
+            Entry::Vacant(VacantEntry { tree: self, key })
+        }
+    }
+}
+
+impl<K: PartialOrd, V> FromIterator<(K, V)> for RedBlackBST<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut t = RedBlackBST::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<K: PartialOrd, V> Extend<(K, V)> for RedBlackBST<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+impl<K, Q, V> Index<&Q> for RedBlackBST<K, V>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    fn index(&self, index: &Q) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for RedBlackBST<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        size(&self.root) == size(&other.root) && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for RedBlackBST<K, V> {}
+
+/// A view into a single entry of a `RedBlackBST`, which may be vacant or occupied
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    node: &'a mut Node<K, V>,
+}
+
+pub struct VacantEntry<'a, K, V> {
+    tree: &'a mut RedBlackBST<K, V>,
+    key: K,
+}
+
+impl<'a, K: PartialOrd, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref e) => e.key(),
+            Entry::Vacant(ref e) => e.key(),
+        }
+    }
+
+    /// insert `default` if the entry is vacant, and return the value
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// insert the result of `default` if the entry is vacant, and return the value
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// modify the value in place if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(e) => {
+                f(&mut e.node.val);
+                Entry::Occupied(e)
+            }
+            vacant => vacant,
+        }
+    }
+}
+
+impl<'a, K: PartialOrd, V: Default> Entry<'a, K, V> {
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.node.key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.node.val
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.node.val
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.node.val
+    }
+
+    /// replace the value, returning the old one
+    pub fn insert(&mut self, val: V) -> V {
+        mem::replace(&mut self.node.val, val)
+    }
+}
+
+impl<'a, K: PartialOrd, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn insert(self, val: V) -> &'a mut V {
+        let VacantEntry { tree, key } = self;
+        // the key is moved into the tree, so find it again by rank
+        let rank = count_prefix(tree.root.as_deref(), |k| *k < key);
+        tree.put(key, val);
+        let mut x = tree.root.as_deref_mut();
+        let mut k = rank;
+        while let Some(node) = x {
+            let t = size(&node.left);
+            match k.cmp(&t) {
+                Ordering::Less => x = node.left.as_deref_mut(),
+                Ordering::Greater => {
+                    x = node.right.as_deref_mut();
+                    k -= t + 1;
+                }
+                Ordering::Equal => return &mut node.val,
+            }
+        }
+        unreachable!()
+    }
+}
+
+// number of keys in the subtree for which pred holds, given it holds for a
+// prefix of the keys in order
+fn count_prefix<K, V, F: Fn(&K) -> bool>(mut x: Option<&Node<K, V>>, pred: F) -> usize {
+    let mut n = 0;
+    while let Some(node) = x {
+        if pred(&node.key) {
+            n += 1 + size(&node.left);
+            x = node.right.as_deref();
+        } else {
+            x = node.left.as_deref();
+        }
+    }
+    n
+}
+
+impl<K, V> RedBlackBST<K, V> {
+    /// iterator over the entries, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            len: size(&self.root),
+        };
+        iter.push_left(self.root.as_deref());
+        iter.push_right(self.root.as_deref());
+        iter
+    }
+
+    /// iterator over the entries with mutable values, in key order
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        let len = size(&self.root);
+        IterMut {
+            pending: self.root.as_deref_mut().map(Pending::Tree).into_iter().collect(),
+            len,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut { inner: self.iter_mut() }
     }
 }
 
 impl<K: PartialOrd, V> RedBlackBST<K, V> {
-    pub fn keys(&self) -> ::std::vec::IntoIter<&K> {
-        fn inorder<'a, K, V>(x: Option<&'a Node<K, V>>, queue: &mut Vec<&'a K>) {
-            if x.is_none() {
-                return;
+    /// iterator over the entries with keys in the range, in key order
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let after_start = |key: &K| match range.start_bound() {
+            Bound::Included(lo) => key.borrow() >= lo,
+            Bound::Excluded(lo) => key.borrow() > lo,
+            Bound::Unbounded => true,
+        };
+        let before_end = |key: &K| match range.end_bound() {
+            Bound::Included(hi) => key.borrow() <= hi,
+            Bound::Excluded(hi) => key.borrow() < hi,
+            Bound::Unbounded => true,
+        };
+
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            len: count_prefix(self.root.as_deref(), before_end)
+                .saturating_sub(count_prefix(self.root.as_deref(), |k| !after_start(k))),
+        };
+        // keep the nodes inside the range along the search paths to either bound
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if after_start(&node.key) {
+                iter.front.push(node);
+                x = node.left.as_deref();
+            } else {
+                x = node.right.as_deref();
+            }
+        }
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if before_end(&node.key) {
+                iter.back.push(node);
+                x = node.right.as_deref();
+            } else {
+                x = node.left.as_deref();
+            }
+        }
+        iter
+    }
+}
+
+/// Iterator over the entries of a `RedBlackBST` in key order, from either end.
+pub struct Iter<'a, K, V> {
+    // nodes not visited yet, whose smaller (front) or larger (back) keys are
+    front: Vec<&'a Node<K, V>>,
+    back: Vec<&'a Node<K, V>>,
+    // entries left between the two ends
+    len: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.front.push(node);
+            x = node.left.as_deref();
+        }
+    }
+
+    fn push_right(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.back.push(node);
+            x = node.right.as_deref();
+        }
+    }
+}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            front: self.front.clone(),
+            back: self.back.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.push_left(node.right.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.push_right(node.left.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.val))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+// part of an in-order walk not visited yet: a whole subtree, or a single entry
+enum Pending<T, E> {
+    Tree(T),
+    Entry(E),
+}
+
+type PendingMut<'a, K, V> = Pending<&'a mut Node<K, V>, (&'a K, &'a mut V)>;
+type PendingOwned<K, V> = Pending<Box<Node<K, V>>, (K, V)>;
+
+/// Mutable iterator over the entries of a `RedBlackBST` in key order, from either end.
+pub struct IterMut<'a, K, V> {
+    // parts of the tree in key order, each end splits the trees it meets
+    pending: VecDeque<PendingMut<'a, K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_front()? {
+                Pending::Entry(entry) => {
+                    self.len -= 1;
+                    return Some(entry);
+                }
+                Pending::Tree(node) => {
+                    let Node {
+                        key, val, left, right, ..
+                    } = node;
+                    if let Some(right) = right.as_deref_mut() {
+                        self.pending.push_front(Pending::Tree(right));
+                    }
+                    self.pending.push_front(Pending::Entry((&*key, val)));
+                    if let Some(left) = left.as_deref_mut() {
+                        self.pending.push_front(Pending::Tree(left));
+                    }
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_back()? {
+                Pending::Entry(entry) => {
+                    self.len -= 1;
+                    return Some(entry);
+                }
+                Pending::Tree(node) => {
+                    let Node {
+                        key, val, left, right, ..
+                    } = node;
+                    if let Some(left) = left.as_deref_mut() {
+                        self.pending.push_back(Pending::Tree(left));
+                    }
+                    self.pending.push_back(Pending::Entry((&*key, val)));
+                    if let Some(right) = right.as_deref_mut() {
+                        self.pending.push_back(Pending::Tree(right));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+/// Owning iterator over the entries of a `RedBlackBST` in key order, from either end.
+pub struct IntoIter<K, V> {
+    pending: VecDeque<PendingOwned<K, V>>,
+    len: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_front()? {
+                Pending::Entry(entry) => {
+                    self.len -= 1;
+                    return Some(entry);
+                }
+                Pending::Tree(node) => {
+                    let Node {
+                        key, val, left, right, ..
+                    } = *node;
+                    if let Some(right) = right {
+                        self.pending.push_front(Pending::Tree(right));
+                    }
+                    self.pending.push_front(Pending::Entry((key, val)));
+                    if let Some(left) = left {
+                        self.pending.push_front(Pending::Tree(left));
+                    }
+                }
             }
-            inorder(x.unwrap().left.as_deref(), queue);
-            queue.push(&x.unwrap().key);
-            inorder(x.unwrap().right.as_deref(), queue);
         }
-        let mut queue = Vec::new();
-        inorder(self.root.as_deref(), &mut queue);
-        queue.into_iter()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_back()? {
+                Pending::Entry(entry) => {
+                    self.len -= 1;
+                    return Some(entry);
+                }
+                Pending::Tree(node) => {
+                    let Node {
+                        key, val, left, right, ..
+                    } = *node;
+                    if let Some(left) = left {
+                        self.pending.push_back(Pending::Tree(left));
+                    }
+                    self.pending.push_back(Pending::Entry((key, val)));
+                    if let Some(right) = right {
+                        self.pending.push_back(Pending::Tree(right));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> IntoIterator for RedBlackBST<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        let len = size(&self.root);
+        IntoIter {
+            pending: self.root.map(Pending::Tree).into_iter().collect(),
+            len,
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a RedBlackBST<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut RedBlackBST<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K: PartialOrd, V> RedBlackBST<K, V> {
+    /// check the red-black tree invariants, naming the first one violated
+    pub fn check(&self) -> Result<(), &'static str> {
+        // keys strictly between lo and hi
+        fn is_bst<K: PartialOrd, V>(x: Option<&Node<K, V>>, lo: Option<&K>, hi: Option<&K>) -> bool {
+            match x {
+                None => true,
+                Some(x) => {
+                    lo.is_none_or(|lo| x.key > *lo)
+                        && hi.is_none_or(|hi| x.key < *hi)
+                        && is_bst(x.left.as_deref(), lo, Some(&x.key))
+                        && is_bst(x.right.as_deref(), Some(&x.key), hi)
+                }
+            }
+        }
+
+        fn is_size_consistent<K, V>(x: Option<&Node<K, V>>) -> bool {
+            match x {
+                None => true,
+                Some(x) => {
+                    x.n == 1 + size(&x.left) + size(&x.right)
+                        && is_size_consistent(x.left.as_deref())
+                        && is_size_consistent(x.right.as_deref())
+                }
+            }
+        }
+
+        // no red right links, and no two red links in a row
+        fn is_23<K, V>(x: Option<&Node<K, V>>) -> bool {
+            match x {
+                None => true,
+                Some(x) => {
+                    !is_red(&x.right)
+                        && (!x.is_red() || !is_red(&x.left))
+                        && is_23(x.left.as_deref())
+                        && is_23(x.right.as_deref())
+                }
+            }
+        }
+
+        // every path from x to a leaf has `black` black links
+        fn is_balanced<K, V>(x: Option<&Node<K, V>>, black: usize) -> bool {
+            match x {
+                None => black == 0,
+                Some(x) => {
+                    let black = if x.is_red() { black } else { black.wrapping_sub(1) };
+                    is_balanced(x.left.as_deref(), black) && is_balanced(x.right.as_deref(), black)
+                }
+            }
+        }
+
+        let root = self.root.as_deref();
+        let mut black = 0;
+        let mut x = root;
+        while let Some(node) = x {
+            if !node.is_red() {
+                black += 1;
+            }
+            x = node.left.as_deref();
+        }
+
+        if !is_bst(root, None, None) {
+            Err("not in symmetric order")
+        } else if !is_size_consistent(root) {
+            Err("subtree counts not consistent")
+        } else if is_red(&self.root) || !is_23(root) {
+            Err("not a 2-3 tree")
+        } else if !is_balanced(root, black) {
+            Err("not balanced")
+        } else {
+            Ok(())
+        }
     }
 }
 
@@ -494,3 +1600,220 @@ fn test_red_black_tree() {
     // inorder visite
     assert_eq!(String::from_iter(t.keys().copied()), "ACEHMPRSX");
 }
+
+#[test]
+fn test_red_black_tree_order_statistics() {
+    let mut t = RedBlackBST::new();
+    for i in (0..1000).map(|i| (i * 7919) % 1000) {
+        t.put(i * 2, ());
+    }
+    assert_eq!(t.size(), 1000);
+    assert_eq!(t.rank(&500), 250);
+    assert_eq!(t.rank(&501), 251);
+    assert_eq!(t.select(250), Some(&500));
+    assert_eq!(t.select(999), Some(&1998));
+    assert_eq!(t.select(1000), None);
+    assert!((0..1000).all(|k| t.rank(t.select(k).unwrap()) == k));
+
+    assert_eq!(t.range_count(&10, &20), 6);
+    assert_eq!(t.range_count(&11, &19), 4);
+    assert_eq!(t.range_count(&20, &10), 0);
+    assert_eq!(t.range_count(&-5, &5000), 1000);
+
+    for i in 0..100 {
+        t.delete(&(i * 4));
+    }
+    t.delete_min();
+    t.delete_max();
+    assert_eq!(t.size(), 898);
+    assert_eq!(t.select(0), Some(&6));
+    assert_eq!(t.range_count(&0, &400), 100);
+    assert_eq!(t.check(), Ok(()));
+}
+
+#[test]
+fn quicktest_red_black_tree_delete() {
+    use std::collections::BTreeMap;
+
+    // 0: put, 1: delete, 2: delete_min, 3: delete_max
+    fn prop(ops: Vec<(u8, u8)>) -> bool {
+        let mut t = RedBlackBST::new();
+        let mut model = BTreeMap::new();
+        for &(op, k) in ops.iter() {
+            match op % 4 {
+                0 => {
+                    t.put(k, op);
+                    model.insert(k, op);
+                }
+                1 => {
+                    t.delete(&k);
+                    model.remove(&k);
+                }
+                2 => {
+                    t.delete_min();
+                    model.pop_first();
+                }
+                _ => {
+                    t.delete_max();
+                    model.pop_last();
+                }
+            }
+            if t.check().is_err() {
+                return false;
+            }
+        }
+        t.size() == model.len() && model.iter().all(|(k, v)| t.get(k) == Some(v))
+    }
+
+    quickcheck(prop as fn(Vec<(u8, u8)>) -> bool);
+}
+
+#[test]
+fn test_red_black_tree_iter() {
+    let mut t = RedBlackBST::new();
+    for c in "SEARCHEXAMPLE".chars() {
+        t.put(c, c as u32);
+    }
+    assert_eq!(t.iter().len(), 10);
+    assert_eq!(t.keys().rev().collect::<String>(), "XSRPMLHECA");
+    assert_eq!(t.values().next(), Some(&65));
+
+    // both ends meet in the middle
+    let mut iter = t.keys();
+    assert_eq!((iter.next(), iter.next_back()), (Some(&'A'), Some(&'X')));
+    assert_eq!(iter.len(), 8);
+    assert_eq!(iter.collect::<String>(), "CEHLMPRS");
+
+    assert_eq!(t.range('E'..'P').map(|(k, _)| *k).collect::<String>(), "EHLM");
+    assert_eq!(t.range('F'..='P').rev().map(|(k, _)| *k).collect::<String>(), "PMLH");
+    assert_eq!(t.range(..'C').len(), 1);
+    assert_eq!(t.range('Y'..).next(), None);
+    assert_eq!(t.range((Bound::Excluded('R'), Bound::Unbounded)).len(), 2);
+
+    for (k, v) in t.iter_mut().rev().take(2) {
+        *v = *k as u32 + 100;
+    }
+    *t.values_mut().next().unwrap() = 0;
+    assert_eq!(t.get(&'S'), Some(&183));
+    assert_eq!(t.get(&'A'), Some(&0));
+    assert_eq!((&t).into_iter().filter(|&(_, &v)| v > 100).count(), 2);
+
+    let mut into_iter = t.into_iter();
+    assert_eq!(into_iter.next_back(), Some(('X', 188)));
+    assert_eq!(into_iter.map(|(k, _)| k).collect::<String>(), "ACEHLMPRS");
+}
+
+#[test]
+fn quicktest_red_black_tree_range() {
+    use std::collections::BTreeMap;
+
+    fn prop(keys: Vec<u8>, lo: u8, hi: u8, inclusive: bool) -> bool {
+        let t = keys.iter().map(|&k| (k, ())).fold(RedBlackBST::new(), |mut t, (k, v)| {
+            t.put(k, v);
+            t
+        });
+        let model = keys.iter().map(|&k| (k, ())).collect::<BTreeMap<_, _>>();
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        let (got, expected) = if inclusive {
+            (t.range(lo..=hi), model.range(lo..=hi))
+        } else {
+            (t.range(lo..hi), model.range(lo..hi))
+        };
+        got.len() == expected.clone().count() && got.rev().eq(expected.rev())
+    }
+
+    quickcheck(prop as fn(Vec<u8>, u8, u8, bool) -> bool);
+}
+
+#[test]
+fn test_red_black_tree_map_api() {
+    let mut t = "it was the best of times it was the worst of times"
+        .split(' ')
+        .map(|w| (w.to_string(), w.len()))
+        .collect::<RedBlackBST<String, usize>>();
+    assert_eq!(t.size(), 7);
+    // lookups by &str
+    assert_eq!(t["best"], 4);
+    assert_eq!(t.get_key_value("of"), Some((&"of".to_string(), &2)));
+    assert_eq!(t.insert("the".into(), 0), Some(3));
+    assert_eq!(t.insert("age".into(), 3), None);
+    *t.get_mut("age").unwrap() += 1;
+    assert_eq!(t.remove("it"), Some(2));
+    assert_eq!(t.remove("it"), None);
+
+    *t.entry("was".into()).or_insert(0) += 10;
+    *t.entry("wisdom".into()).or_default() += 6;
+    t.entry("foolishness".into()).and_modify(|v| *v = 0).or_insert(11);
+    assert_eq!((t["was"], t["wisdom"], t["foolishness"]), (13, 6, 11));
+
+    assert_eq!(t.first_key_value(), Some((&"age".to_string(), &4)));
+    assert_eq!(t.last_key_value(), Some((&"worst".to_string(), &5)));
+    assert_eq!(t.pop_first(), Some(("age".into(), 4)));
+    assert_eq!(t.pop_last(), Some(("worst".into(), 5)));
+
+    let copy = t.clone();
+    assert!(copy == t);
+    let mut upper = t.split_off("of");
+    assert_eq!(t.keys().cloned().collect::<Vec<_>>(), ["best", "foolishness"]);
+    assert_eq!(upper.keys().cloned().collect::<Vec<_>>(), ["of", "the", "times", "was", "wisdom"]);
+    assert_eq!(upper.check(), Ok(()));
+    assert!(copy != t);
+
+    upper.retain(|k, v| {
+        *v += 1;
+        k.starts_with('t')
+    });
+    assert_eq!(upper.iter().map(|(k, &v)| (k.as_str(), v)).collect::<Vec<_>>(), [("the", 1), ("times", 6)]);
+
+    let mut other = RedBlackBST::new();
+    other.extend(vec![("the".to_string(), 100), ("of".to_string(), 2)]);
+    t.append(&mut upper);
+    t.append(&mut other);
+    assert!(upper.is_empty() && other.is_empty());
+    assert_eq!(t.values().copied().collect::<Vec<_>>(), [4, 11, 2, 100, 6]);
+    let tail = t.split_off("the");
+    assert_eq!((t.size(), tail.size()), (3, 2));
+    assert_eq!((t.check(), tail.check()), (Ok(()), Ok(())));
+
+    // ordered queries by &str
+    assert_eq!(t.floor("cat").map(String::as_str), Some("best"));
+    assert_eq!(t.ceiling("cat").map(String::as_str), Some("foolishness"));
+    assert_eq!((t.rank("of"), t.range_count("a", "g")), (2, 2));
+}
+
+#[test]
+fn quicktest_red_black_tree_split_off() {
+    use std::collections::BTreeMap;
+
+    // split at key, retain on both halves, then append them back
+    fn prop(keys: Vec<(u8, u16)>, key: u8, modulo: u8) -> bool {
+        let mut t = keys.iter().cloned().collect::<RedBlackBST<_, _>>();
+        let mut model = keys.into_iter().collect::<BTreeMap<_, _>>();
+        let entries = |t: &RedBlackBST<u8, u16>| t.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+        let same = |t: &RedBlackBST<u8, u16>, model: &BTreeMap<u8, u16>| {
+            t.check().is_ok() && entries(t) == model.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>()
+        };
+
+        let mut upper = t.split_off(&key);
+        let mut model_upper = model.split_off(&key);
+        if !same(&t, &model) || !same(&upper, &model_upper) {
+            return false;
+        }
+        let keep = |k: &u8, v: &mut u16| {
+            *v = v.wrapping_add(1);
+            k.is_multiple_of(modulo % 4 + 1)
+        };
+        t.retain(keep);
+        model.retain(keep);
+        upper.retain(keep);
+        model_upper.retain(keep);
+        if !same(&t, &model) || !same(&upper, &model_upper) {
+            return false;
+        }
+        t.append(&mut upper);
+        model.append(&mut model_upper);
+        same(&t, &model) && upper.is_empty()
+    }
+
+    quickcheck(prop as fn(Vec<(u8, u16)>, u8, u8) -> bool);
+}
@@ -0,0 +1,330 @@
+use super::{hash_pair, put_u64, Reader, SketchError, StableHashBuilder};
+use std::f64::consts::LN_2;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+/// optimal (bits, hash functions) for `n` items at false positive rate `p`
+fn optimal_size(n: usize, p: f64) -> (usize, usize) {
+    assert!(p > 0.0 && p < 1.0, "false positive rate must be in (0, 1)");
+    let n = n.max(1) as f64;
+    let m = (-n * p.ln() / (LN_2 * LN_2)).ceil();
+    let k = (m / n * LN_2).round().max(1.0);
+    (m as usize, k as usize)
+}
+
+/// Approximate set membership: `contains` may report false positives, but
+/// never false negatives.
+pub struct BloomFilter<T: ?Sized, S = StableHashBuilder> {
+    bits: Vec<u64>,
+    m: usize,
+    k: usize,
+    n: usize,
+    hash_builder: S,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> BloomFilter<T> {
+    /// create a filter for `n` items with false positive rate `p`
+    pub fn new(n: usize, p: f64) -> BloomFilter<T> {
+        BloomFilter::with_rate_and_hasher(n, p, Default::default())
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> BloomFilter<T, S> {
+    pub fn with_rate_and_hasher(n: usize, p: f64, hash_builder: S) -> BloomFilter<T, S> {
+        let (m, k) = optimal_size(n, p);
+        BloomFilter::with_size_and_hasher(m, k, hash_builder)
+    }
+
+    /// create a filter of `m` bits probed by `k` hash functions
+    pub fn with_size_and_hasher(m: usize, k: usize, hash_builder: S) -> BloomFilter<T, S> {
+        assert!(m > 0 && k > 0, "a bloom filter needs bits and hash functions");
+        BloomFilter {
+            bits: vec![0; m.div_ceil(64)],
+            m,
+            k,
+            n: 0,
+            hash_builder,
+            _marker: PhantomData,
+        }
+    }
+
+    fn indices(&self, item: &T) -> impl Iterator<Item = usize> {
+        let (h1, h2) = hash_pair(&self.hash_builder, item);
+        let m = self.m as u64;
+        (0..self.k as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+
+    pub fn insert(&mut self, item: &T) {
+        for i in self.indices(item) {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+        self.n += 1;
+    }
+
+    /// is the item possibly in the set?
+    pub fn contains(&self, item: &T) -> bool {
+        self.indices(item).all(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|w| *w = 0);
+        self.n = 0;
+    }
+
+    /// number of insertions
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.m
+    }
+
+    pub fn num_hashes(&self) -> usize {
+        self.k
+    }
+
+    /// expected false positive rate after the insertions so far
+    pub fn false_positive_rate(&self) -> f64 {
+        let (m, k, n) = (self.m as f64, self.k as f64, self.n as f64);
+        (1.0 - (-k * n / m).exp()).powf(k)
+    }
+
+    /// union with a filter of the same size
+    pub fn merge(&mut self, other: &BloomFilter<T, S>) -> Result<(), SketchError> {
+        if self.m != other.m || self.k != other.k {
+            return Err(SketchError::Incompatible);
+        }
+        for (w, o) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *w |= *o;
+        }
+        self.n += other.n;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = b"ADBF".to_vec();
+        put_u64(&mut buf, self.m as u64);
+        put_u64(&mut buf, self.k as u64);
+        put_u64(&mut buf, self.n as u64);
+        for &w in self.bits.iter() {
+            put_u64(&mut buf, w);
+        }
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8], hash_builder: S) -> Result<BloomFilter<T, S>, SketchError> {
+        let mut r = Reader::new(bytes, b"ADBF")?;
+        let (m, k, n) = (r.usize()?, r.usize()?, r.usize()?);
+        if m == 0 || k == 0 {
+            return Err(SketchError::Malformed);
+        }
+        // check the payload is there before allocating anything for it
+        let len = m.div_ceil(64).checked_mul(8).ok_or(SketchError::Malformed)?;
+        let bits = r
+            .finish(len)?
+            .chunks(8)
+            .map(|chunk| {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(chunk);
+                u64::from_le_bytes(buf)
+            })
+            .collect();
+        Ok(BloomFilter {
+            bits,
+            m,
+            k,
+            n,
+            hash_builder,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Bloom filter with a small counter per slot, supporting removal.
+///
+/// Counters saturate at 255 and are never decremented from there, so a
+/// removal can not introduce false negatives.
+pub struct CountingBloomFilter<T: ?Sized, S = StableHashBuilder> {
+    counters: Vec<u8>,
+    k: usize,
+    n: usize,
+    hash_builder: S,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> CountingBloomFilter<T> {
+    /// create a filter for `n` items with false positive rate `p`
+    pub fn new(n: usize, p: f64) -> CountingBloomFilter<T> {
+        CountingBloomFilter::with_rate_and_hasher(n, p, Default::default())
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> CountingBloomFilter<T, S> {
+    pub fn with_rate_and_hasher(n: usize, p: f64, hash_builder: S) -> CountingBloomFilter<T, S> {
+        let (m, k) = optimal_size(n, p);
+        CountingBloomFilter::with_size_and_hasher(m, k, hash_builder)
+    }
+
+    /// create a filter of `m` counters probed by `k` hash functions
+    pub fn with_size_and_hasher(m: usize, k: usize, hash_builder: S) -> CountingBloomFilter<T, S> {
+        assert!(m > 0 && k > 0, "a bloom filter needs counters and hash functions");
+        CountingBloomFilter {
+            counters: vec![0; m],
+            k,
+            n: 0,
+            hash_builder,
+            _marker: PhantomData,
+        }
+    }
+
+    fn indices(&self, item: &T) -> impl Iterator<Item = usize> {
+        let (h1, h2) = hash_pair(&self.hash_builder, item);
+        let m = self.counters.len() as u64;
+        (0..self.k as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+
+    pub fn insert(&mut self, item: &T) {
+        for i in self.indices(item) {
+            self.counters[i] = self.counters[i].saturating_add(1);
+        }
+        self.n += 1;
+    }
+
+    /// remove an item inserted before, returns false if it is surely absent
+    pub fn remove(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        for i in self.indices(item) {
+            if self.counters[i] != u8::MAX {
+                self.counters[i] -= 1;
+            }
+        }
+        self.n = self.n.saturating_sub(1);
+        true
+    }
+
+    /// is the item possibly in the set?
+    pub fn contains(&self, item: &T) -> bool {
+        self.indices(item).all(|i| self.counters[i] > 0)
+    }
+
+    /// number of insertions not removed
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn num_counters(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn num_hashes(&self) -> usize {
+        self.k
+    }
+
+    /// multiset union with a filter of the same size
+    pub fn merge(&mut self, other: &CountingBloomFilter<T, S>) -> Result<(), SketchError> {
+        if self.counters.len() != other.counters.len() || self.k != other.k {
+            return Err(SketchError::Incompatible);
+        }
+        for (c, o) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *c = c.saturating_add(*o);
+        }
+        self.n += other.n;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = b"ADCB".to_vec();
+        put_u64(&mut buf, self.counters.len() as u64);
+        put_u64(&mut buf, self.k as u64);
+        put_u64(&mut buf, self.n as u64);
+        buf.extend_from_slice(&self.counters);
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8], hash_builder: S) -> Result<CountingBloomFilter<T, S>, SketchError> {
+        let mut r = Reader::new(bytes, b"ADCB")?;
+        let (m, k, n) = (r.usize()?, r.usize()?, r.usize()?);
+        if m == 0 || k == 0 {
+            return Err(SketchError::Malformed);
+        }
+        let counters = r.finish(m)?.to_vec();
+        Ok(CountingBloomFilter {
+            counters,
+            k,
+            n,
+            hash_builder,
+            _marker: PhantomData,
+        })
+    }
+}
+
+#[test]
+fn test_bloom_filter() {
+    let mut filter = BloomFilter::new(1000, 0.01);
+    assert_eq!(filter.num_hashes(), 7);
+    assert!(filter.num_bits() > 9000 && filter.num_bits() < 10000);
+    for i in 0..1000 {
+        filter.insert(&i);
+    }
+    assert!((0..1000).all(|i| filter.contains(&i)));
+    let false_positives = (1000..11000).filter(|i| filter.contains(i)).count();
+    assert!(false_positives < 300, "{} false positives", false_positives);
+    assert!((filter.false_positive_rate() - 0.01).abs() < 0.005);
+
+    let mut other = BloomFilter::new(1000, 0.01);
+    other.insert(&-1);
+    filter.merge(&other).unwrap();
+    assert!(filter.contains(&-1));
+    assert_eq!(filter.merge(&BloomFilter::new(10, 0.01)), Err(SketchError::Incompatible));
+
+    let bytes = filter.to_bytes();
+    let decoded: BloomFilter<i32> = BloomFilter::from_bytes(&bytes, Default::default()).unwrap();
+    assert_eq!(decoded.size(), 1001);
+    assert!((-1..1000).all(|i| decoded.contains(&i)));
+    assert_eq!(decoded.to_bytes(), bytes);
+    assert!(BloomFilter::<i32>::from_bytes(&bytes[..bytes.len() - 1], Default::default()).is_err());
+
+    // a header claiming more bits than the payload holds is rejected, not allocated
+    for &m in [1u64 << 40, u64::MAX].iter() {
+        let mut forged = bytes.clone();
+        forged[4..12].copy_from_slice(&m.to_le_bytes());
+        let decoded = BloomFilter::<i32>::from_bytes(&forged, Default::default());
+        assert_eq!(decoded.err(), Some(SketchError::Malformed));
+    }
+    assert!(BloomFilter::<i32>::from_bytes(&bytes[..28], Default::default()).is_err());
+}
+
+#[test]
+fn test_counting_bloom_filter() {
+    let mut filter: CountingBloomFilter<str> = CountingBloomFilter::new(100, 0.001);
+    for w in "it was the best of times it was the worst of times".split(' ') {
+        filter.insert(w);
+    }
+    assert!(filter.contains("best") && filter.contains("worst"));
+    assert!(filter.remove("best"));
+    assert!(!filter.contains("best"));
+    assert!(!filter.remove("age"));
+    assert!(filter.remove("times"));
+    assert!(filter.contains("times"));
+    assert_eq!(filter.size(), 10);
+
+    let mut other = CountingBloomFilter::new(100, 0.001);
+    other.insert("best");
+    filter.merge(&other).unwrap();
+    let decoded: CountingBloomFilter<str> =
+        CountingBloomFilter::from_bytes(&filter.to_bytes(), Default::default()).unwrap();
+    assert!(decoded.contains("best") && decoded.contains("it"));
+    assert_eq!(decoded.size(), 11);
+}
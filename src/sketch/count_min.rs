@@ -0,0 +1,248 @@
+use super::{hash_pair, put_u64, Reader, SketchError, StableHashBuilder};
+use crate::hashst::HashST;
+use std::cmp::Reverse;
+use std::f64::consts::E;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+/// Frequency estimation of a stream in sublinear space.
+///
+/// With width `⌈e/ε⌉` and depth `⌈ln(1/δ)⌉`, an estimate never undercounts and
+/// overcounts by more than `ε` times the total count with probability at most `δ`.
+pub struct CountMinSketch<T: ?Sized, S = StableHashBuilder> {
+    width: usize,
+    depth: usize,
+    // depth rows of width counters
+    counts: Vec<u64>,
+    total: u64,
+    hash_builder: S,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> CountMinSketch<T> {
+    /// create a sketch with error `epsilon` and failure probability `delta`
+    pub fn new(epsilon: f64, delta: f64) -> CountMinSketch<T> {
+        CountMinSketch::with_error_and_hasher(epsilon, delta, Default::default())
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> CountMinSketch<T, S> {
+    pub fn with_error_and_hasher(epsilon: f64, delta: f64, hash_builder: S) -> CountMinSketch<T, S> {
+        assert!(epsilon > 0.0 && delta > 0.0 && delta < 1.0, "invalid error bounds");
+        let width = (E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil().max(1.0) as usize;
+        CountMinSketch::with_size_and_hasher(width, depth, hash_builder)
+    }
+
+    pub fn with_size_and_hasher(width: usize, depth: usize, hash_builder: S) -> CountMinSketch<T, S> {
+        assert!(width > 0 && depth > 0, "a count-min sketch needs counters");
+        CountMinSketch {
+            width,
+            depth,
+            counts: vec![0; width * depth],
+            total: 0,
+            hash_builder,
+            _marker: PhantomData,
+        }
+    }
+
+    // counter of the item in each row
+    fn cells(&self, item: &T) -> impl Iterator<Item = usize> {
+        let (h1, h2) = hash_pair(&self.hash_builder, item);
+        let w = self.width as u64;
+        (0..self.depth as u64).map(move |r| (r * w + h1.wrapping_add(r.wrapping_mul(h2)) % w) as usize)
+    }
+
+    /// add `count` occurrences of the item
+    pub fn add(&mut self, item: &T, count: u64) {
+        for c in self.cells(item) {
+            self.counts[c] = self.counts[c].saturating_add(count);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// estimated number of occurrences of the item, never less than the true count
+    pub fn estimate(&self, item: &T) -> u64 {
+        self.cells(item).map(|c| self.counts[c]).min().unwrap()
+    }
+
+    /// total count of all items added
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// candidates estimated to make up at least `phi` of the total, most frequent first
+    pub fn heavy_hitters<'a, I>(&self, candidates: I, phi: f64) -> Vec<(&'a T, u64)>
+    where
+        I: IntoIterator<Item = &'a T>,
+    {
+        let threshold = phi * self.total as f64;
+        let mut hitters = candidates
+            .into_iter()
+            .map(|item| (item, self.estimate(item)))
+            .filter(|&(_, est)| est as f64 >= threshold)
+            .collect::<Vec<_>>();
+        hitters.sort_by_key(|&(_, est)| Reverse(est));
+        hitters
+    }
+
+    /// add the counts of a sketch of the same size
+    pub fn merge(&mut self, other: &CountMinSketch<T, S>) -> Result<(), SketchError> {
+        if self.width != other.width || self.depth != other.depth {
+            return Err(SketchError::Incompatible);
+        }
+        for (c, o) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *c = c.saturating_add(*o);
+        }
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = b"ADCM".to_vec();
+        put_u64(&mut buf, self.width as u64);
+        put_u64(&mut buf, self.depth as u64);
+        put_u64(&mut buf, self.total);
+        for &c in self.counts.iter() {
+            put_u64(&mut buf, c);
+        }
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8], hash_builder: S) -> Result<CountMinSketch<T, S>, SketchError> {
+        let mut r = Reader::new(bytes, b"ADCM")?;
+        let (width, depth, total) = (r.usize()?, r.usize()?, r.u64()?);
+        let cells = width
+            .checked_mul(depth)
+            .filter(|&n| n > 0)
+            .ok_or(SketchError::Malformed)?;
+        let counts = r
+            .finish(cells.checked_mul(8).ok_or(SketchError::Malformed)?)?
+            .chunks(8)
+            .map(|chunk| {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(chunk);
+                u64::from_le_bytes(buf)
+            })
+            .collect();
+        Ok(CountMinSketch {
+            width,
+            depth,
+            counts,
+            total,
+            hash_builder,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Streaming heavy hitters: items making up at least `phi` of a stream,
+/// tracked with a Count-Min sketch and a table of candidates.
+pub struct HeavyHitters<T> {
+    sketch: CountMinSketch<T>,
+    candidates: HashST<T, u64>,
+    phi: f64,
+}
+
+impl<T: Hash + PartialEq + Clone> HeavyHitters<T> {
+    /// track items above `phi` of the stream, with sketch error `epsilon`
+    pub fn new(phi: f64, epsilon: f64) -> HeavyHitters<T> {
+        assert!(phi > 0.0 && phi < 1.0, "phi must be in (0, 1)");
+        HeavyHitters {
+            sketch: CountMinSketch::new(epsilon, 0.01),
+            candidates: HashST::new(),
+            phi,
+        }
+    }
+
+    fn threshold(&self) -> f64 {
+        self.phi * self.sketch.total() as f64
+    }
+
+    pub fn add(&mut self, item: T) {
+        self.sketch.add(&item, 1);
+        let est = self.sketch.estimate(&item);
+        if est as f64 >= self.threshold() {
+            self.candidates.put(item, est);
+        }
+        // at most 1/phi items can be above the threshold
+        if self.candidates.size() as f64 > 2.0 / self.phi {
+            let threshold = self.threshold();
+            let fallen = self
+                .candidates
+                .iter()
+                .filter(|&(_, &est)| (est as f64) < threshold)
+                .map(|(k, _)| k.clone())
+                .collect::<Vec<_>>();
+            for k in fallen {
+                self.candidates.delete(&k);
+            }
+        }
+    }
+
+    /// the sketch of the whole stream
+    pub fn sketch(&self) -> &CountMinSketch<T> {
+        &self.sketch
+    }
+
+    /// current heavy hitters with estimated counts, most frequent first
+    pub fn top(&self) -> Vec<(T, u64)> {
+        self.sketch
+            .heavy_hitters(self.candidates.keys(), self.phi)
+            .into_iter()
+            .map(|(k, est)| (k.clone(), est))
+            .collect()
+    }
+}
+
+#[test]
+fn test_count_min_sketch() {
+    let mut sketch = CountMinSketch::new(0.001, 0.01);
+    assert_eq!((sketch.width(), sketch.depth()), (2719, 5));
+    for i in 0..10_000u32 {
+        sketch.add(&(i % 100), 1);
+    }
+    sketch.add(&7, 1000);
+    assert_eq!(sketch.total(), 11_000);
+    assert!(sketch.estimate(&7) >= 1100);
+    assert!(sketch.estimate(&7) <= 1100 + 11);
+    assert!(sketch.estimate(&1000) <= 11);
+
+    let candidates = (0..200).collect::<Vec<u32>>();
+    let hitters = sketch.heavy_hitters(&candidates, 0.05);
+    assert_eq!(hitters.len(), 1);
+    assert_eq!(*hitters[0].0, 7);
+
+    let mut other = CountMinSketch::new(0.001, 0.01);
+    other.add(&7, 1);
+    sketch.merge(&other).unwrap();
+    let decoded: CountMinSketch<u32> = CountMinSketch::from_bytes(&sketch.to_bytes(), Default::default()).unwrap();
+    assert_eq!(decoded.total(), 11_001);
+    assert_eq!(decoded.estimate(&7), sketch.estimate(&7));
+    assert_eq!(sketch.merge(&CountMinSketch::new(0.1, 0.01)), Err(SketchError::Incompatible));
+}
+
+#[test]
+fn test_heavy_hitters() {
+    let mut hh = HeavyHitters::new(0.1, 0.001);
+    for i in 0..10_000u32 {
+        // 0 is a fifth of the stream, 1 a tenth and more, the rest is noise
+        let item = match i % 20 {
+            0..=3 => 0,
+            4..=6 => 1,
+            _ => 100 + i,
+        };
+        hh.add(item);
+    }
+    let top = hh.top();
+    assert_eq!(top.iter().map(|&(k, _)| k).collect::<Vec<_>>(), vec![0, 1]);
+    assert!(top[0].1 >= 2000 && top[1].1 >= 1500);
+}
@@ -0,0 +1,307 @@
+use super::{put_u64, Reader, SketchError, StableHashBuilder};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+const BUCKET_SIZE: usize = 4;
+const MAX_KICKS: usize = 500;
+// an empty slot
+const EMPTY: u16 = 0;
+
+/// Approximate set membership with removal, storing 16-bit fingerprints in a
+/// cuckoo hash table.
+///
+/// The false positive rate is about `8 / 2^16`. An item must only be removed
+/// if it was inserted, or another item sharing its fingerprint may be lost.
+pub struct CuckooFilter<T: ?Sized, S = StableHashBuilder> {
+    // the number of buckets is a power of two
+    buckets: Vec<[u16; BUCKET_SIZE]>,
+    // a fingerprint evicted by an insertion that ran out of kicks
+    victim: Option<(usize, u16)>,
+    n: usize,
+    hash_builder: S,
+    rng: StdRng,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> CuckooFilter<T> {
+    /// create a filter for about `capacity` items
+    pub fn new(capacity: usize) -> CuckooFilter<T> {
+        CuckooFilter::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> CuckooFilter<T, S> {
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> CuckooFilter<T, S> {
+        // buckets fill up to about 95%
+        let buckets = (capacity * 100 / 95 / BUCKET_SIZE + 1).next_power_of_two().max(2);
+        CuckooFilter::with_buckets(buckets, hash_builder)
+    }
+
+    fn with_buckets(buckets: usize, hash_builder: S) -> CuckooFilter<T, S> {
+        CuckooFilter {
+            buckets: vec![[EMPTY; BUCKET_SIZE]; buckets],
+            victim: None,
+            n: 0,
+            hash_builder,
+            rng: StdRng::seed_from_u64(0x5eed),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn mask(&self) -> usize {
+        self.buckets.len() - 1
+    }
+
+    // first bucket and fingerprint of an item
+    fn locate(&self, item: &T) -> (usize, u16) {
+        let h = self.hash_builder.hash_one(item);
+        let fp = (h >> 48) as u16;
+        (h as usize & self.mask(), if fp == EMPTY { 1 } else { fp })
+    }
+
+    // the other bucket of a fingerprint, an involution
+    #[inline]
+    fn alt(&self, i: usize, fp: u16) -> usize {
+        (i ^ (fp as u64).wrapping_mul(0x5bd1_e995) as usize) & self.mask()
+    }
+
+    fn put_in(&mut self, i: usize, fp: u16) -> bool {
+        match self.buckets[i].iter_mut().find(|s| **s == EMPTY) {
+            Some(slot) => {
+                *slot = fp;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert_fingerprint(&mut self, i: usize, fp: u16) -> Result<(), SketchError> {
+        if self.victim.is_some() {
+            return Err(SketchError::Full);
+        }
+        let j = self.alt(i, fp);
+        if self.put_in(i, fp) || self.put_in(j, fp) {
+            self.n += 1;
+            return Ok(());
+        }
+        let (mut i, mut fp) = if self.rng.gen() { (i, fp) } else { (j, fp) };
+        for _ in 0..MAX_KICKS {
+            let slot = self.rng.gen_range(0, BUCKET_SIZE);
+            std::mem::swap(&mut fp, &mut self.buckets[i][slot]);
+            i = self.alt(i, fp);
+            if self.put_in(i, fp) {
+                self.n += 1;
+                return Ok(());
+            }
+        }
+        // keep the evicted fingerprint, the filter is full from now on
+        self.victim = Some((i, fp));
+        self.n += 1;
+        Ok(())
+    }
+
+    /// insert an item, failing if the filter is full
+    pub fn insert(&mut self, item: &T) -> Result<(), SketchError> {
+        let (i, fp) = self.locate(item);
+        self.insert_fingerprint(i, fp)
+    }
+
+    /// is the item possibly in the set?
+    pub fn contains(&self, item: &T) -> bool {
+        let (i, fp) = self.locate(item);
+        let j = self.alt(i, fp);
+        self.buckets[i].contains(&fp)
+            || self.buckets[j].contains(&fp)
+            || self.victim.is_some_and(|(v, vfp)| vfp == fp && (v == i || v == j))
+    }
+
+    /// remove an inserted item, returns false if it is surely absent
+    pub fn remove(&mut self, item: &T) -> bool {
+        let (i, fp) = self.locate(item);
+        let j = self.alt(i, fp);
+        if self.victim.is_some_and(|(v, vfp)| vfp == fp && (v == i || v == j)) {
+            self.victim = None;
+            self.n -= 1;
+            return true;
+        }
+        for &b in [i, j].iter() {
+            if let Some(slot) = self.buckets[b].iter_mut().find(|s| **s == fp) {
+                *slot = EMPTY;
+                self.n -= 1;
+                // there is room for the victim again
+                if let Some((v, vfp)) = self.victim.take() {
+                    self.n -= 1;
+                    self.insert_fingerprint(v, vfp).unwrap();
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// number of items in the filter
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// number of fingerprint slots
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    pub fn load_factor(&self) -> f64 {
+        self.n as f64 / self.capacity() as f64
+    }
+
+    /// insert all items of a filter of the same size. If they do not fit,
+    /// the filter is left unchanged.
+    pub fn merge(&mut self, other: &CuckooFilter<T, S>) -> Result<(), SketchError> {
+        if self.buckets.len() != other.buckets.len() {
+            return Err(SketchError::Incompatible);
+        }
+        if self.n + other.n > self.capacity() {
+            return Err(SketchError::Full);
+        }
+        // even then the kicks may run out, insert into a copy and keep it on success
+        let saved = (self.buckets.clone(), self.victim, self.n, self.rng.clone());
+        let result = other
+            .buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(i, bucket)| bucket.iter().filter(|&&fp| fp != EMPTY).map(move |&fp| (i, fp)))
+            .chain(other.victim)
+            .try_for_each(|(i, fp)| self.insert_fingerprint(i, fp));
+        if result.is_err() {
+            let (buckets, victim, n, rng) = saved;
+            self.buckets = buckets;
+            self.victim = victim;
+            self.n = n;
+            self.rng = rng;
+        }
+        result
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = b"ADCF".to_vec();
+        put_u64(&mut buf, self.buckets.len() as u64);
+        put_u64(&mut buf, self.n as u64);
+        match self.victim {
+            Some((i, fp)) => {
+                put_u64(&mut buf, 1 + i as u64);
+                buf.extend_from_slice(&fp.to_le_bytes());
+            }
+            None => {
+                put_u64(&mut buf, 0);
+                buf.extend_from_slice(&[0, 0]);
+            }
+        }
+        for &fp in self.buckets.iter().flat_map(|b| b.iter()) {
+            buf.extend_from_slice(&fp.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8], hash_builder: S) -> Result<CuckooFilter<T, S>, SketchError> {
+        let mut r = Reader::new(bytes, b"ADCF")?;
+        let (buckets, n, victim) = (r.usize()?, r.usize()?, r.usize()?);
+        if !buckets.is_power_of_two() || victim > buckets {
+            return Err(SketchError::Malformed);
+        }
+        let vfp = r.take(2)?;
+        let vfp = u16::from_le_bytes([vfp[0], vfp[1]]);
+        let mut filter = CuckooFilter::with_buckets(buckets, hash_builder);
+        let fps = r.finish(buckets.checked_mul(2 * BUCKET_SIZE).ok_or(SketchError::Malformed)?)?;
+        for (slot, chunk) in filter.buckets.iter_mut().flat_map(|b| b.iter_mut()).zip(fps.chunks(2)) {
+            *slot = u16::from_le_bytes([chunk[0], chunk[1]]);
+        }
+        if victim > 0 {
+            filter.victim = Some((victim - 1, vfp));
+        }
+        filter.n = n;
+        Ok(filter)
+    }
+}
+
+#[test]
+fn test_cuckoo_filter() {
+    let mut filter = CuckooFilter::new(10_000);
+    for i in 0..10_000 {
+        filter.insert(&i).unwrap();
+    }
+    assert_eq!(filter.size(), 10_000);
+    assert!((0..10_000).all(|i| filter.contains(&i)));
+    let false_positives = (10_000..110_000).filter(|i| filter.contains(i)).count();
+    assert!(false_positives < 100, "{} false positives", false_positives);
+
+    for i in (0..10_000).step_by(2) {
+        assert!(filter.remove(&i));
+    }
+    assert_eq!(filter.size(), 5_000);
+    assert!((1..10_000).step_by(2).all(|i| filter.contains(&i)));
+    assert!((0..10_000).step_by(2).filter(|i| filter.contains(i)).count() < 10);
+
+    let mut other = CuckooFilter::new(10_000);
+    other.insert(&-1).unwrap();
+    filter.merge(&other).unwrap();
+    let decoded: CuckooFilter<i32> = CuckooFilter::from_bytes(&filter.to_bytes(), Default::default()).unwrap();
+    assert_eq!(decoded.size(), 5_001);
+    assert!(decoded.contains(&-1) && decoded.contains(&9_999));
+    assert_eq!(filter.merge(&CuckooFilter::new(10)), Err(SketchError::Incompatible));
+}
+
+#[test]
+fn test_cuckoo_filter_full() {
+    let mut filter = CuckooFilter::new(100);
+    let inserted = (0..1000).take_while(|i| filter.insert(i).is_ok()).count();
+    assert!(inserted as f64 >= 0.9 * filter.capacity() as f64);
+    assert!(inserted <= filter.capacity() + 1);
+    assert!((0..inserted as i32).all(|i| filter.contains(&i)));
+    assert_eq!(filter.insert(&-1), Err(SketchError::Full));
+    assert!(filter.remove(&0));
+    assert!(filter.insert(&-1).is_ok());
+}
+
+#[test]
+fn test_cuckoo_filter_failed_merge() {
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // every item gets the same fingerprint and buckets, 8 slots in all
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn write(&mut self, _: &[u8]) {}
+
+        fn finish(&self) -> u64 {
+            0xabcd << 48
+        }
+    }
+
+    let mut filter = CuckooFilter::with_capacity_and_hasher(100, BuildHasherDefault::<Collide>::default());
+    let mut other = CuckooFilter::with_capacity_and_hasher(100, BuildHasherDefault::<Collide>::default());
+    for i in 0..5 {
+        filter.insert(&i).unwrap();
+        other.insert(&i).unwrap();
+    }
+    let before = filter.to_bytes();
+    assert_eq!(filter.merge(&other), Err(SketchError::Full));
+    assert_eq!(filter.to_bytes(), before);
+    assert!(filter.insert(&5).is_ok());
+    assert_eq!(filter.size(), 6);
+
+    let mut big = CuckooFilter::new(100);
+    let more = (0..100).map(|i| big.insert(&i)).filter(Result::is_ok).count();
+    let mut small = CuckooFilter::new(100);
+    for i in 0..(small.capacity() - more + 1) {
+        small.insert(&(1000 + i)).unwrap();
+    }
+    assert_eq!(big.merge(&small), Err(SketchError::Full));
+    assert_eq!(big.size(), more);
+}
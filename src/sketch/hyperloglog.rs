@@ -0,0 +1,258 @@
+use super::{put_u64, Reader, SketchError, StableHashBuilder};
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 18;
+
+// a sparse entry packs a register index above its 6-bit rank
+#[inline]
+fn pack(idx: usize, rank: u8) -> u32 {
+    (idx as u32) << 6 | rank as u32
+}
+
+#[inline]
+fn unpack(entry: u32) -> (usize, u8) {
+    ((entry >> 6) as usize, (entry & 0x3f) as u8)
+}
+
+enum Registers {
+    // sorted by index, one entry per nonzero register
+    Sparse(Vec<u32>),
+    Dense(Vec<u8>),
+}
+
+/// Distinct count estimation over a stream with `2^p` small registers, with a
+/// standard error of about `1.04 / sqrt(2^p)`.
+///
+/// Small cardinalities are kept in a sparse list of nonzero registers, which
+/// turns dense once it would take more memory than the registers themselves.
+pub struct HyperLogLog<T: ?Sized, S = StableHashBuilder> {
+    p: u8,
+    registers: Registers,
+    hash_builder: S,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> HyperLogLog<T> {
+    /// create an estimator with `2^p` registers
+    pub fn new(p: u8) -> HyperLogLog<T> {
+        HyperLogLog::with_precision_and_hasher(p, Default::default())
+    }
+}
+
+impl<T: Hash + ?Sized, S: BuildHasher> HyperLogLog<T, S> {
+    pub fn with_precision_and_hasher(p: u8, hash_builder: S) -> HyperLogLog<T, S> {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&p),
+            "precision must be between {} and {}",
+            MIN_PRECISION,
+            MAX_PRECISION
+        );
+        HyperLogLog {
+            p,
+            registers: Registers::Sparse(Vec::new()),
+            hash_builder,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn m(&self) -> usize {
+        1 << self.p
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.p
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.registers, Registers::Sparse(_))
+    }
+
+    pub fn insert(&mut self, item: &T) {
+        let h = self.hash_builder.hash_one(item);
+        let idx = (h >> (64 - self.p)) as usize;
+        // position of the first 1 bit after the index bits
+        let rank = ((h << self.p) | (1 << (self.p - 1))).leading_zeros() as u8 + 1;
+        self.update(idx, rank);
+    }
+
+    fn update(&mut self, idx: usize, rank: u8) {
+        let m = self.m();
+        match self.registers {
+            Registers::Dense(ref mut regs) => regs[idx] = regs[idx].max(rank),
+            Registers::Sparse(ref mut entries) => {
+                match entries.binary_search_by_key(&idx, |&e| unpack(e).0) {
+                    Ok(i) => entries[i] = entries[i].max(pack(idx, rank)),
+                    Err(i) => entries.insert(i, pack(idx, rank)),
+                }
+                // 4 bytes per entry against 1 byte per register
+                if entries.len() > m / 4 {
+                    self.densify();
+                }
+            }
+        }
+    }
+
+    fn densify(&mut self) {
+        if let Registers::Sparse(ref entries) = self.registers {
+            let mut regs = vec![0; self.m()];
+            for &e in entries.iter() {
+                let (idx, rank) = unpack(e);
+                regs[idx] = rank;
+            }
+            self.registers = Registers::Dense(regs);
+        }
+    }
+
+    /// estimated number of distinct items
+    pub fn estimate(&self) -> f64 {
+        let m = self.m() as f64;
+        let (zeros, sum) = match self.registers {
+            Registers::Sparse(ref entries) => {
+                let sum = entries.iter().map(|&e| 2f64.powi(-(unpack(e).1 as i32))).sum::<f64>();
+                (self.m() - entries.len(), sum + (self.m() - entries.len()) as f64)
+            }
+            Registers::Dense(ref regs) => {
+                (regs.iter().filter(|&&r| r == 0).count(), regs.iter().map(|&r| 2f64.powi(-(r as i32))).sum())
+            }
+        };
+        let alpha = match self.p {
+            4 => 0.673,
+            5 => 0.697,
+            6 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let raw = alpha * m * m / sum;
+        if raw <= 2.5 * m && zeros > 0 {
+            // linear counting is more accurate for small cardinalities
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    /// estimated number of distinct items, rounded
+    pub fn count(&self) -> u64 {
+        self.estimate().round() as u64
+    }
+
+    pub fn clear(&mut self) {
+        self.registers = Registers::Sparse(Vec::new());
+    }
+
+    /// union with an estimator of the same precision
+    pub fn merge(&mut self, other: &HyperLogLog<T, S>) -> Result<(), SketchError> {
+        if self.p != other.p {
+            return Err(SketchError::Incompatible);
+        }
+        match other.registers {
+            Registers::Sparse(ref entries) => {
+                for &e in entries.iter() {
+                    let (idx, rank) = unpack(e);
+                    self.update(idx, rank);
+                }
+            }
+            Registers::Dense(ref other_regs) => {
+                self.densify();
+                if let Registers::Dense(ref mut regs) = self.registers {
+                    for (r, &o) in regs.iter_mut().zip(other_regs.iter()) {
+                        *r = (*r).max(o);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = b"ADHL".to_vec();
+        buf.push(self.p);
+        match self.registers {
+            Registers::Sparse(ref entries) => {
+                buf.push(0);
+                put_u64(&mut buf, entries.len() as u64);
+                for &e in entries.iter() {
+                    buf.extend_from_slice(&e.to_le_bytes());
+                }
+            }
+            Registers::Dense(ref regs) => {
+                buf.push(1);
+                buf.extend_from_slice(regs);
+            }
+        }
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8], hash_builder: S) -> Result<HyperLogLog<T, S>, SketchError> {
+        let mut r = Reader::new(bytes, b"ADHL")?;
+        let header = r.take(2)?;
+        let (p, dense) = (header[0], header[1]);
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&p) || dense > 1 {
+            return Err(SketchError::Malformed);
+        }
+        let mut hll = HyperLogLog::with_precision_and_hasher(p, hash_builder);
+        let max_rank = 64 - p + 1;
+        if dense == 1 {
+            let regs = r.finish(hll.m())?;
+            if regs.iter().any(|&rank| rank > max_rank) {
+                return Err(SketchError::Malformed);
+            }
+            hll.registers = Registers::Dense(regs.to_vec());
+        } else {
+            let n = r.usize()?;
+            let raw = r.finish(n.checked_mul(4).ok_or(SketchError::Malformed)?)?;
+            let entries = raw
+                .chunks(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect::<Vec<_>>();
+            let valid = entries
+                .iter()
+                .all(|&e| unpack(e).0 < hll.m() && unpack(e).1 <= max_rank)
+                && entries.windows(2).all(|w| unpack(w[0]).0 < unpack(w[1]).0);
+            if !valid {
+                return Err(SketchError::Malformed);
+            }
+            hll.registers = Registers::Sparse(entries);
+        }
+        Ok(hll)
+    }
+}
+
+#[test]
+fn test_hyperloglog() {
+    let mut hll = HyperLogLog::new(12);
+    for i in 0..500u32 {
+        hll.insert(&i);
+        hll.insert(&i);
+    }
+    assert!(hll.is_sparse());
+    assert!((hll.count() as i64 - 500).abs() < 15);
+
+    for i in 500..100_000u32 {
+        hll.insert(&i);
+    }
+    assert!(!hll.is_sparse());
+    let error = (hll.estimate() - 100_000.0).abs() / 100_000.0;
+    assert!(error < 0.05, "relative error {}", error);
+
+    // overlapping halves merge into the union
+    let mut other = HyperLogLog::new(12);
+    for i in 50_000..150_000u32 {
+        other.insert(&i);
+    }
+    let mut small = HyperLogLog::new(12);
+    small.insert(&1_000_000);
+    hll.merge(&other).unwrap();
+    hll.merge(&small).unwrap();
+    let error = (hll.estimate() - 150_001.0).abs() / 150_001.0;
+    assert!(error < 0.05, "relative error {}", error);
+    assert_eq!(hll.merge(&HyperLogLog::new(10)), Err(SketchError::Incompatible));
+
+    for h in [hll, small].iter() {
+        let decoded: HyperLogLog<u32> = HyperLogLog::from_bytes(&h.to_bytes(), Default::default()).unwrap();
+        assert_eq!(decoded.is_sparse(), h.is_sparse());
+        assert_eq!(decoded.count(), h.count());
+    }
+}
@@ -0,0 +1,99 @@
+use std::error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+pub mod bloom;
+pub mod count_min;
+pub mod cuckoo_filter;
+pub mod hyperloglog;
+pub mod reservoir;
+pub mod stable_hash;
+
+// reexports
+pub use self::bloom::{BloomFilter, CountingBloomFilter};
+pub use self::count_min::{CountMinSketch, HeavyHitters};
+pub use self::cuckoo_filter::CuckooFilter;
+pub use self::hyperloglog::HyperLogLog;
+pub use self::reservoir::{Reservoir, WeightedReservoir};
+pub use self::stable_hash::{StableHashBuilder, StableHasher};
+
+/// Errors reported when merging or decoding sketches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SketchError {
+    /// the sketches were built with different parameters
+    Incompatible,
+    /// the bytes do not encode this kind of sketch
+    Malformed,
+    /// there is no room left for an item
+    Full,
+}
+
+impl fmt::Display for SketchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SketchError::Incompatible => write!(f, "sketches have different parameters"),
+            SketchError::Malformed => write!(f, "malformed sketch bytes"),
+            SketchError::Full => write!(f, "sketch is full"),
+        }
+    }
+}
+
+impl error::Error for SketchError {}
+
+// two independent hashes of an item, for double hashing
+fn hash_pair<T: Hash + ?Sized, S: BuildHasher>(hash_builder: &S, item: &T) -> (u64, u64) {
+    let h1 = hash_builder.hash_one(item);
+    let h2 = hash_builder.hash_one((0x9e37_79b9_7f4a_7c15_u64, item));
+    // an odd step is never zero, so the k probes do not all hit the slot of h1.
+    // They are distinct when the step is coprime to the table size.
+    (h1, h2 | 1)
+}
+
+fn put_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+// little-endian reader over encoded sketches
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], magic: &[u8; 4]) -> Result<Reader<'a>, SketchError> {
+        if bytes.len() < 4 || &bytes[..4] != magic {
+            return Err(SketchError::Malformed);
+        }
+        Ok(Reader { bytes: &bytes[4..] })
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SketchError> {
+        if self.bytes.len() < n {
+            return Err(SketchError::Malformed);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u64(&mut self) -> Result<u64, SketchError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn usize(&mut self) -> Result<usize, SketchError> {
+        let v = self.u64()?;
+        if v > usize::MAX as u64 {
+            return Err(SketchError::Malformed);
+        }
+        Ok(v as usize)
+    }
+
+    // the rest must be exactly `n` bytes
+    fn finish(self, n: usize) -> Result<&'a [u8], SketchError> {
+        if self.bytes.len() != n {
+            return Err(SketchError::Malformed);
+        }
+        Ok(self.bytes)
+    }
+}
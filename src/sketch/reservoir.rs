@@ -0,0 +1,190 @@
+use crate::priority_queue::BinaryHeapPQ;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+
+/// Uniform sample of `k` items from a stream of unknown length (Algorithm R).
+///
+/// Every item seen so far is in the sample with probability `k / seen`.
+/// Seed the generator with `with_rng` for reproducible samples.
+pub struct Reservoir<T, R = StdRng> {
+    k: usize,
+    seen: u64,
+    items: Vec<T>,
+    rng: R,
+}
+
+impl<T> Reservoir<T> {
+    /// create a reservoir of `k` items with an entropy-seeded generator
+    pub fn new(k: usize) -> Reservoir<T> {
+        Reservoir::with_rng(k, StdRng::from_entropy())
+    }
+}
+
+impl<T, R: Rng> Reservoir<T, R> {
+    pub fn with_rng(k: usize, rng: R) -> Reservoir<T, R> {
+        Reservoir {
+            k,
+            seen: 0,
+            items: Vec::with_capacity(k),
+            rng,
+        }
+    }
+
+    /// offer an item of the stream to the sample
+    pub fn sample(&mut self, item: T) {
+        self.seen += 1;
+        if self.items.len() < self.k {
+            self.items.push(item);
+        } else {
+            let j = self.rng.gen_range(0, self.seen);
+            if j < self.k as u64 {
+                self.items[j as usize] = item;
+            }
+        }
+    }
+
+    /// number of items offered so far
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// the sample, in no particular order
+    pub fn samples(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T, R: Rng> Extend<T> for Reservoir<T, R> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.sample(item);
+        }
+    }
+}
+
+type KeyOrder<T> = fn(&(f64, T), &(f64, T)) -> Ordering;
+
+/// Weighted sample of `k` items without replacement (A-Res, Efraimidis and Spirakis).
+///
+/// Each item gets the key `u^(1/w)` for a uniform `u` and its weight `w`, and
+/// the `k` items with the largest keys are kept in a min-heap.
+pub struct WeightedReservoir<T, R = StdRng> {
+    k: usize,
+    seen: u64,
+    // keys are kept as ln(u) / w, which orders the same and does not underflow
+    heap: BinaryHeapPQ<(f64, T), KeyOrder<T>>,
+    rng: R,
+}
+
+impl<T> WeightedReservoir<T> {
+    /// create a reservoir of `k` items with an entropy-seeded generator
+    pub fn new(k: usize) -> WeightedReservoir<T> {
+        WeightedReservoir::with_rng(k, StdRng::from_entropy())
+    }
+}
+
+impl<T, R: Rng> WeightedReservoir<T, R> {
+    pub fn with_rng(k: usize, rng: R) -> WeightedReservoir<T, R> {
+        let smallest_key_first: KeyOrder<T> = |a, b| a.0.total_cmp(&b.0);
+        WeightedReservoir {
+            k,
+            seen: 0,
+            heap: BinaryHeapPQ::with_comparator(smallest_key_first),
+            rng,
+        }
+    }
+
+    /// offer an item of the stream with a positive weight
+    pub fn sample(&mut self, item: T, weight: f64) {
+        assert!(weight > 0.0 && weight.is_finite(), "weight must be positive");
+        self.seen += 1;
+        if self.k == 0 {
+            return;
+        }
+        // u in (0, 1]
+        let u = 1.0 - self.rng.gen::<f64>();
+        let key = u.ln() / weight;
+        if self.heap.size() < self.k {
+            self.heap.insert((key, item));
+        } else if let Some(mut top) = self.heap.peek_mut() {
+            if key > top.0 {
+                *top = (key, item);
+            }
+        }
+    }
+
+    /// number of items offered so far
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// the sample, in no particular order
+    pub fn samples(&self) -> Vec<&T> {
+        self.heap.iter().map(|(_, item)| item).collect()
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap.into_vec().into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+impl<T, R: Rng> Extend<(T, f64)> for WeightedReservoir<T, R> {
+    fn extend<I: IntoIterator<Item = (T, f64)>>(&mut self, iter: I) {
+        for (item, weight) in iter {
+            self.sample(item, weight);
+        }
+    }
+}
+
+#[test]
+fn test_reservoir() {
+    let mut hits = [0; 10];
+    for seed in 0..2000 {
+        let mut reservoir = Reservoir::with_rng(3, StdRng::seed_from_u64(seed));
+        reservoir.extend(0..10);
+        assert_eq!(reservoir.seen(), 10);
+        assert_eq!(reservoir.samples().len(), 3);
+        for &i in reservoir.samples() {
+            hits[i] += 1;
+        }
+    }
+    // each item is sampled 600 times in expectation
+    assert!(hits.iter().all(|&h| h > 500 && h < 700), "{:?}", hits);
+
+    let mut a = Reservoir::with_rng(5, StdRng::seed_from_u64(42));
+    let mut b = Reservoir::with_rng(5, StdRng::seed_from_u64(42));
+    a.extend(0..1000);
+    b.extend(0..1000);
+    assert_eq!(a.into_vec(), b.into_vec());
+}
+
+#[test]
+fn test_weighted_reservoir() {
+    let mut heavy = 0;
+    let mut light = 0;
+    for seed in 0..1000 {
+        let mut reservoir = WeightedReservoir::with_rng(2, StdRng::seed_from_u64(seed));
+        reservoir.extend((0..20).map(|i| (i, if i == 7 { 100.0 } else { 1.0 })));
+        let samples = reservoir.into_vec();
+        assert_eq!(samples.len(), 2);
+        assert_ne!(samples[0], samples[1]);
+        heavy += samples.iter().filter(|&&i| i == 7).count();
+        light += samples.iter().filter(|&&i| i == 3).count();
+    }
+    // the heavy item is missed with probability about 0.16 * 0.15
+    assert!(heavy > 950, "heavy item sampled {} times", heavy);
+    assert!(light < 100, "light item sampled {} times", light);
+}
@@ -0,0 +1,84 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hasher the sketches use unless another `BuildHasher` is given.
+///
+/// It runs 64-bit FNV-1a over the bytes written and finishes with the SplitMix64 mixer.
+/// Integers are written little-endian, and `usize`/`isize` as 64 bits. Unlike std's
+/// `DefaultHasher`, whose algorithm may change between Rust releases, the output only
+/// depends on the items' `Hash` implementations. A sketch saved with `to_bytes` reads
+/// back the same with `from_bytes` on any platform and toolchain.
+#[derive(Clone, Copy, Debug)]
+pub struct StableHasher {
+    state: u64,
+}
+
+/// `BuildHasher` of `StableHasher`, the default of the sketches.
+pub type StableHashBuilder = BuildHasherDefault<StableHasher>;
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state = (self.state ^ b as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        // FNV alone mixes the last bytes poorly into the high bits
+        let mut z = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[test]
+fn test_stable_hasher() {
+    use std::hash::{BuildHasher, Hash};
+
+    let hash = |item: &dyn Fn(&mut StableHasher)| {
+        let mut h = StableHasher::default();
+        item(&mut h);
+        h.finish()
+    };
+    // reference values, any change breaks the sketches saved so far
+    assert_eq!(hash(&|_| ()), 0xc381_7c01_6ba4_ff30);
+    assert_eq!(hash(&|h| h.write(b"a")), 0x5f29_c2aa_dd9b_8527);
+    assert_eq!(hash(&|h| 1u32.hash(h)), hash(&|h| h.write(&[1, 0, 0, 0])));
+    assert_eq!(hash(&|h| 1usize.hash(h)), hash(&|h| 1u64.hash(h)));
+    assert_eq!(hash(&|h| (-1i16).hash(h)), hash(&|h| h.write(&[0xff, 0xff])));
+
+    let builder = StableHashBuilder::default();
+    assert_eq!(builder.hash_one("a"), hash(&|h| "a".hash(h)));
+    assert_ne!(builder.hash_one(1u64), builder.hash_one(2u64));
+}
@@ -0,0 +1,634 @@
+use super::{rotate_left, rotate_right, Rotate};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+/// An associative operation with an identity, aggregated over ranges.
+pub trait Monoid {
+    type Item: Clone;
+
+    fn identity() -> Self::Item;
+
+    fn combine(a: &Self::Item, b: &Self::Item) -> Self::Item;
+}
+
+/// A monoid with tags that update whole ranges lazily.
+///
+/// A tag is applied to the aggregate of a range in one step and only pushed
+/// down to the elements when a splay passes through. `apply` is also used on
+/// the aggregate of a range read backwards, which reversing a range needs.
+pub trait LazyMonoid: Monoid {
+    type Tag: Clone;
+
+    /// aggregate of a range of `len` elements after the tag is applied
+    fn apply(tag: &Self::Tag, x: &Self::Item, len: usize) -> Self::Item;
+
+    /// the tag doing `inner` then `outer`
+    fn compose(outer: &Self::Tag, inner: &Self::Tag) -> Self::Tag;
+}
+
+/// Range sums with range add.
+pub struct RangeSum;
+
+impl Monoid for RangeSum {
+    type Item = i64;
+
+    fn identity() -> i64 {
+        0
+    }
+
+    fn combine(a: &i64, b: &i64) -> i64 {
+        a + b
+    }
+}
+
+impl LazyMonoid for RangeSum {
+    type Tag = i64;
+
+    fn apply(tag: &i64, x: &i64, len: usize) -> i64 {
+        x + tag * len as i64
+    }
+
+    fn compose(outer: &i64, inner: &i64) -> i64 {
+        outer + inner
+    }
+}
+
+/// Range minimums with range add.
+pub struct RangeMin;
+
+impl Monoid for RangeMin {
+    type Item = i64;
+
+    fn identity() -> i64 {
+        i64::MAX
+    }
+
+    fn combine(a: &i64, b: &i64) -> i64 {
+        *a.min(b)
+    }
+}
+
+impl LazyMonoid for RangeMin {
+    type Tag = i64;
+
+    fn apply(tag: &i64, x: &i64, _: usize) -> i64 {
+        x + tag
+    }
+
+    fn compose(outer: &i64, inner: &i64) -> i64 {
+        outer + inner
+    }
+}
+
+/// Range maximums with range add.
+pub struct RangeMax;
+
+impl Monoid for RangeMax {
+    type Item = i64;
+
+    fn identity() -> i64 {
+        i64::MIN
+    }
+
+    fn combine(a: &i64, b: &i64) -> i64 {
+        *a.max(b)
+    }
+}
+
+impl LazyMonoid for RangeMax {
+    type Tag = i64;
+
+    fn apply(tag: &i64, x: &i64, _: usize) -> i64 {
+        x + tag
+    }
+
+    fn compose(outer: &i64, inner: &i64) -> i64 {
+        outer + inner
+    }
+}
+
+type NodeCell<M> = Option<Box<Node<M>>>;
+
+struct Node<M: LazyMonoid> {
+    val: M::Item,
+    // aggregate of the subtree, tag and reversal already included
+    agg: M::Item,
+    // the same, over the subtree read backwards
+    rev_agg: M::Item,
+    // pending for the children
+    tag: Option<M::Tag>,
+    rev: bool,
+    // number of nodes in the subtree
+    n: usize,
+    left: NodeCell<M>,
+    right: NodeCell<M>,
+}
+
+#[inline]
+fn size<M: LazyMonoid>(x: &NodeCell<M>) -> usize {
+    x.as_ref().map_or(0, |n| n.n)
+}
+
+impl<M: LazyMonoid> Rotate for Node<M> {
+    fn left(&mut self) -> &mut NodeCell<M> {
+        &mut self.left
+    }
+
+    fn right(&mut self) -> &mut NodeCell<M> {
+        &mut self.right
+    }
+
+    fn pull(&mut self) {
+        self.n = 1 + size(&self.left) + size(&self.right);
+        let (mut agg, mut rev_agg) = (self.val.clone(), self.val.clone());
+        if let Some(left) = self.left.as_ref() {
+            agg = M::combine(&left.agg, &agg);
+            rev_agg = M::combine(&rev_agg, &left.rev_agg);
+        }
+        if let Some(right) = self.right.as_ref() {
+            agg = M::combine(&agg, &right.agg);
+            rev_agg = M::combine(&right.rev_agg, &rev_agg);
+        }
+        self.agg = agg;
+        self.rev_agg = rev_agg;
+    }
+}
+
+impl<M: LazyMonoid> Node<M> {
+    fn new(val: M::Item) -> Node<M> {
+        Node {
+            agg: val.clone(),
+            rev_agg: val.clone(),
+            val,
+            tag: None,
+            rev: false,
+            n: 1,
+            left: None,
+            right: None,
+        }
+    }
+
+    fn apply(&mut self, tag: &M::Tag) {
+        self.val = M::apply(tag, &self.val, 1);
+        self.agg = M::apply(tag, &self.agg, self.n);
+        self.rev_agg = M::apply(tag, &self.rev_agg, self.n);
+        self.tag = Some(match self.tag.take() {
+            Some(inner) => M::compose(tag, &inner),
+            None => tag.clone(),
+        });
+    }
+
+    fn reverse(&mut self) {
+        mem::swap(&mut self.left, &mut self.right);
+        mem::swap(&mut self.agg, &mut self.rev_agg);
+        self.rev = !self.rev;
+    }
+
+    // hand the pending tag and reversal down to the children
+    fn push(&mut self) {
+        let tag = self.tag.take();
+        let rev = mem::replace(&mut self.rev, false);
+        for child in [&mut self.left, &mut self.right].iter_mut() {
+            if let Some(child) = child.as_mut() {
+                if let Some(tag) = tag.as_ref() {
+                    child.apply(tag);
+                }
+                if rev {
+                    child.reverse();
+                }
+            }
+        }
+    }
+
+    // splay the k-th node of the subtree to its root, top-down so that a
+    // degenerate tree does not run the stack out
+    fn splay(h: NodeCell<M>, mut k: usize) -> NodeCell<M> {
+        let mut t = h?;
+        // nodes hung off the left and the right tree, from the top down, each
+        // missing the child on the search path
+        let mut lefts = Vec::new();
+        let mut rights = Vec::new();
+        loop {
+            t.push();
+            let s = size(&t.left);
+            if k < s {
+                let l = t.left.as_mut().unwrap();
+                l.push();
+                if k < size(&l.left) {
+                    // zig-zig, rotate before linking
+                    t = rotate_right(Some(t)).unwrap();
+                }
+                let next = t.left.take().unwrap();
+                rights.push(t);
+                t = next;
+            } else if k > s {
+                let r = t.right.as_mut().unwrap();
+                r.push();
+                if k - s - 1 > size(&r.left) {
+                    // zig-zig, rotate before linking
+                    t = rotate_left(Some(t)).unwrap();
+                }
+                k -= size(&t.left) + 1;
+                let next = t.right.take().unwrap();
+                lefts.push(t);
+                t = next;
+            } else {
+                break;
+            }
+        }
+        // reassemble bottom up: the left tree hangs off the right spine of
+        // its nodes, the right tree off the left spine
+        let mut left = t.left.take();
+        while let Some(mut x) = lefts.pop() {
+            x.right = left;
+            x.pull();
+            left = Some(x);
+        }
+        let mut right = t.right.take();
+        while let Some(mut x) = rights.pop() {
+            x.left = right;
+            x.pull();
+            right = Some(x);
+        }
+        t.left = left;
+        t.right = right;
+        t.pull();
+        Some(t)
+    }
+
+    // the first k nodes of x, and the rest
+    fn split(x: NodeCell<M>, k: usize) -> (NodeCell<M>, NodeCell<M>) {
+        if k == 0 {
+            return (None, x);
+        }
+        let mut h = Node::splay(x, k - 1).unwrap();
+        let right = h.right.take();
+        h.pull();
+        (Some(h), right)
+    }
+
+    // a followed by b
+    fn merge(a: NodeCell<M>, b: NodeCell<M>) -> NodeCell<M> {
+        let n = size(&a);
+        if n == 0 {
+            return b;
+        }
+        let mut h = Node::splay(a, n - 1).unwrap();
+        h.right = b;
+        h.pull();
+        Some(h)
+    }
+
+    // balanced subtree of the values
+    fn build(vals: &mut dyn Iterator<Item = M::Item>, n: usize) -> NodeCell<M> {
+        if n == 0 {
+            return None;
+        }
+        let left = Node::build(vals, n / 2);
+        let mut h = Box::new(Node::new(vals.next().unwrap()));
+        h.left = left;
+        h.right = Node::build(vals, n - n / 2 - 1);
+        h.pull();
+        Some(h)
+    }
+
+    // apply every pending tag and reversal in the subtree
+    fn push_all(&mut self) {
+        let mut stack = vec![self];
+        while let Some(x) = stack.pop() {
+            x.push();
+            stack.extend(x.left.as_deref_mut());
+            stack.extend(x.right.as_deref_mut());
+        }
+    }
+}
+
+/// A sequence kept in a splay tree ordered by position instead of by key.
+///
+/// Inserting and removing at any position, reversing a range, aggregating a
+/// range with the monoid `M` and updating a range with one of its tags all
+/// take O(log n) amortized: the range is cut out by splaying its ends, worked
+/// on at the root of its own subtree, and joined back.
+pub struct SplaySequence<M: LazyMonoid> {
+    root: NodeCell<M>,
+}
+
+impl<M: LazyMonoid> Drop for SplaySequence<M> {
+    // take the tree apart node by node, a long spine would overflow the
+    // stack if dropped recursively
+    fn drop(&mut self) {
+        let mut stack = self.root.take().into_iter().collect::<Vec<_>>();
+        while let Some(mut x) = stack.pop() {
+            stack.extend(x.left.take());
+            stack.extend(x.right.take());
+        }
+    }
+}
+
+impl<M: LazyMonoid> Default for SplaySequence<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: LazyMonoid> SplaySequence<M> {
+    pub fn new() -> SplaySequence<M> {
+        SplaySequence { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// element at position i, splayed to the root
+    pub fn get(&mut self, i: usize) -> Option<&M::Item> {
+        if i >= self.len() {
+            return None;
+        }
+        self.root = Node::splay(self.root.take(), i);
+        self.root.as_ref().map(|n| &n.val)
+    }
+
+    /// replace the element at position i
+    pub fn set(&mut self, i: usize, val: M::Item) {
+        assert!(i < self.len(), "index out of bounds");
+        self.root = Node::splay(self.root.take(), i);
+        let root = self.root.as_mut().unwrap();
+        root.val = val;
+        root.pull();
+    }
+
+    /// insert val at position i, moving the elements after it back
+    pub fn insert(&mut self, i: usize, val: M::Item) {
+        assert!(i <= self.len(), "index out of bounds");
+        let (left, right) = Node::split(self.root.take(), i);
+        let mut h = Box::new(Node::new(val));
+        h.left = left;
+        h.right = right;
+        h.pull();
+        self.root = Some(h);
+    }
+
+    pub fn push(&mut self, val: M::Item) {
+        self.insert(self.len(), val);
+    }
+
+    /// remove the element at position i
+    pub fn remove(&mut self, i: usize) -> Option<M::Item> {
+        if i >= self.len() {
+            return None;
+        }
+        let mut h = Node::splay(self.root.take(), i).unwrap();
+        self.root = Node::merge(h.left.take(), h.right.take());
+        Some(h.val)
+    }
+
+    // work on the subtree holding the range, then join it back
+    fn with_range<R, F, T>(&mut self, range: R, f: F) -> T
+    where
+        R: RangeBounds<usize>,
+        F: FnOnce(&mut NodeCell<M>) -> T,
+    {
+        let lo = match range.start_bound() {
+            Bound::Included(&lo) => lo,
+            Bound::Excluded(&lo) => lo + 1,
+            Bound::Unbounded => 0,
+        };
+        let hi = match range.end_bound() {
+            Bound::Included(&hi) => hi + 1,
+            Bound::Excluded(&hi) => hi,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(lo <= hi && hi <= self.len(), "range out of bounds");
+        let (left, rest) = Node::split(self.root.take(), lo);
+        let (mut mid, right) = Node::split(rest, hi - lo);
+        let result = f(&mut mid);
+        self.root = Node::merge(Node::merge(left, mid), right);
+        result
+    }
+
+    /// aggregate of the elements in the range, the identity if it is empty
+    pub fn query<R: RangeBounds<usize>>(&mut self, range: R) -> M::Item {
+        self.with_range(range, |x| x.as_ref().map_or_else(M::identity, |n| n.agg.clone()))
+    }
+
+    /// apply tag to every element in the range
+    pub fn update<R: RangeBounds<usize>>(&mut self, range: R, tag: &M::Tag) {
+        self.with_range(range, |x| {
+            if let Some(n) = x.as_mut() {
+                n.apply(tag);
+            }
+        })
+    }
+
+    /// reverse the order of the elements in the range
+    pub fn reverse<R: RangeBounds<usize>>(&mut self, range: R) {
+        self.with_range(range, |x| {
+            if let Some(n) = x.as_mut() {
+                n.reverse();
+            }
+        })
+    }
+
+    /// split off the elements from position at on into a new sequence
+    pub fn split_off(&mut self, at: usize) -> SplaySequence<M> {
+        assert!(at <= self.len(), "index out of bounds");
+        let (left, right) = Node::split(self.root.take(), at);
+        self.root = left;
+        SplaySequence { root: right }
+    }
+
+    /// move the elements of other to the end of the sequence
+    pub fn append(&mut self, other: &mut SplaySequence<M>) {
+        self.root = Node::merge(self.root.take(), other.root.take());
+    }
+
+    /// iterator over the elements in order, applying the pending updates first
+    pub fn iter(&mut self) -> Iter<'_, M> {
+        if let Some(root) = self.root.as_mut() {
+            root.push_all();
+        }
+        let mut iter = Iter {
+            stack: Vec::new(),
+            len: self.len(),
+        };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+}
+
+impl<M: LazyMonoid> FromIterator<M::Item> for SplaySequence<M> {
+    fn from_iter<I: IntoIterator<Item = M::Item>>(iter: I) -> Self {
+        let vals = iter.into_iter().collect::<Vec<_>>();
+        let n = vals.len();
+        SplaySequence {
+            root: Node::build(&mut vals.into_iter(), n),
+        }
+    }
+}
+
+/// Iterator over the elements of a `SplaySequence` in order.
+pub struct Iter<'a, M: LazyMonoid> {
+    stack: Vec<&'a Node<M>>,
+    len: usize,
+}
+
+impl<'a, M: LazyMonoid> Iter<'a, M> {
+    fn push_left(&mut self, mut x: Option<&'a Node<M>>) {
+        while let Some(node) = x {
+            self.stack.push(node);
+            x = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, M: LazyMonoid> Iterator for Iter<'a, M> {
+    type Item = &'a M::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        self.len -= 1;
+        Some(&node.val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<M: LazyMonoid> ExactSizeIterator for Iter<'_, M> {}
+
+#[test]
+fn test_splay_sequence() {
+    let mut s = (1..=10).collect::<SplaySequence<RangeSum>>();
+    assert_eq!(s.len(), 10);
+    assert_eq!(s.query(..), 55);
+    assert_eq!(s.query(2..5), 3 + 4 + 5);
+    assert_eq!(s.query(3..3), 0);
+
+    s.reverse(0..4);
+    assert_eq!(s.iter().cloned().collect::<Vec<_>>(), vec![4, 3, 2, 1, 5, 6, 7, 8, 9, 10]);
+    s.update(3..=5, &10);
+    assert_eq!(s.get(4), Some(&15));
+    assert_eq!(s.query(..4), 4 + 3 + 2 + 11);
+
+    s.insert(0, 100);
+    s.push(-1);
+    assert_eq!(s.remove(5), Some(15));
+    s.set(1, 40);
+    assert_eq!(s.iter().cloned().collect::<Vec<_>>(), vec![100, 40, 3, 2, 11, 16, 7, 8, 9, 10, -1]);
+
+    let mut tail = s.split_off(6);
+    assert_eq!(s.query(..), 100 + 40 + 3 + 2 + 11 + 16);
+    tail.reverse(..);
+    s.append(&mut tail);
+    assert!(tail.is_empty());
+    assert_eq!(s.iter().cloned().collect::<Vec<_>>(), vec![100, 40, 3, 2, 11, 16, -1, 10, 9, 8, 7]);
+
+    let mut m = vec![5, 1, 4, 2, 3].into_iter().collect::<SplaySequence<RangeMin>>();
+    m.update(1..3, &5);
+    assert_eq!(m.query(..), 2);
+    assert_eq!(m.query(1..3), 6);
+    let mut m = vec![5, 1, 4, 2, 3].into_iter().collect::<SplaySequence<RangeMax>>();
+    m.reverse(1..);
+    assert_eq!(m.query(1..3), 3);
+    assert_eq!(m.query(..), 5);
+}
+
+#[test]
+fn test_splay_sequence_order() {
+    // concatenation does not commute, so reversals must reverse aggregates too
+    struct Concat;
+
+    impl Monoid for Concat {
+        type Item = String;
+
+        fn identity() -> String {
+            String::new()
+        }
+
+        fn combine(a: &String, b: &String) -> String {
+            a.clone() + b
+        }
+    }
+
+    impl LazyMonoid for Concat {
+        type Tag = ();
+
+        fn apply(_: &(), x: &String, _: usize) -> String {
+            x.clone()
+        }
+
+        fn compose(_: &(), _: &()) {}
+    }
+
+    let mut s = "abcdefg".chars().map(String::from).collect::<SplaySequence<Concat>>();
+    s.reverse(1..5);
+    assert_eq!(s.query(..), "aedcbfg");
+    s.reverse(..4);
+    assert_eq!(s.query(2..6), "eabf");
+    assert_eq!(s.query(..), "cdeabfg");
+
+    // appending leaves a long spine, which must not be walked recursively
+    let mut s = SplaySequence::<RangeSum>::new();
+    for i in 0..1_000_000 {
+        s.push(i);
+    }
+    assert_eq!(s.get(0), Some(&0));
+    assert_eq!(s.query(..), 999_999 * 1_000_000 / 2);
+    let mut s = SplaySequence::<RangeMax>::new();
+    for i in 0..1_000_000 {
+        s.push(i);
+    }
+    assert_eq!(s.len(), 1_000_000);
+}
+
+#[test]
+fn quicktest_splay_sequence() {
+    // operations on positions taken modulo the length, checked against a vector
+    fn prop(init: Vec<i8>, ops: Vec<(u8, u8, u8, i8)>) -> bool {
+        let mut s = init.iter().map(|&x| x as i64).collect::<SplaySequence<RangeSum>>();
+        let mut v = init.iter().map(|&x| x as i64).collect::<Vec<_>>();
+        for &(op, a, b, x) in ops.iter() {
+            let n = v.len();
+            let (lo, hi) = ((a as usize).min(b as usize) % (n + 1), (a as usize).max(b as usize) % (n + 1));
+            let (lo, hi) = (lo.min(hi), lo.max(hi));
+            match op % 5 {
+                0 => {
+                    s.insert(lo, x as i64);
+                    v.insert(lo, x as i64);
+                }
+                1 if lo < n => {
+                    if s.remove(lo) != Some(v.remove(lo)) {
+                        return false;
+                    }
+                }
+                2 => {
+                    s.reverse(lo..hi);
+                    v[lo..hi].reverse();
+                }
+                3 => {
+                    s.update(lo..hi, &(x as i64));
+                    v[lo..hi].iter_mut().for_each(|e| *e += x as i64);
+                }
+                _ => {
+                    if s.query(lo..hi) != v[lo..hi].iter().sum::<i64>() {
+                        return false;
+                    }
+                }
+            }
+        }
+        s.len() == v.len() && s.iter().eq(v.iter())
+    }
+
+    quickcheck(prop as fn(Vec<i8>, Vec<(u8, u8, u8, i8)>) -> bool);
+}
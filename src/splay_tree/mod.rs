@@ -0,0 +1,620 @@
+use std::cmp;
+use std::fmt;
+use std::iter;
+use std::ops::{Bound, RangeBounds};
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+pub mod implicit;
+
+// reexports
+pub use self::implicit::{LazyMonoid, Monoid, RangeMax, RangeMin, RangeSum, SplaySequence};
+
+// the links a rotation rewires, and the bookkeeping to redo afterwards
+trait Rotate: Sized {
+    fn left(&mut self) -> &mut Option<Box<Self>>;
+    fn right(&mut self) -> &mut Option<Box<Self>>;
+
+    // recompute whatever the node keeps about its subtree
+    fn pull(&mut self) {}
+}
+
+fn rotate_right<N: Rotate>(mut h: Option<Box<N>>) -> Option<Box<N>> {
+    let mut x = h.as_mut().and_then(|n| n.left().take());
+    if let Some(n) = h.as_mut() {
+        *n.left() = x.as_mut().and_then(|n| n.right().take());
+        n.pull();
+    }
+    if let Some(n) = x.as_mut() {
+        *n.right() = h;
+        n.pull();
+    }
+    x
+}
+
+fn rotate_left<N: Rotate>(mut h: Option<Box<N>>) -> Option<Box<N>> {
+    let mut x = h.as_mut().and_then(|n| n.right().take());
+    if let Some(n) = h.as_mut() {
+        *n.right() = x.as_mut().and_then(|n| n.left().take());
+        n.pull();
+    }
+    if let Some(n) = x.as_mut() {
+        *n.left() = h;
+        n.pull();
+    }
+    x
+}
+
+fn compare<T: PartialOrd>(a: &T, b: &T) -> i32 {
+    match a.partial_cmp(b).unwrap() {
+        cmp::Ordering::Greater => 1,
+        cmp::Ordering::Less => -1,
+        _ => 0,
+    }
+}
+
+pub type NodeCell<K, V> = Option<Box<Node<K, V>>>;
+
+pub struct Node<K, V> {
+    left: NodeCell<K, V>,
+    right: NodeCell<K, V>,
+    key: K,
+    val: V,
+}
+
+impl<K, V> Rotate for Node<K, V> {
+    fn left(&mut self) -> &mut NodeCell<K, V> {
+        &mut self.left
+    }
+
+    fn right(&mut self) -> &mut NodeCell<K, V> {
+        &mut self.right
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> Node<K, V> {
+    fn dump(&self, depth: usize, f: &mut fmt::Formatter, symbol: char) {
+        if depth == 0 {
+            writeln!(f, "\n{:?}[{:?}]", self.key, self.val).unwrap();
+        } else {
+            writeln!(
+                f,
+                "{}{}--{:?}[{:?}]",
+                iter::repeat("|  ").take(depth - 1).collect::<Vec<&str>>().concat(),
+                symbol,
+                self.key,
+                self.val
+            )
+            .unwrap();
+        }
+        if self.left.is_some() {
+            self.left.as_ref().unwrap().dump(depth + 1, f, '+');
+        }
+        if self.right.is_some() {
+            self.right.as_ref().unwrap().dump(depth + 1, f, '`');
+        }
+    }
+}
+
+impl<K: PartialOrd, V> Node<K, V> {
+    fn new(key: K, val: V) -> Node<K, V> {
+        Node {
+            left: None,
+            right: None,
+            key,
+            val,
+        }
+    }
+
+    fn height(x: Option<&Node<K, V>>) -> usize {
+        if let Some(node) = x {
+            let lh = Node::height(node.left.as_deref());
+            let rh = Node::height(node.left.as_deref());
+            if lh <= rh {
+                rh + 1
+            } else {
+                lh + 1
+            }
+        } else {
+            0
+        }
+    }
+
+    fn size(x: Option<&Node<K, V>>) -> usize {
+        if let Some(node) = x {
+            1 + Node::size(node.left.as_deref()) + Node::size(node.right.as_deref())
+        } else {
+            0
+        }
+    }
+
+    fn splay(mut h: NodeCell<K, V>, key: &K) -> NodeCell<K, V> {
+        h.as_ref()?;
+        let cmp1 = h.as_ref().map(|n| compare(key, &n.key)).unwrap();
+        match cmp1.cmp(&0) {
+            cmp::Ordering::Less => {
+                // key not in tree, done
+                if h.as_ref().unwrap().left.is_none() {
+                    return h;
+                }
+                let cmp2 = compare(key, &h.as_ref().unwrap().left.as_ref().unwrap().key);
+                match cmp2.cmp(&0) {
+                    cmp::Ordering::Less => {
+                        h.as_mut().map(|n| {
+                            n.left.as_mut().map(|n| {
+                                n.left = Node::splay(n.left.take(), key);
+                            })
+                        });
+                        h = rotate_right(h);
+                    }
+                    cmp::Ordering::Greater => {
+                        if let Some(ref mut n) = h.as_mut() {
+                            let f = |n: &mut Box<Node<K, V>>| {
+                                n.right = Node::splay(n.right.take(), key);
+                                n.right.is_some()
+                            };
+                            if n.left.as_mut().map_or(false, f) {
+                                n.left = rotate_left(n.left.take());
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                // key not in tree
+                if h.as_ref().unwrap().left.is_none() {
+                    return h;
+                } else {
+                    return rotate_right(h);
+                }
+            }
+            cmp::Ordering::Greater => {
+                // key not in tree, done
+                if h.as_ref().unwrap().right.is_none() {
+                    return h;
+                }
+                let cmp2 = compare(key, &h.as_ref().unwrap().right.as_ref().unwrap().key);
+                match cmp2.cmp(&0) {
+                    cmp::Ordering::Less => {
+                        if let Some(n) = h.as_mut() {
+                            let f = |n: &mut Box<Node<K, V>>| {
+                                n.left = Node::splay(n.left.take(), key);
+                                n.left.is_some()
+                            };
+                            if n.right.as_mut().map_or(false, f) {
+                                n.right = rotate_right(n.right.take());
+                            }
+                        }
+                    }
+                    cmp::Ordering::Greater => {
+                        h.as_mut().map(|n| {
+                            n.right.as_mut().map(|n| {
+                                n.right = Node::splay(n.right.take(), key);
+                            })
+                        });
+                        h = rotate_left(h);
+                    }
+                    _ => {}
+                }
+                // key not in tree
+                if h.as_ref().unwrap().right.is_none() {
+                    return h;
+                } else {
+                    return rotate_left(h);
+                }
+            }
+            _ => {}
+        }
+        h
+    }
+
+    // splay the smallest node to the root
+    fn splay_min(mut h: NodeCell<K, V>) -> NodeCell<K, V> {
+        match h.as_ref()?.left.as_ref().map(|l| l.left.is_some()) {
+            None => h,
+            Some(false) => rotate_right(h),
+            Some(true) => {
+                // zig-zig
+                if let Some(l) = h.as_mut().unwrap().left.as_mut() {
+                    l.left = Node::splay_min(l.left.take());
+                }
+                rotate_right(rotate_right(h))
+            }
+        }
+    }
+
+    // splay the largest node to the root
+    fn splay_max(mut h: NodeCell<K, V>) -> NodeCell<K, V> {
+        match h.as_ref()?.right.as_ref().map(|r| r.right.is_some()) {
+            None => h,
+            Some(false) => rotate_left(h),
+            Some(true) => {
+                // zig-zig
+                if let Some(r) = h.as_mut().unwrap().right.as_mut() {
+                    r.right = Node::splay_max(r.right.take());
+                }
+                rotate_left(rotate_left(h))
+            }
+        }
+    }
+}
+
+/// Splay tree. Supports splay-insert, -search, and -delete.
+pub struct SplayTree<K, V> {
+    root: NodeCell<K, V>,
+    // size: usize
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SplayTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.root.is_none() {
+            write!(f, "<empty tree>")
+        } else {
+            self.root.as_ref().unwrap().dump(0, f, ' ');
+            Ok(())
+        }
+    }
+}
+
+impl<K: PartialOrd, V> Default for SplayTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd, V> SplayTree<K, V> {
+    pub fn new() -> SplayTree<K, V> {
+        SplayTree {
+            root: None,
+            // size: 0
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        Node::size(self.root.as_deref())
+    }
+
+    pub fn height(&self) -> usize {
+        Node::height(self.root.as_deref())
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    // get() needs to update tree structure
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.root = Node::splay(self.root.take(), key);
+        self.root
+            .as_ref()
+            .and_then(|n| if n.key == *key { Some(&n.val) } else { None })
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get_mut<'t>(&'t mut self, key: &K) -> Option<&'t mut V> {
+        self.root = Node::splay(self.root.take(), key);
+        self.root
+            .as_mut()
+            .and_then(|n| if n.key == *key { Some(&mut n.val) } else { None })
+    }
+
+    /// Splay tree insertion.
+    pub fn insert(&mut self, key: K, val: V) {
+        if self.root.is_none() {
+            self.root = Some(Box::new(Node::new(key, val)));
+            return;
+        }
+
+        let mut root = Node::splay(self.root.take(), &key);
+
+        let cmp = compare(&key, &root.as_ref().unwrap().key);
+        match cmp.cmp(&0) {
+            std::cmp::Ordering::Less => {
+                let mut n = Node::new(key, val);
+                n.left = root.as_mut().unwrap().left.take();
+                n.right = root;
+                self.root = Some(Box::new(n));
+            }
+            std::cmp::Ordering::Greater => {
+                let mut n = Node::new(key, val);
+                n.right = root.as_mut().unwrap().right.take();
+                n.left = root;
+                self.root = Some(Box::new(n));
+            }
+            std::cmp::Ordering::Equal => {
+                if let Some(n) = root.as_mut() {
+                    n.val = val;
+                }
+                self.root = root;
+            }
+        }
+    }
+
+    /// Splay tree deletion.
+    // use Algs4 approach
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.root.as_ref()?;
+
+        let mut root = Node::splay(self.root.take(), key);
+
+        if *key == root.as_ref().unwrap().key {
+            if root.as_ref().unwrap().left.is_none() {
+                self.root = root.as_mut().unwrap().right.take();
+            } else {
+                let x = root.as_mut().unwrap().right.take();
+                self.root = Node::splay(root.as_mut().unwrap().left.take(), key);
+                if let Some(n) = self.root.as_mut() {
+                    n.right = x;
+                }
+            }
+            root.map(|n| n.val)
+        } else {
+            self.root = root;
+            None
+        }
+    }
+
+    /// smallest key, splayed to the root
+    pub fn min(&mut self) -> Option<&K> {
+        self.root = Node::splay_min(self.root.take());
+        self.root.as_ref().map(|n| &n.key)
+    }
+
+    /// largest key, splayed to the root
+    pub fn max(&mut self) -> Option<&K> {
+        self.root = Node::splay_max(self.root.take());
+        self.root.as_ref().map(|n| &n.key)
+    }
+
+    /// largest key less than or equal to key
+    pub fn floor(&mut self, key: &K) -> Option<&K> {
+        self.root = Node::splay(self.root.take(), key);
+        let root = self.root.as_mut()?;
+        if root.key <= *key {
+            return Some(&root.key);
+        }
+        // the root is the successor, so the floor is the largest key on its left
+        root.left = Node::splay_max(root.left.take());
+        root.left.as_ref().map(|n| &n.key)
+    }
+
+    /// smallest key greater than or equal to key
+    pub fn ceiling(&mut self, key: &K) -> Option<&K> {
+        self.root = Node::splay(self.root.take(), key);
+        let root = self.root.as_mut()?;
+        if root.key >= *key {
+            return Some(&root.key);
+        }
+        // the root is the predecessor, so the ceiling is the smallest key on its right
+        root.right = Node::splay_min(root.right.take());
+        root.right.as_ref().map(|n| &n.key)
+    }
+
+    /// iterator over the entries in key order, leaves the tree as it is
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            last: None,
+        };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// iterator over the entries with keys in the range, in key order
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        let after_start = |key: &K| match range.start_bound() {
+            Bound::Included(lo) => key >= lo,
+            Bound::Excluded(lo) => key > lo,
+            Bound::Unbounded => true,
+        };
+        let before_end = |key: &K| match range.end_bound() {
+            Bound::Included(hi) => key <= hi,
+            Bound::Excluded(hi) => key < hi,
+            Bound::Unbounded => true,
+        };
+
+        let mut iter = Iter {
+            stack: Vec::new(),
+            last: None,
+        };
+        // the path to the first key in range, keeping the nodes still to visit
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if after_start(&node.key) {
+                iter.stack.push(node);
+                x = node.left.as_deref();
+            } else {
+                x = node.right.as_deref();
+            }
+        }
+        // the last key in range, where the iteration stops
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            if before_end(&node.key) {
+                iter.last = Some(&node.key);
+                x = node.right.as_deref();
+            } else {
+                x = node.left.as_deref();
+            }
+        }
+        if iter.last.is_none() {
+            iter.stack.clear();
+        }
+        iter
+    }
+
+    /// split off the keys greater than or equal to key into a new tree
+    pub fn split(&mut self, key: &K) -> SplayTree<K, V> {
+        let mut root = match Node::splay(self.root.take(), key) {
+            None => return SplayTree::new(),
+            Some(root) => root,
+        };
+        // after the splay no key lies between the root and key
+        if root.key < *key {
+            let right = root.right.take();
+            self.root = Some(root);
+            SplayTree { root: right }
+        } else {
+            self.root = root.left.take();
+            SplayTree { root: Some(root) }
+        }
+    }
+
+    /// append a tree whose keys are all greater than the keys of this one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key ranges of the two trees overlap.
+    pub fn join(&mut self, other: SplayTree<K, V>) {
+        self.root = Node::splay_max(self.root.take());
+        match self.root.as_mut() {
+            None => self.root = other.root,
+            Some(root) => {
+                if let Some(min) = other.iter().next() {
+                    assert!(root.key < *min.0, "joined trees must not overlap");
+                }
+                // the largest node has no right child
+                root.right = other.root;
+            }
+        }
+    }
+}
+
+impl<'a, K: PartialOrd, V> IntoIterator for &'a SplayTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a `SplayTree` in key order.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    // last key to visit, if the iteration stops before the end
+    last: Option<&'a K>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.stack.push(node);
+            x = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K: PartialOrd, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if self.last.is_some_and(|last| node.key > *last) {
+            self.stack.clear();
+            return None;
+        }
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.val))
+    }
+}
+
+#[test]
+fn test_splay_tree() {
+    let mut st1 = SplayTree::new();
+    assert!(st1.is_empty());
+    st1.insert(5, 5);
+    st1.insert(9, 9);
+    st1.insert(13, 13);
+    st1.insert(11, 11);
+    st1.insert(1, 1);
+
+    assert_eq!(5, st1.size());
+    assert!(st1.height() < 5);
+    for i in [5, 9, 13, 11, 1].iter() {
+        assert_eq!(st1.get(i), Some(i));
+    }
+
+    st1.get_mut(&1).map(|val| *val = 1000);
+    assert_eq!(st1.get(&1), Some(&1000));
+
+    assert!(st1.remove(&9).is_some());
+    assert!(st1.remove(&9).is_none());
+    assert!(!st1.contains_key(&9));
+}
+
+#[test]
+fn test_splay_tree_ordered() {
+    let mut t = SplayTree::new();
+    for (i, c) in "SEARCHEXAMPLE".chars().enumerate() {
+        t.insert(c, i);
+    }
+    assert_eq!(t.keys().collect::<String>(), "ACEHLMPRSX");
+    assert_eq!(t.min(), Some(&'A'));
+    assert_eq!(t.max(), Some(&'X'));
+    assert_eq!(t.floor(&'O'), Some(&'M'));
+    assert_eq!(t.ceiling(&'O'), Some(&'P'));
+    assert_eq!(t.floor(&'0'), None);
+    assert_eq!(t.ceiling(&'Z'), None);
+    assert_eq!(t.range('E'..'P').map(|(k, _)| *k).collect::<String>(), "EHLM");
+    assert_eq!(t.range('Y'..).next(), None);
+
+    let mut high = t.split(&'M');
+    assert_eq!(t.keys().collect::<String>(), "ACEHL");
+    assert_eq!(high.keys().collect::<String>(), "MPRSX");
+    high.remove(&'M');
+    t.join(high);
+    assert_eq!(t.keys().collect::<String>(), "ACEHLPRSX");
+    assert_eq!(t.get(&'X'), Some(&7));
+}
+
+#[test]
+fn quicktest_splay_tree() {
+    use std::collections::BTreeMap;
+
+    fn prop(ops: Vec<(bool, u8)>, lo: u8, hi: u8) -> bool {
+        let mut t = SplayTree::new();
+        let mut model = BTreeMap::new();
+        for &(insert, k) in ops.iter() {
+            if insert {
+                t.insert(k, ());
+                model.insert(k, ());
+            } else if t.remove(&k) != model.remove(&k) {
+                return false;
+            }
+        }
+        let (lo, hi) = (lo.min(hi), lo.max(hi));
+        if !(t.range(lo..=hi).eq(model.range(lo..=hi))
+            && t.floor(&hi).cloned() == model.range(..=hi).next_back().map(|(k, _)| *k)
+            && t.ceiling(&lo).cloned() == model.range(lo..).next().map(|(k, _)| *k)
+            && t.min().cloned() == model.keys().next().cloned()
+            && t.iter().eq(model.iter()))
+        {
+            return false;
+        }
+
+        let mut high = t.split(&lo);
+        let model_high = model.split_off(&lo);
+        let split_ok = t.iter().eq(model.iter()) && high.iter().eq(model_high.iter());
+        high.remove(&lo);
+        t.join(high);
+        split_ok && t.size() == model.len() + model_high.len() - model_high.contains_key(&lo) as usize
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8)>, u8, u8) -> bool);
+}
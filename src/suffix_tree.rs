@@ -8,6 +8,7 @@ use std::collections::BTreeMap;
 
 use vec_map::VecMap;
 
+use super::graph::GraphExporter;
 use super::{Queue, Stack};
 
 pub use self::Node::*;
@@ -463,6 +464,42 @@ impl<'a, T: Ord + Copy + fmt::Display + fmt::Debug> SuffixTree<'a, T> {
         dot.push_str("}\n");
         dot
     }
+
+    /// Configurable DOT/GraphML export. Nodes are numbered in BFS order from the root (0),
+    /// edges carry their text label and terminal nodes are colored red.
+    pub fn exporter(&self) -> GraphExporter<'_> {
+        let mut nodes = vec![&self.root];
+        let mut edges = Vec::new();
+        let mut queue = Queue::new();
+        queue.enqueue(0);
+        while !queue.is_empty() {
+            let pid = queue.dequeue().unwrap();
+            for node in nodes[pid].iter_children() {
+                let nid = nodes.len();
+                nodes.push(node);
+                edges.push((pid, nid));
+                queue.enqueue(nid);
+            }
+        }
+
+        let mut exporter = GraphExporter::new(nodes.len(), true);
+        for (v, node) in nodes.iter().enumerate() {
+            exporter.set_vertex_attr(v, "shape", "point");
+            if !node.is_root() && node.terminates_any() {
+                exporter.set_vertex_attr(v, "color", "red");
+            }
+        }
+        for (v, w) in edges {
+            let label = nodes[w]
+                .data()
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>()
+                .concat();
+            exporter.add_edge_with_attrs(v, w, &[("label", &label)]);
+        }
+        exporter
+    }
 }
 
 impl<'a, T: Ord + Copy + fmt::Display + fmt::Debug> fmt::Display for SuffixTree<'a, T> {
@@ -500,6 +537,10 @@ fn test_suffix_tree() {
     let st = SuffixTree::new(&s1);
     println!("got => {}", st);
     println!("dot =>\n{}", st.to_dot());
+
+    let dot = st.exporter().to_dot();
+    assert!(dot.contains("  0 -> 1 [label=\"#\"];\n"));
+    assert!(dot.contains("[label=\"xabcd#\"]"));
 }
 
 // #[test]
@@ -0,0 +1,101 @@
+// Model checks shared by the tests of the ordered symbol tables.
+
+use std::collections::BTreeMap;
+
+pub type Model = BTreeMap<u8, u16>;
+
+/// Symbol table operations replayed against a `BTreeMap` model.
+pub trait Table {
+    fn insert(&mut self, key: u8, val: u16) -> Option<u16>;
+    fn remove(&mut self, key: &u8) -> Option<u16>;
+    /// all entries in iteration order
+    fn entries(&self) -> Vec<(u8, u16)>;
+    fn check(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+/// Ordered queries of a table, compared with the same queries on the model.
+pub trait OrderedTable: Table {
+    fn rank(&self, key: &u8) -> usize;
+    fn select(&self, k: usize) -> Option<u8>;
+    fn floor(&self, key: &u8) -> Option<u8>;
+    fn ceiling(&self, key: &u8) -> Option<u8>;
+}
+
+/// apply `(insert, key, value)` operations to the table and a model,
+/// `None` as soon as the table returns a different old value
+pub fn replay<T: Table>(t: &mut T, model: &mut Model, ops: &[(bool, u8, u16)]) -> Option<()> {
+    for &(insert, k, v) in ops {
+        let (got, expected) = if insert {
+            (t.insert(k, v), model.insert(k, v))
+        } else {
+            (t.remove(&k), model.remove(&k))
+        };
+        if got != expected {
+            return None;
+        }
+    }
+    Some(())
+}
+
+/// the table is valid and holds exactly the entries of the model
+pub fn matches<T: Table>(t: &T, model: &Model) -> bool {
+    t.check().is_ok() && t.entries() == model.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>()
+}
+
+/// the ordered queries around `probe` agree with the model
+pub fn queries_match<T: OrderedTable>(t: &T, model: &Model, probe: u8) -> bool {
+    t.rank(&probe) == model.range(..probe).count()
+        && t.select(probe as usize) == model.keys().nth(probe as usize).cloned()
+        && t.floor(&probe) == model.range(..=probe).next_back().map(|(&k, _)| k)
+        && t.ceiling(&probe) == model.range(probe..).next().map(|(&k, _)| k)
+}
+
+/// replay `ops` on an empty table, then compare its contents and ordered queries with the model
+pub fn model_check<T: OrderedTable>(mut t: T, ops: &[(bool, u8, u16)], probe: u8) -> bool {
+    let mut model = Model::new();
+    replay(&mut t, &mut model, ops).is_some() && matches(&t, &model) && queries_match(&t, &model, probe)
+}
+
+/// Implements `Table` and `OrderedTable` for a `u8 -> u16` table whose inherent methods
+/// have the usual names and signatures.
+macro_rules! impl_ordered_table {
+    ($t:ty) => {
+        impl $crate::testing::Table for $t {
+            fn insert(&mut self, key: u8, val: u16) -> Option<u16> {
+                <$t>::insert(self, key, val)
+            }
+
+            fn remove(&mut self, key: &u8) -> Option<u16> {
+                <$t>::remove(self, key)
+            }
+
+            fn entries(&self) -> Vec<(u8, u16)> {
+                <$t>::iter(self).map(|(&k, &v)| (k, v)).collect()
+            }
+
+            fn check(&self) -> Result<(), &'static str> {
+                <$t>::check(self)
+            }
+        }
+
+        impl $crate::testing::OrderedTable for $t {
+            fn rank(&self, key: &u8) -> usize {
+                <$t>::rank(self, key)
+            }
+
+            fn select(&self, k: usize) -> Option<u8> {
+                <$t>::select(self, k).cloned()
+            }
+
+            fn floor(&self, key: &u8) -> Option<u8> {
+                <$t>::floor(self, key).cloned()
+            }
+
+            fn ceiling(&self, key: &u8) -> Option<u8> {
+                <$t>::ceiling(self, key).cloned()
+            }
+        }
+    };
+}
@@ -0,0 +1,630 @@
+use rand::{thread_rng, Rng};
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Index;
+
+#[cfg(test)]
+use quickcheck::quickcheck;
+
+pub type NodeCell<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Clone)]
+pub struct Node<K, V> {
+    pub key: K,
+    pub val: V,
+    pub left: NodeCell<K, V>,
+    pub right: NodeCell<K, V>,
+    // heap ordered, no child has a higher priority
+    priority: u64,
+    // number of nodes in the subtree
+    n: usize,
+}
+
+impl<K, V> Node<K, V> {
+    #[inline]
+    pub fn new(key: K, val: V) -> Node<K, V> {
+        Node {
+            key,
+            val,
+            left: None,
+            right: None,
+            priority: thread_rng().gen(),
+            n: 1,
+        }
+    }
+
+    #[inline]
+    fn update(&mut self) {
+        self.n = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+#[inline]
+fn size<K, V>(x: &NodeCell<K, V>) -> usize {
+    x.as_ref().map_or(0, |n| n.n)
+}
+
+// split x into the keys less than key and the keys greater than or equal to key
+fn split<K, Q, V>(x: NodeCell<K, V>, key: &Q) -> (NodeCell<K, V>, NodeCell<K, V>)
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    let mut h = match x {
+        None => return (None, None),
+        Some(h) => h,
+    };
+    if h.key.borrow() < key {
+        let (mid, right) = split(h.right.take(), key);
+        h.right = mid;
+        h.update();
+        (Some(h), right)
+    } else {
+        let (left, mid) = split(h.left.take(), key);
+        h.left = mid;
+        h.update();
+        (left, Some(h))
+    }
+}
+
+// join a and b, every key in a less than every key in b
+fn merge<K, V>(a: NodeCell<K, V>, b: NodeCell<K, V>) -> NodeCell<K, V> {
+    match (a, b) {
+        (None, x) | (x, None) => x,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority >= b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+// split x into the keys less than key, the node with key and the keys greater than key
+fn split_at<K, Q, V>(x: NodeCell<K, V>, key: &Q) -> (NodeCell<K, V>, NodeCell<K, V>, NodeCell<K, V>)
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    let mut h = match x {
+        None => return (None, None, None),
+        Some(h) => h,
+    };
+    match key.partial_cmp(h.key.borrow()).unwrap() {
+        Ordering::Less => {
+            let (left, mid, right) = split_at(h.left.take(), key);
+            h.left = right;
+            h.update();
+            (left, mid, Some(h))
+        }
+        Ordering::Greater => {
+            let (left, mid, right) = split_at(h.right.take(), key);
+            h.right = left;
+            h.update();
+            (Some(h), mid, right)
+        }
+        Ordering::Equal => {
+            let (left, right) = (h.left.take(), h.right.take());
+            h.update();
+            (left, Some(h), right)
+        }
+    }
+}
+
+// all entries of a and b, b's values win on equal keys. The root with the higher
+// priority stays on top and splits the other tree, O(m log(n / m)) expected for
+// sizes m <= n.
+fn union<K: PartialOrd, V>(a: NodeCell<K, V>, b: NodeCell<K, V>) -> NodeCell<K, V> {
+    match (a, b) {
+        (None, x) | (x, None) => x,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority >= b.priority {
+                let (left, mid, right) = split_at(Some(b), &a.key);
+                if let Some(mid) = mid {
+                    a.val = mid.val;
+                }
+                a.left = union(a.left.take(), left);
+                a.right = union(a.right.take(), right);
+                a.update();
+                Some(a)
+            } else {
+                // a's node with b's key, if any, is dropped
+                let (left, _, right) = split_at(Some(a), &b.key);
+                b.left = union(left, b.left.take());
+                b.right = union(right, b.right.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+// insert a node whose key is not in x
+fn put<K: PartialOrd, V>(x: NodeCell<K, V>, mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut h = match x {
+        None => return node,
+        Some(h) => h,
+    };
+    if node.priority > h.priority {
+        // the new node goes here, the subtree splits around it
+        let (left, right) = split(Some(h), &node.key);
+        node.left = left;
+        node.right = right;
+        node.update();
+        return node;
+    }
+    if node.key < h.key {
+        h.left = Some(put(h.left.take(), node));
+    } else {
+        h.right = Some(put(h.right.take(), node));
+    }
+    h.update();
+    h
+}
+
+fn delete<K, Q, V>(x: &mut NodeCell<K, V>, key: &Q) -> NodeCell<K, V>
+where
+    K: Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    let h = x.as_mut()?;
+    let deleted = match key.partial_cmp(h.key.borrow()).unwrap() {
+        Ordering::Less => delete(&mut h.left, key),
+        Ordering::Greater => delete(&mut h.right, key),
+        Ordering::Equal => {
+            let mut h = x.take().unwrap();
+            *x = merge(h.left.take(), h.right.take());
+            return Some(h);
+        }
+    };
+    if deleted.is_some() {
+        h.n -= 1;
+    }
+    deleted
+}
+
+/// A symbol table implemented using a treap.
+///
+/// Each node gets a random priority and the tree is kept a binary search tree
+/// by key and a heap by priority, which makes it shaped like a BST built from
+/// a random insertion order: O(log n) expected height whatever the order of
+/// the keys. `split` and `merge` cut and join whole trees in O(log n), `union`
+/// combines overlapping ones, and every node counts its subtree for rank and select.
+#[derive(Clone)]
+pub struct Treap<K, V> {
+    pub root: NodeCell<K, V>,
+}
+
+impl<K: PartialOrd, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd, V> Treap<K, V> {
+    pub fn new() -> Treap<K, V> {
+        Treap { root: None }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref();
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.val),
+            }
+        }
+        None
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut x = self.root.as_deref_mut();
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref_mut(),
+                Ordering::Greater => x = node.right.as_deref_mut(),
+                Ordering::Equal => return Some(&mut node.val),
+            }
+        }
+        None
+    }
+
+    pub fn put(&mut self, key: K, val: V) {
+        self.insert(key, val);
+    }
+
+    /// insert a key-value pair, returns the value the key had before
+    pub fn insert(&mut self, key: K, val: V) -> Option<V> {
+        if let Some(v) = self.get_mut(&key) {
+            return Some(mem::replace(v, val));
+        }
+        self.root = Some(put(self.root.take(), Box::new(Node::new(key, val))));
+        None
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.remove(key);
+    }
+
+    /// remove a key, returns its value if it was in the table
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        delete(&mut self.root, key).map(|n| n.val)
+    }
+
+    /// split into the entries with keys less than key and the rest
+    pub fn split<Q>(self, key: &Q) -> (Treap<K, V>, Treap<K, V>)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (left, right) = split(self.root, key);
+        (Treap { root: left }, Treap { root: right })
+    }
+
+    /// join with a treap whose keys are all greater than the keys of this one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key ranges of the two treaps overlap.
+    pub fn merge(self, other: Treap<K, V>) -> Treap<K, V> {
+        if let (Some(max), Some(min)) = (self.max(), other.min()) {
+            assert!(max < min, "merged treaps must not overlap");
+        }
+        Treap {
+            root: merge(self.root, other.root),
+        }
+    }
+
+    /// split off the keys greater than or equal to key into a new treap
+    pub fn split_off<Q>(&mut self, key: &Q) -> Treap<K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (left, right) = split(self.root.take(), key);
+        self.root = left;
+        Treap { root: right }
+    }
+
+    /// all entries of both treaps, other's values win on equal keys.
+    /// O(m log(n / m)) expected for sizes m <= n, unlike inserting one by one
+    pub fn union(self, other: Treap<K, V>) -> Treap<K, V> {
+        Treap {
+            root: union(self.root, other.root),
+        }
+    }
+
+    /// move all entries of other into the treap, other's values win on equal keys
+    pub fn append(&mut self, other: &mut Treap<K, V>) {
+        self.root = union(self.root.take(), other.root.take());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn size(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn height(&self) -> usize {
+        fn height<K, V>(x: &NodeCell<K, V>) -> usize {
+            x.as_ref()
+                .map_or(0, |n| 1 + cmp::max(height(&n.left), height(&n.right)))
+        }
+        height(&self.root)
+    }
+
+    /// smallest key
+    pub fn min(&self) -> Option<&K> {
+        let mut x = self.root.as_deref()?;
+        while let Some(left) = x.left.as_deref() {
+            x = left;
+        }
+        Some(&x.key)
+    }
+
+    /// largest key
+    pub fn max(&self) -> Option<&K> {
+        let mut x = self.root.as_deref()?;
+        while let Some(right) = x.right.as_deref() {
+            x = right;
+        }
+        Some(&x.key)
+    }
+
+    /// largest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (mut x, mut best) = (self.root.as_deref(), None);
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    best = Some(&node.key);
+                    x = node.right.as_deref();
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        best
+    }
+
+    /// smallest key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<&K>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (mut x, mut best) = (self.root.as_deref(), None);
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => {
+                    best = Some(&node.key);
+                    x = node.left.as_deref();
+                }
+                Ordering::Greater => x = node.right.as_deref(),
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        best
+    }
+
+    /// number of keys less than key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (mut x, mut r) = (self.root.as_deref(), 0);
+        while let Some(node) = x {
+            match key.partial_cmp(node.key.borrow()).unwrap() {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    r += 1 + size(&node.left);
+                    x = node.right.as_deref();
+                }
+                Ordering::Equal => return r + size(&node.left),
+            }
+        }
+        r
+    }
+
+    /// key of rank k
+    pub fn select(&self, k: usize) -> Option<&K> {
+        let (mut x, mut k) = (self.root.as_deref(), k);
+        while let Some(node) = x {
+            let t = size(&node.left);
+            match k.cmp(&t) {
+                Ordering::Less => x = node.left.as_deref(),
+                Ordering::Greater => {
+                    x = node.right.as_deref();
+                    k -= t + 1;
+                }
+                Ordering::Equal => return Some(&node.key),
+            }
+        }
+        None
+    }
+
+    /// iterator over the entries, in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            len: self.size(),
+        };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// check the treap invariants, naming the first one violated
+    pub fn check(&self) -> Result<(), &'static str> {
+        fn check_node<K: PartialOrd, V>(
+            x: &NodeCell<K, V>,
+            lo: Option<&K>,
+            hi: Option<&K>,
+        ) -> Result<(), &'static str> {
+            let node = match x {
+                None => return Ok(()),
+                Some(node) => node,
+            };
+            if lo.is_some_and(|lo| node.key <= *lo) || hi.is_some_and(|hi| node.key >= *hi) {
+                return Err("not in symmetric order");
+            }
+            if [&node.left, &node.right]
+                .iter()
+                .any(|c| c.as_ref().is_some_and(|c| c.priority > node.priority))
+            {
+                return Err("not in heap order");
+            }
+            if node.n != 1 + size(&node.left) + size(&node.right) {
+                return Err("subtree counts not consistent");
+            }
+            check_node(&node.left, lo, Some(&node.key))?;
+            check_node(&node.right, Some(&node.key), hi)
+        }
+
+        check_node(&self.root, None, None)
+    }
+}
+
+impl<K: PartialOrd, V> FromIterator<(K, V)> for Treap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut t = Treap::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<K: PartialOrd, V> Extend<(K, V)> for Treap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, val) in iter {
+            self.put(key, val);
+        }
+    }
+}
+
+impl<K, Q, V> Index<&Q> for Treap<K, V>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    fn index(&self, index: &Q) -> &V {
+        self.get(index).expect("key not exists")
+    }
+}
+
+/// Iterator over the entries of a `Treap` in key order.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut x: Option<&'a Node<K, V>>) {
+        while let Some(node) = x {
+            self.stack.push(node);
+            x = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K: PartialOrd, V> IntoIterator for &'a Treap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_treap() {
+    let mut t = (0..1000).map(|i| (i, i * i)).collect::<Treap<_, _>>();
+    assert_eq!(t.size(), 1000);
+    assert_eq!(t.check(), Ok(()));
+    assert!(t.height() < 60);
+    assert_eq!(t[&30], 900);
+    assert_eq!(t.insert(30, 0), Some(900));
+    assert_eq!((t.rank(&500), t.select(500)), (500, Some(&500)));
+
+    // cut out [250, 750) and glue the ends back together
+    let (low, rest) = t.split(&250);
+    let (mid, high) = rest.split(&750);
+    assert_eq!((low.size(), mid.size(), high.size()), (250, 500, 250));
+    assert_eq!((mid.min(), mid.max()), (Some(&250), Some(&749)));
+    let mut t = low.merge(high);
+    assert_eq!(t.check(), Ok(()));
+    assert_eq!((t.floor(&500), t.ceiling(&500)), (Some(&249), Some(&750)));
+    assert_eq!(t.select(250), Some(&750));
+
+    let mut high = t.split_off(&600);
+    assert_eq!((t.size(), high.size()), (250, 250));
+    high.delete(&750);
+    t.append(&mut high);
+    assert!(high.is_empty());
+    assert_eq!(t.size(), 499);
+    assert_eq!(t.check(), Ok(()));
+
+    // overlapping keys, the other side's values win
+    let evens = (0..100).step_by(2).map(|i| (i, "even")).collect::<Treap<_, _>>();
+    let mut threes = (0..100).step_by(3).map(|i| (i, "three")).collect::<Treap<_, _>>();
+    let both = evens.clone().union(threes.clone());
+    assert_eq!(both.check(), Ok(()));
+    assert_eq!(both.size(), 67);
+    assert_eq!((both[&4], both[&6], both[&9]), ("even", "three", "three"));
+    threes.append(&mut evens.clone());
+    assert_eq!((threes[&6], threes.size()), ("even", 67));
+}
+
+#[cfg(test)]
+impl_ordered_table!(Treap<u8, u16>);
+
+#[test]
+fn quicktest_treap() {
+    use crate::testing::{matches, queries_match, replay, Model};
+
+    fn prop(ops: Vec<(bool, u8, u16)>, others: Vec<(bool, u8, u16)>, probe: u8) -> bool {
+        let mut t = Treap::new();
+        let mut model = Model::new();
+        if replay(&mut t, &mut model, &ops).is_none() || !matches(&t, &model) || !queries_match(&t, &model, probe) {
+            return false;
+        }
+
+        let mut other = Treap::new();
+        let mut other_model = Model::new();
+        replay(&mut other, &mut other_model, &others).unwrap();
+        let mut union = model.clone();
+        union.extend(other_model.iter().map(|(&k, &v)| (k, v)));
+        if !matches(&t.clone().union(other), &union) {
+            return false;
+        }
+
+        let (left, right) = t.split(&probe);
+        let high = model.split_off(&probe);
+        matches(&left, &model) && matches(&right, &high) && left.merge(right).size() == model.len() + high.len()
+    }
+
+    quickcheck(prop as fn(Vec<(bool, u8, u16)>, Vec<(bool, u8, u16)>, u8) -> bool);
+}